@@ -0,0 +1,130 @@
+//! Build steps behind features:
+//!
+//! - `embed-binary` writes `$OUT_DIR/embedded_archive.rs`: the release archive named by
+//!   `CUIMP_EMBED_ARCHIVE` as `Some((file name, include_bytes!(..)))`, or `None` when the
+//!   variable is unset.
+//! - `vendored` builds curl-impersonate from source into `$OUT_DIR/vendored` and passes
+//!   its `bin` directory to the crate as `CUIMP_VENDORED_BIN_DIR`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Repository and tag built by `vendored` unless `CUIMP_VENDORED_SOURCE` or
+/// `CUIMP_VENDORED_REF` say otherwise
+const VENDORED_REPOSITORY: &str = "https://github.com/lexiforest/curl-impersonate";
+const VENDORED_REF: &str = "v1.0.0";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets OUT_DIR"));
+    if env::var_os("CARGO_FEATURE_EMBED_BINARY").is_some() {
+        embed_archive(&out_dir);
+    }
+    if env::var_os("CARGO_FEATURE_VENDORED").is_some() {
+        build_vendored(&out_dir);
+    }
+}
+
+fn embed_archive(out_dir: &Path) {
+    println!("cargo:rerun-if-env-changed=CUIMP_EMBED_ARCHIVE");
+    let archive = match env::var_os("CUIMP_EMBED_ARCHIVE").filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let path = fs::canonicalize(&path)
+                .unwrap_or_else(|e| panic!("CUIMP_EMBED_ARCHIVE={:?}: {}", path, e));
+            println!("cargo:rerun-if-changed={}", path.display());
+            let name = path
+                .file_name()
+                .expect("a canonical file path has a name")
+                .to_string_lossy()
+                .into_owned();
+            format!(
+                "Some(({:?}, include_bytes!({:?})))",
+                name,
+                path.to_string_lossy()
+            )
+        }
+        None => {
+            println!("cargo:warning=embed-binary is enabled but CUIMP_EMBED_ARCHIVE is not set; nothing is embedded");
+            "None".to_string()
+        }
+    };
+    fs::write(out_dir.join("embedded_archive.rs"), archive).expect("OUT_DIR is writable");
+}
+
+/// Configure, build and install curl-impersonate with its own build system, which needs
+/// autoconf, make, cmake, ninja, Go (for BoringSSL) and a C/C++ compiler
+fn build_vendored(out_dir: &Path) {
+    println!("cargo:rerun-if-env-changed=CUIMP_VENDORED_SOURCE");
+    println!("cargo:rerun-if-env-changed=CUIMP_VENDORED_REF");
+    if env::var("CARGO_CFG_WINDOWS").is_ok() {
+        panic!("the `vendored` feature cannot build curl-impersonate for Windows; use a release binary");
+    }
+
+    let prefix = out_dir.join("vendored");
+    let bin_dir = prefix.join("bin");
+    println!(
+        "cargo:rustc-env=CUIMP_VENDORED_BIN_DIR={}",
+        bin_dir.display()
+    );
+    if bin_dir.join("curl-impersonate").exists() {
+        return;
+    }
+
+    let source = match env::var_os("CUIMP_VENDORED_SOURCE").filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let path = fs::canonicalize(&path)
+                .unwrap_or_else(|e| panic!("CUIMP_VENDORED_SOURCE={:?}: {}", path, e));
+            println!("cargo:rerun-if-changed={}", path.display());
+            path
+        }
+        None => {
+            let checkout = out_dir.join("curl-impersonate");
+            if !checkout.join("configure").exists() {
+                let git_ref =
+                    env::var("CUIMP_VENDORED_REF").unwrap_or_else(|_| VENDORED_REF.to_string());
+                let _ = fs::remove_dir_all(&checkout);
+                run(Command::new("git")
+                    .args([
+                        "clone",
+                        "--depth",
+                        "1",
+                        "--branch",
+                        &git_ref,
+                        VENDORED_REPOSITORY,
+                    ])
+                    .arg(&checkout));
+            }
+            checkout
+        }
+    };
+
+    let build_dir = out_dir.join("vendored-build");
+    fs::create_dir_all(&build_dir).expect("OUT_DIR is writable");
+    run(Command::new(source.join("configure"))
+        .arg(format!("--prefix={}", prefix.display()))
+        .current_dir(&build_dir));
+    let jobs = env::var("NUM_JOBS").unwrap_or_else(|_| "1".to_string());
+    run(Command::new("make")
+        .args(["-j", &jobs, "build"])
+        .current_dir(&build_dir));
+    run(Command::new("make").arg("install").current_dir(&build_dir));
+}
+
+fn run(command: &mut Command) {
+    let status = command.status().unwrap_or_else(|e| {
+        panic!(
+            "vendored curl-impersonate build: cannot run {:?}: {}",
+            command, e
+        )
+    });
+    if !status.success() {
+        panic!(
+            "vendored curl-impersonate build: {:?} failed with {}. Building from source needs git, \
+             autoconf, make, cmake, ninja, Go and a C/C++ compiler; or set CUIMP_VENDORED_SOURCE \
+             to a prepared checkout.",
+            command, status
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use cuimp::{CuimpDescriptor, CuimpHttp, CuimpOptions};
+use cuimp::{Browser, CuimpDescriptor, CuimpHttp, CuimpOptions};
 use serde_json::json;
 
 #[tokio::main]
@@ -7,7 +7,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create a descriptor for Chrome 123
     let descriptor = CuimpDescriptor {
-        browser: Some("chrome".to_string()),
+        browser: Some(Browser::Chrome),
         version: Some("123".to_string()),
         ..Default::default()
     };
@@ -0,0 +1,209 @@
+//! Versioned serde representation of `CuimpResponse`.
+//!
+//! Serialized responses carry a `schema_version` and store `raw_body` as base64, so
+//! archives stay readable when the in-memory types change. Deserialization also
+//! accepts the unversioned layout written by earlier releases (`raw_body` as an
+//! array of numbers) and headers written as a multimap (`name -> [values]` or a
+//! list of `[name, value]` pairs), joining repeated values with `", "`.
+
+use crate::connection::ConnectionInfo;
+use crate::cookie::Cookie;
+use crate::retry::AttemptInfo;
+use crate::types::{CuimpResponse, Diagnostics, HttpVersion, RequestInfo};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Current `schema_version` written by `CuimpResponse`'s `Serialize` impl
+pub const RESPONSE_SCHEMA_VERSION: u32 = 1;
+
+impl<T: Serialize> Serialize for CuimpResponse<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CuimpResponse", 15)?;
+        state.serialize_field("schema_version", &RESPONSE_SCHEMA_VERSION)?;
+        state.serialize_field("status", &self.status)?;
+        state.serialize_field("status_text", &self.status_text)?;
+        state.serialize_field("http_version", &self.http_version)?;
+        state.serialize_field("headers", &self.headers)?;
+        state.serialize_field("cookies", &self.cookies)?;
+        state.serialize_field("data", &self.data)?;
+        state.serialize_field("raw_body", &STANDARD.encode(&self.raw_body))?;
+        state.serialize_field("request", &self.request)?;
+        state.serialize_field("attempts", &self.attempts)?;
+        state.serialize_field("diagnostics", &self.diagnostics)?;
+        state.serialize_field("debug_info", &self.debug_info)?;
+        state.serialize_field("connection", &self.connection)?;
+        state.serialize_field("sha256", &self.sha256)?;
+        state.serialize_field("truncated", &self.truncated)?;
+        state.end()
+    }
+}
+
+/// Any layout a response has been written in
+#[derive(Deserialize)]
+struct ResponseRecord<T> {
+    /// Absent in archives written before the schema was versioned
+    #[serde(default)]
+    schema_version: u32,
+    status: u16,
+    status_text: String,
+    #[serde(default)]
+    http_version: Option<HttpVersion>,
+    headers: HeaderRecord,
+    #[serde(default)]
+    cookies: Vec<Cookie>,
+    data: T,
+    raw_body: BodyRecord,
+    request: RequestInfo,
+    #[serde(default)]
+    attempts: Vec<AttemptInfo>,
+    #[serde(default)]
+    diagnostics: Option<Diagnostics>,
+    #[serde(default)]
+    debug_info: Option<String>,
+    #[serde(default)]
+    connection: Option<ConnectionInfo>,
+    #[serde(default)]
+    sha256: Option<String>,
+    #[serde(default)]
+    truncated: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeaderRecord {
+    Map(HashMap<String, HeaderValues>),
+    Pairs(Vec<(String, String)>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeaderValues {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BodyRecord {
+    Base64(String),
+    Bytes(Vec<u8>),
+}
+
+impl HeaderRecord {
+    fn into_map(self) -> HashMap<String, String> {
+        match self {
+            HeaderRecord::Map(map) => map
+                .into_iter()
+                .map(|(name, values)| match values {
+                    HeaderValues::One(value) => (name, value),
+                    HeaderValues::Many(values) => (name, values.join(", ")),
+                })
+                .collect(),
+            HeaderRecord::Pairs(pairs) => {
+                let mut map: HashMap<String, String> = HashMap::new();
+                for (name, value) in pairs {
+                    map.entry(name)
+                        .and_modify(|existing| {
+                            existing.push_str(", ");
+                            existing.push_str(&value);
+                        })
+                        .or_insert(value);
+                }
+                map
+            }
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for CuimpResponse<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let record = ResponseRecord::<T>::deserialize(deserializer)?;
+        if record.schema_version > RESPONSE_SCHEMA_VERSION {
+            return Err(de::Error::custom(format!(
+                "unsupported response schema_version {} (this version reads up to {})",
+                record.schema_version, RESPONSE_SCHEMA_VERSION
+            )));
+        }
+
+        let raw_body = match record.raw_body {
+            BodyRecord::Bytes(bytes) => bytes,
+            BodyRecord::Base64(text) => STANDARD
+                .decode(text)
+                .map_err(|e| de::Error::custom(format!("invalid base64 raw_body: {}", e)))?,
+        };
+
+        Ok(CuimpResponse {
+            status: record.status,
+            status_text: record.status_text,
+            http_version: record.http_version,
+            headers: record.headers.into_map(),
+            cookies: record.cookies,
+            data: record.data,
+            raw_body,
+            request: record.request,
+            attempts: record.attempts,
+            diagnostics: record.diagnostics,
+            debug_info: record.debug_info,
+            connection: record.connection,
+            sha256: record.sha256,
+            truncated: record.truncated,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_versioned_round_trip_and_legacy() {
+        let response = CuimpResponse {
+            status: 200,
+            status_text: "OK".to_string(),
+            http_version: Some(HttpVersion::Http2),
+            headers: HashMap::from([("content-type".to_string(), "text/plain".to_string())]),
+            cookies: vec![Cookie::new("session", "abc")],
+            data: Value::String("hi".to_string()),
+            raw_body: b"hi".to_vec(),
+            request: RequestInfo {
+                url: "https://example.com/".parse().unwrap(),
+                method: "GET".to_string(),
+                headers: HashMap::new(),
+                command: String::new(),
+                unicode_host: None,
+            },
+            attempts: Vec::new(),
+            diagnostics: None,
+            debug_info: None,
+            connection: None,
+            sha256: None,
+            truncated: false,
+        };
+
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["schema_version"], 1);
+        assert_eq!(value["raw_body"], "aGk=");
+        let back: CuimpResponse<Value> = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(back.raw_body, b"hi");
+        assert_eq!(back.cookies, response.cookies);
+
+        let mut legacy = value.clone();
+        legacy.as_object_mut().unwrap().remove("schema_version");
+        legacy.as_object_mut().unwrap().remove("cookies");
+        legacy["raw_body"] = json!([104, 105]);
+        legacy["headers"] = json!([["set-cookie", "a=1"], ["set-cookie", "b=2"]]);
+        let back: CuimpResponse<Value> = serde_json::from_value(legacy).unwrap();
+        assert_eq!(back.raw_body, b"hi");
+        assert_eq!(back.header("Set-Cookie"), Some("a=1, b=2"));
+
+        let mut future = value;
+        future["schema_version"] = json!(2);
+        assert!(serde_json::from_value::<CuimpResponse<Value>>(future).is_err());
+    }
+}
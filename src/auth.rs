@@ -0,0 +1,121 @@
+use crate::error::Result;
+use crate::types::CuimpResponse;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type RefreshFuture = Pin<Box<dyn Future<Output = Result<HashMap<String, String>>> + Send>>;
+
+/// Renews expired credentials when a request gets `401 Unauthorized`, then sends the
+/// request once more.
+///
+/// The callback receives the 401 response (body as bytes) and returns headers to send
+/// from then on, typically a new `Authorization` or `Cookie` after logging in again.
+/// They replace client defaults of the same name and the original request's own. An
+/// error from the callback fails the request; a second 401 is returned as it is.
+#[derive(Clone)]
+pub struct AuthRefresh(Arc<dyn Fn(CuimpResponse<Vec<u8>>) -> RefreshFuture + Send + Sync>);
+
+impl AuthRefresh {
+    pub fn new<F, Fut>(refresh: F) -> Self
+    where
+        F: Fn(CuimpResponse<Vec<u8>>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<HashMap<String, String>>> + Send + 'static,
+    {
+        AuthRefresh(Arc::new(move |response| Box::pin(refresh(response))))
+    }
+
+    pub(crate) fn refresh(&self, response: CuimpResponse<Vec<u8>>) -> RefreshFuture {
+        (self.0)(response)
+    }
+}
+
+impl fmt::Debug for AuthRefresh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AuthRefresh(..)")
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::fake_curl::FakeCurl;
+    use crate::types::{CuimpOptions, CuimpRequestConfig};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_auth_refresh() {
+        let curl = FakeCurl::new(
+            "auth",
+            "#!/bin/sh\ncase \"$*\" in\n\
+             *'Authorization: Bearer fresh'*) printf 'HTTP/1.1 200 OK\\r\\n\\r\\nok' ;;\n\
+             *) printf 'HTTP/1.1 401 Unauthorized\\r\\n\\r\\nexpired' ;;\nesac\n",
+        );
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let refresh = AuthRefresh::new(move |response: CuimpResponse<Vec<u8>>| {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                assert_eq!(response.data, b"expired");
+                let token = if call == 0 { "fresh" } else { "revoked" };
+                Ok(HashMap::from([(
+                    "Authorization".to_string(),
+                    format!("Bearer {}", token),
+                )]))
+            }
+        });
+        let mut client = curl.client(CuimpOptions {
+            on_unauthorized: Some(refresh.clone()),
+            ..Default::default()
+        });
+        let config = |authorization: &str| CuimpRequestConfig {
+            url: Some("https://portal.example.com/account".to_string()),
+            headers: Some(HashMap::from([(
+                "authorization".to_string(),
+                authorization.to_string(),
+            )])),
+            ..Default::default()
+        };
+
+        let refreshed = client.request_raw(config("Bearer stale")).await.unwrap();
+        let later = client
+            .get::<serde_json::Value>("https://portal.example.com/orders")
+            .await
+            .unwrap();
+
+        // The new token is rejected too: the 401 is returned after one refresh
+        let mut client = curl.client(CuimpOptions {
+            on_unauthorized: Some(refresh),
+            ..Default::default()
+        });
+        let rejected = client.request_raw(config("Bearer stale")).await.unwrap();
+
+        let mut client = curl.client(CuimpOptions {
+            on_unauthorized: Some(AuthRefresh::new(|_| async {
+                Ok(HashMap::from([(
+                    "Authorization".to_string(),
+                    "Bearer fresh".to_string(),
+                )]))
+            })),
+            ..Default::default()
+        });
+        let batch = client
+            .run_all::<serde_json::Value>(vec![config("Bearer stale"), config("Bearer old")], 2)
+            .await;
+
+        assert_eq!((refreshed.status, refreshed.attempts.len()), (200, 2));
+        assert_eq!(refreshed.request.headers.len(), 1);
+        assert_eq!(refreshed.request.headers["Authorization"], "Bearer fresh");
+        assert_eq!((later.status, later.attempts.len()), (200, 1));
+        assert_eq!((rejected.status, rejected.attempts.len()), (401, 2));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        for response in batch {
+            let response = response.unwrap();
+            assert_eq!((response.status, response.attempts.len()), (200, 2));
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Response parsing handles untrusted process output: never index or unwrap.
+#![deny(clippy::indexing_slicing, clippy::unwrap_used, clippy::expect_used)]
+
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::runner::run_binary_with_input;
+use crate::types::{CuimpRequestConfig, CuimpResponse};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl CuimpHttp {
+    /// Make several requests with one curl process, separated by `--next`.
+    ///
+    /// Connections are reused between the requests. Each request is prepared like
+    /// `request` (headers, cookies, proxy, ...) but is not retried, throttled by domain
+    /// profiles or recorded in a cassette. `timeout` applies per request (`--max-time`).
+    /// At most one request may carry a `body`, since bodies are passed on stdin, and all
+    /// requests must resolve to the same binary, which is run even with `Backend::Ffi`.
+    ///
+    /// The outer error covers preparing and spawning; each request's own result is
+    /// returned in order.
+    pub async fn batch<T>(
+        &mut self,
+        configs: Vec<CuimpRequestConfig>,
+    ) -> Result<Vec<Result<CuimpResponse<T>>>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if self.is_replaying() {
+            return Err(CuimpError::RequestFailed(
+                "Batched requests cannot be replayed from a cassette".to_string(),
+            ));
+        }
+        if configs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let boundary = batch_boundary();
+        let write_out = format!("\\n{}%{{exitcode}}--\\n", boundary);
+
+        let mut prepared_requests = Vec::with_capacity(configs.len());
+        for config in &configs {
+            let max_time = config
+                .timeout
+                .map(|ms| format!("{:.3}", ms as f64 / 1000.0));
+            let mut output_flags = vec!["-i", "--write-out", write_out.as_str()];
+            if let Some(max_time) = &max_time {
+                output_flags.push("--max-time");
+                output_flags.push(max_time);
+            }
+            prepared_requests.push(self.prepare(config, &output_flags).await?);
+        }
+
+        let Some(first) = prepared_requests.first() else {
+            return Ok(Vec::new());
+        };
+        let bin = first.bin.clone();
+        if prepared_requests.iter().any(|prepared| prepared.bin != bin) {
+            return Err(CuimpError::InvalidConfig(
+                "Batched requests must use the same binary (fingerprint)".to_string(),
+            ));
+        }
+        let mut stdin = None;
+        for prepared in &prepared_requests {
+            if prepared.stdin.is_some() && stdin.replace(prepared.stdin.clone()).is_some() {
+                return Err(CuimpError::InvalidConfig(
+                    "At most one batched request can send a binary body".to_string(),
+                ));
+            }
+        }
+
+        let mut args = Vec::new();
+        for (index, prepared) in prepared_requests.iter().enumerate() {
+            if index > 0 {
+                args.push("--next".to_string());
+            }
+            args.extend(prepared.args.iter().cloned());
+        }
+
+        self.emit_command(&bin, &args);
+        let result = run_binary_with_input(&bin, &args, stdin.flatten().as_deref(), None).await?;
+        let mut segments = split_batch_output(&result.stdout, &boundary).into_iter();
+
+        let mut responses = Vec::with_capacity(prepared_requests.len());
+        for prepared in &prepared_requests {
+            responses.push(match segments.next() {
+                Some((output, 0)) => self.finish(prepared, output),
+                Some((_, code)) => Err(CuimpError::RequestFailed(format!(
+                    "curl exited with code {} for {}",
+                    code, prepared.url
+                ))),
+                None => Err(CuimpError::RequestFailed(format!(
+                    "No output for batched request to {}",
+                    prepared.url
+                ))),
+            });
+        }
+        Ok(responses)
+    }
+}
+
+/// Marker curl writes after each transfer, unlikely to occur in a body
+fn batch_boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("--cuimp-batch-{:x}{:x}-", std::process::id(), nanos)
+}
+
+/// Split combined output at each `\n{boundary}{exitcode}--\n` into the transfer's
+/// output and curl's exit code for it
+fn split_batch_output<'a>(output: &'a [u8], boundary: &str) -> Vec<(&'a [u8], i32)> {
+    let marker = format!("\n{}", boundary);
+    let marker = marker.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+
+    while let Some(offset) = output.get(start..).and_then(|rest| {
+        rest.windows(marker.len())
+            .position(|window| window == marker)
+    }) {
+        let segment_end = start + offset;
+        let code_start = segment_end + marker.len();
+        let rest = output.get(code_start..).unwrap_or_default();
+        let Some(code_len) = rest.windows(3).position(|window| window == b"--\n") else {
+            break;
+        };
+        let code = std::str::from_utf8(rest.get(..code_len).unwrap_or_default())
+            .ok()
+            .and_then(|code| code.parse().ok())
+            .unwrap_or(-1);
+        segments.push((output.get(start..segment_end).unwrap_or_default(), code));
+        start = code_start + code_len + 3;
+    }
+    segments
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_batch_output() {
+        let boundary = "--cuimp-batch-1-";
+        let output = b"HTTP/1.1 200 OK\r\n\r\none\n--cuimp-batch-1-0--\n\
+            \n--cuimp-batch-1-6--\n\
+            HTTP/2 404\r\n\r\n\n--cuimp-batch-1-0--\n";
+        let segments = split_batch_output(output, boundary);
+        assert_eq!(
+            segments,
+            vec![
+                (&b"HTTP/1.1 200 OK\r\n\r\none"[..], 0),
+                (&b""[..], 6),
+                (&b"HTTP/2 404\r\n\r\n"[..], 0),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_batch_splits_responses() {
+        use crate::fake_curl::fake_client;
+        use serde_json::Value;
+
+        // Fake curl: answers every URL argument with a response and the write-out marker
+        let (mut client, _curl) = fake_client(
+            "batch",
+            "#!/bin/sh\n\
+             wo=''\n\
+             while [ $# -gt 0 ]; do\n\
+             case \"$1\" in\n\
+             --write-out) wo=\"$2\"; shift ;;\n\
+             http*) printf 'HTTP/1.1 200 OK\\r\\nContent-Type: text/plain\\r\\n\\r\\n%s' \"$1\"\n\
+             printf \"$(printf '%s' \"$wo\" | sed 's/%{exitcode}/0/')\" ;;\n\
+             esac\n\
+             shift\n\
+             done\n",
+        );
+        let configs = ["https://example.com/a", "https://example.com/b"]
+            .iter()
+            .map(|url| CuimpRequestConfig {
+                url: Some(url.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        let responses = client.batch::<Value>(configs).await.unwrap();
+
+        let bodies: Vec<Vec<u8>> = responses.into_iter().map(|r| r.unwrap().raw_body).collect();
+        assert_eq!(
+            bodies,
+            vec![
+                b"https://example.com/a".to_vec(),
+                b"https://example.com/b".to_vec()
+            ]
+        );
+    }
+}
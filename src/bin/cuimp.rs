@@ -0,0 +1,185 @@
+//! Command-line interface for cuimp
+//!
+//! ```text
+//! cuimp [-X METHOD] [-H "Name: value"]... [-d DATA] [-b BROWSER] [--proxy URL] [-i] [--output json] URL
+//! ```
+//!
+//! `--output json` prints a `ResponseEnvelope` (or `ErrorEnvelope`) as one JSON line.
+
+use cuimp::{
+    Browser, CuimpDescriptor, CuimpError, CuimpEvent, CuimpHttp, CuimpOptions, CuimpRequestConfig,
+    ErrorEnvelope, Method, ResponseEnvelope,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage: cuimp [-X METHOD] [-H \"Name: value\"]... [-d DATA] [-b BROWSER] [--proxy URL] [-i] [--output json] URL";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Body,
+    Json,
+}
+
+struct CliArgs {
+    method: Method,
+    headers: HashMap<String, String>,
+    data: Option<String>,
+    browser: Option<Browser>,
+    proxy: Option<String>,
+    include_headers: bool,
+    output: OutputFormat,
+    url: String,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<CliArgs, String> {
+    let mut method = None;
+    let mut headers = HashMap::new();
+    let mut data = None;
+    let mut browser = None;
+    let mut proxy = None;
+    let mut include_headers = false;
+    let mut output = OutputFormat::Body;
+    let mut url = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+        match arg.as_str() {
+            "-X" | "--request" => {
+                method = Some(
+                    value(&arg)?
+                        .parse()
+                        .map_err(|e: CuimpError| e.to_string())?,
+                )
+            }
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (key, val) = header
+                    .split_once(':')
+                    .ok_or_else(|| format!("Invalid header: {}", header))?;
+                headers.insert(key.trim().to_string(), val.trim().to_string());
+            }
+            "-d" | "--data" => data = Some(value(&arg)?),
+            "-b" | "--browser" => {
+                browser = Some(value(&arg)?.parse::<Browser>().map_err(|e| e.to_string())?)
+            }
+            "--proxy" => proxy = Some(value(&arg)?),
+            "-i" | "--include" => include_headers = true,
+            "-o" | "--output" => {
+                output = match value(&arg)?.as_str() {
+                    "json" => OutputFormat::Json,
+                    "body" => OutputFormat::Body,
+                    other => {
+                        return Err(format!(
+                            "Unknown output format: {} (expected json or body)",
+                            other
+                        ))
+                    }
+                }
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other if other.starts_with('-') => {
+                return Err(format!("Unknown option: {}\n{}", other, USAGE))
+            }
+            other => url = Some(other.to_string()),
+        }
+    }
+
+    let url = url.ok_or_else(|| USAGE.to_string())?;
+    let method = method.unwrap_or(if data.is_some() {
+        Method::POST
+    } else {
+        Method::GET
+    });
+
+    Ok(CliArgs {
+        method,
+        headers,
+        data,
+        browser,
+        proxy,
+        include_headers,
+        output,
+        url,
+    })
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = match parse_args(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    let options = CuimpOptions {
+        descriptor: Some(CuimpDescriptor {
+            browser: cli.browser,
+            ..Default::default()
+        }),
+        // A first run downloads the binary; say so on stderr, keeping stdout for the response
+        on_event: Some(Arc::new(|event: &CuimpEvent| match event {
+            CuimpEvent::DownloadStarted { url } => eprintln!("Downloading {}...", url),
+            CuimpEvent::DownloadWaiting { .. } => {
+                eprintln!("Waiting for another download to finish...")
+            }
+            _ => {}
+        })),
+        ..Default::default()
+    };
+
+    let mut client = match CuimpHttp::new(options) {
+        Ok(client) => client,
+        Err(e) => return report_error(&e, cli.output),
+    };
+
+    let config = CuimpRequestConfig {
+        url: Some(cli.url),
+        method: Some(cli.method),
+        headers: Some(cli.headers),
+        data: cli.data.map(Value::String),
+        proxy: cli.proxy,
+        ..Default::default()
+    };
+
+    match client.request::<Value>(config).await {
+        Ok(response) => {
+            let mut stdout = std::io::stdout().lock();
+            if cli.output == OutputFormat::Json {
+                return match ResponseEnvelope::new(&response, client.redaction()).to_json() {
+                    Ok(json) => {
+                        let _ = writeln!(stdout, "{}", json);
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => report_error(&e, cli.output),
+                };
+            }
+            if cli.include_headers {
+                let _ = writeln!(stdout, "{} {}", response.status, response.status_text);
+                for (key, value) in &response.headers {
+                    let _ = writeln!(stdout, "{}: {}", key, value);
+                }
+                let _ = writeln!(stdout);
+            }
+            let _ = stdout.write_all(&response.raw_body);
+            ExitCode::SUCCESS
+        }
+        Err(e) => report_error(&e, cli.output),
+    }
+}
+
+fn report_error(error: &CuimpError, output: OutputFormat) -> ExitCode {
+    match (output, ErrorEnvelope::new(error).to_json()) {
+        (OutputFormat::Json, Ok(json)) => println!("{}", json),
+        _ => eprintln!("Error: {}", error),
+    }
+    ExitCode::FAILURE
+}
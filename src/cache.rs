@@ -0,0 +1,372 @@
+use crate::error::Result;
+use crate::types::CuimpResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A stored response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub status: u16,
+    pub status_text: String,
+    pub headers: HashMap<String, String>,
+    #[serde(skip)]
+    pub body: Vec<u8>,
+    /// When the response was stored, in seconds since the Unix epoch
+    pub stored_at: u64,
+}
+
+impl CachedResponse {
+    /// Snapshot of a response, with the body as received
+    pub fn new<T>(response: &CuimpResponse<T>) -> Self {
+        CachedResponse {
+            url: response.request.url.to_string(),
+            status: response.status,
+            status_text: response.status_text.clone(),
+            headers: response.headers.clone(),
+            body: response.raw_body.clone(),
+            stored_at: unix_now(),
+        }
+    }
+
+    /// Value of a header, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Header names of `Vary`, lowercased; `None` for `Vary: *`
+    fn vary(&self) -> Option<Vec<String>> {
+        let mut names = Vec::new();
+        for name in self.header("vary").unwrap_or_default().split(',') {
+            match name.trim() {
+                "" => {}
+                "*" => return None,
+                name => names.push(name.to_ascii_lowercase()),
+            }
+        }
+        names.sort();
+        names.dedup();
+        Some(names)
+    }
+}
+
+/// Storage for cached responses, keyed by URL and the request headers named in the
+/// response's `Vary`. Freshness and revalidation are up to the caller.
+pub trait CacheStore {
+    /// The response stored for a request, if any
+    fn get(
+        &self,
+        url: &str,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<Option<CachedResponse>>;
+
+    /// Store a response for a request, replacing the previous one for the same variant.
+    /// Responses with `Vary: *` are not stored.
+    fn put(
+        &self,
+        url: &str,
+        request_headers: &HashMap<String, String>,
+        response: &CachedResponse,
+    ) -> Result<()>;
+
+    /// Remove every stored variant of a URL
+    fn remove(&self, url: &str) -> Result<()>;
+}
+
+/// Persistent `CacheStore`: a directory with a metadata and a body file per response.
+///
+/// Entries survive restarts. When the files exceed `max_bytes`, the least recently used
+/// entries are evicted. Files are written to a temporary name and renamed, so
+/// concurrent readers never see partial entries.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Use `dir` for the cache, creating it if needed
+    pub fn open(dir: impl Into<PathBuf>, max_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskCache { dir, max_bytes })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Total size of the stored files in bytes
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.files()?.iter().map(|file| file.size).sum())
+    }
+
+    /// Remove every entry
+    pub fn clear(&self) -> Result<()> {
+        for file in self.files()? {
+            remove_file(&file.path)?;
+        }
+        Ok(())
+    }
+
+    /// Path of an entry file: `<url hash>[-<variant hash>].<extension>`
+    fn path(&self, url: &str, variant: Option<&str>, extension: &str) -> PathBuf {
+        let name = match variant {
+            Some(variant) => format!("{:016x}-{:016x}.{}", fnv1a(url), fnv1a(variant), extension),
+            None => format!("{:016x}.{}", fnv1a(url), extension),
+        };
+        self.dir.join(name)
+    }
+
+    /// Variant of a request: the values of the `Vary` headers
+    fn variant(vary: &[String], request_headers: &HashMap<String, String>) -> String {
+        let mut variant = String::new();
+        for name in vary {
+            let value = request_headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map_or("", |(_, v)| v.trim());
+            variant.push_str(name);
+            variant.push(':');
+            variant.push_str(value);
+            variant.push('\n');
+        }
+        variant
+    }
+
+    /// Header names the stored responses of a URL vary on
+    fn stored_vary(&self, url: &str) -> Result<Option<Vec<String>>> {
+        match fs::read_to_string(self.path(url, None, "vary")) {
+            Ok(text) => Ok(Some(text.lines().map(str::to_string).collect())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Entry files (`.json` and `.body`) with their size and last use
+    fn files(&self) -> Result<Vec<CacheFile>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("json" | "body" | "vary")
+            ) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            files.push(CacheFile {
+                path,
+                size: metadata.len(),
+                used: metadata.modified().unwrap_or(UNIX_EPOCH),
+            });
+        }
+        Ok(files)
+    }
+
+    /// Remove least recently used entries until the cache fits in `max_bytes`
+    fn evict(&self) -> Result<()> {
+        let files = self.files()?;
+        let mut total: u64 = files.iter().map(|file| file.size).sum();
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        // An entry is its metadata file plus its body; `.vary` files are tiny and kept
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = files
+            .iter()
+            .filter(|file| file.path.extension().is_some_and(|e| e == "json"))
+            .map(|meta| {
+                let body = meta.path.with_extension("body");
+                let body_size = files.iter().find(|f| f.path == body).map_or(0, |f| f.size);
+                (meta.used, meta.size + body_size, meta.path.clone())
+            })
+            .collect();
+        entries.sort();
+        for (_, size, meta) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            remove_file(&meta)?;
+            remove_file(&meta.with_extension("body"))?;
+            total = total.saturating_sub(size);
+        }
+        Ok(())
+    }
+}
+
+impl CacheStore for DiskCache {
+    fn get(
+        &self,
+        url: &str,
+        request_headers: &HashMap<String, String>,
+    ) -> Result<Option<CachedResponse>> {
+        let Some(vary) = self.stored_vary(url)? else {
+            return Ok(None);
+        };
+        let variant = DiskCache::variant(&vary, request_headers);
+        let meta_path = self.path(url, Some(&variant), "json");
+        let meta = match fs::read(&meta_path) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut response: CachedResponse = serde_json::from_slice(&meta)?;
+        // Hash collision or an entry written for another URL
+        if response.url != url {
+            return Ok(None);
+        }
+        response.body = match fs::read(meta_path.with_extension("body")) {
+            Ok(body) => body,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Mark as recently used for eviction
+        if let Ok(file) = fs::File::options().append(true).open(&meta_path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Ok(Some(response))
+    }
+
+    fn put(
+        &self,
+        url: &str,
+        request_headers: &HashMap<String, String>,
+        response: &CachedResponse,
+    ) -> Result<()> {
+        let Some(vary) = response.vary() else {
+            return Ok(());
+        };
+        // Variants stored under other `Vary` names can no longer be looked up
+        if self.stored_vary(url)?.is_some_and(|stored| stored != vary) {
+            self.remove(url)?;
+        }
+
+        let variant = DiskCache::variant(&vary, request_headers);
+        let meta_path = self.path(url, Some(&variant), "json");
+        let mut stored = response.clone();
+        stored.url = url.to_string();
+        write_atomic(&meta_path.with_extension("body"), &response.body)?;
+        write_atomic(&meta_path, &serde_json::to_vec(&stored)?)?;
+        write_atomic(&self.path(url, None, "vary"), vary.join("\n").as_bytes())?;
+        self.evict()
+    }
+
+    fn remove(&self, url: &str) -> Result<()> {
+        let prefix = format!("{:016x}", fnv1a(url));
+        for file in self.files()? {
+            let is_entry = file
+                .path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix));
+            if is_entry {
+                remove_file(&file.path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct CacheFile {
+    path: PathBuf,
+    size: u64,
+    used: SystemTime,
+}
+
+/// 64-bit FNV-1a: stable across builds, unlike `DefaultHasher`, so keys survive restarts
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Remove a file that may already be gone
+fn remove_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn response(url: &str, vary: &str, body: &[u8]) -> CachedResponse {
+        CachedResponse {
+            url: url.to_string(),
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: HashMap::from([("Vary".to_string(), vary.to_string())]),
+            body: body.to_vec(),
+            stored_at: unix_now(),
+        }
+    }
+
+    #[test]
+    fn test_disk_cache_variants_and_eviction() {
+        let dir = std::env::temp_dir().join(format!("cuimp-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = DiskCache::open(&dir, 4096).unwrap();
+        let url = "https://example.com/page";
+        let en = HashMap::from([("Accept-Language".to_string(), "en".to_string())]);
+        let de = HashMap::from([("accept-language".to_string(), "de".to_string())]);
+
+        cache
+            .put(url, &en, &response(url, "Accept-Language", b"hello"))
+            .unwrap();
+        cache
+            .put(url, &de, &response(url, "Accept-Language", b"hallo"))
+            .unwrap();
+        cache.put(url, &en, &response(url, "*", b"never")).unwrap();
+
+        // Entries survive reopening the directory
+        let cache = DiskCache::open(&dir, 4096).unwrap();
+        assert_eq!(cache.get(url, &en).unwrap().unwrap().body, b"hello");
+        assert_eq!(cache.get(url, &de).unwrap().unwrap().body, b"hallo");
+        assert!(cache.get(url, &HashMap::new()).unwrap().is_none());
+        assert!(cache
+            .get("https://example.com/other", &en)
+            .unwrap()
+            .is_none());
+
+        // Each new 1 KiB entry pushes the least recently used ones out
+        for i in 0..8 {
+            let url = format!("https://example.com/{}", i);
+            cache
+                .put(&url, &en, &response(&url, "", &[b'x'; 1024]))
+                .unwrap();
+        }
+        assert!(cache.size().unwrap() <= 4096);
+        assert!(cache.get("https://example.com/7", &en).unwrap().is_some());
+        assert!(cache.get("https://example.com/0", &en).unwrap().is_none());
+
+        cache.remove("https://example.com/7").unwrap();
+        assert!(cache.get("https://example.com/7", &en).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+use crate::error::{CuimpError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// How a cassette is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Execute requests normally and append every response to the cassette file
+    Record,
+    /// Serve responses from the cassette file without spawning curl
+    Replay,
+}
+
+/// VCR-style record/replay configuration
+#[derive(Debug, Clone)]
+pub struct Cassette {
+    pub path: PathBuf,
+    pub mode: CassetteMode,
+}
+
+impl Cassette {
+    /// Record responses into `path`
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Cassette {
+            path: path.into(),
+            mode: CassetteMode::Record,
+        }
+    }
+
+    /// Replay responses from `path`
+    pub fn replay(path: impl Into<PathBuf>) -> Self {
+        Cassette {
+            path: path.into(),
+            mode: CassetteMode::Replay,
+        }
+    }
+}
+
+/// Raw curl output, stored as text when it is valid UTF-8 to keep cassettes diffable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RecordedOutput {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+    body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    output: RecordedOutput,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+/// Loaded cassette state owned by a client
+#[derive(Debug)]
+pub(crate) struct CassetteStore {
+    config: Cassette,
+    file: CassetteFile,
+    /// Number of times each interaction has been replayed
+    played: Vec<usize>,
+}
+
+impl CassetteStore {
+    pub(crate) fn open(config: Cassette) -> Result<Self> {
+        let file = match fs::read(&config.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e)
+                if e.kind() == std::io::ErrorKind::NotFound
+                    && config.mode == CassetteMode::Record =>
+            {
+                CassetteFile::default()
+            }
+            Err(e) => {
+                return Err(CuimpError::IoError(std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to open cassette {:?}: {}", config.path, e),
+                )))
+            }
+        };
+        let played = vec![0; file.interactions.len()];
+        Ok(CassetteStore {
+            config,
+            file,
+            played,
+        })
+    }
+
+    pub(crate) fn is_replay(&self) -> bool {
+        self.config.mode == CassetteMode::Replay
+    }
+
+    /// Find the recorded output for a request.
+    ///
+    /// Matching interactions are served in recording order; once all have been
+    /// played, the last one is repeated.
+    pub(crate) fn replay(
+        &mut self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        let candidates: Vec<usize> = self
+            .file
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| {
+                i.request.method == method
+                    && i.request.url == url
+                    && i.request.body.as_deref() == body
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let index = candidates
+            .iter()
+            .copied()
+            .find(|&index| self.played.get(index) == Some(&0))
+            .or_else(|| candidates.last().copied())
+            .ok_or_else(|| {
+                CuimpError::RequestFailed(format!(
+                    "No cassette entry for {} {} in {:?}",
+                    method, url, self.config.path
+                ))
+            })?;
+
+        if let Some(count) = self.played.get_mut(index) {
+            *count += 1;
+        }
+        let output = match self.file.interactions.get(index).map(|i| &i.output) {
+            Some(RecordedOutput::Text(text)) => text.as_bytes().to_vec(),
+            Some(RecordedOutput::Bytes(bytes)) => bytes.clone(),
+            None => Vec::new(),
+        };
+        Ok(output)
+    }
+
+    /// Append an interaction and persist the cassette
+    pub(crate) fn record(
+        &mut self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+        stdout: &[u8],
+    ) -> Result<()> {
+        let output = match std::str::from_utf8(stdout) {
+            Ok(text) => RecordedOutput::Text(text.to_string()),
+            Err(_) => RecordedOutput::Bytes(stdout.to_vec()),
+        };
+        self.file.interactions.push(Interaction {
+            request: RecordedRequest {
+                method: method.to_string(),
+                url: url.to_string(),
+                body: body.map(str::to_string),
+            },
+            output,
+        });
+        self.played.push(0);
+
+        if let Some(parent) = self.config.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(&self.config.path, serde_json::to_vec_pretty(&self.file)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_then_replay() {
+        let path = std::env::temp_dir().join(format!("cuimp-cassette-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut recorder = CassetteStore::open(Cassette::record(&path)).unwrap();
+        recorder
+            .record(
+                "GET",
+                "https://a.test/",
+                None,
+                b"HTTP/1.1 200 OK\r\n\r\nfirst",
+            )
+            .unwrap();
+        recorder
+            .record(
+                "GET",
+                "https://a.test/",
+                None,
+                b"HTTP/1.1 200 OK\r\n\r\n\xff",
+            )
+            .unwrap();
+
+        let mut player = CassetteStore::open(Cassette::replay(&path)).unwrap();
+        assert!(player.is_replay());
+        assert_eq!(
+            player.replay("GET", "https://a.test/", None).unwrap(),
+            b"HTTP/1.1 200 OK\r\n\r\nfirst"
+        );
+        assert_eq!(
+            player.replay("GET", "https://a.test/", None).unwrap(),
+            b"HTTP/1.1 200 OK\r\n\r\n\xff"
+        );
+        assert_eq!(
+            player.replay("GET", "https://a.test/", None).unwrap(),
+            b"HTTP/1.1 200 OK\r\n\r\n\xff"
+        );
+        assert!(player.replay("POST", "https://a.test/", None).is_err());
+
+        let _ = fs::remove_file(&path);
+    }
+}
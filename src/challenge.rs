@@ -0,0 +1,296 @@
+use crate::types::CuimpResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Bytes of the body searched for markers; challenge pages put them near the top
+const BODY_SCAN_BYTES: usize = 64 * 1024;
+
+/// Anti-bot vendor that blocked a request
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlockVendor {
+    Cloudflare,
+    Akamai,
+    PerimeterX,
+    DataDome,
+    Imperva,
+    /// Vendor of a custom `BlockSignature`
+    Other(String),
+}
+
+impl fmt::Display for BlockVendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockVendor::Cloudflare => f.write_str("Cloudflare"),
+            BlockVendor::Akamai => f.write_str("Akamai"),
+            BlockVendor::PerimeterX => f.write_str("PerimeterX"),
+            BlockVendor::DataDome => f.write_str("DataDome"),
+            BlockVendor::Imperva => f.write_str("Imperva"),
+            BlockVendor::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A response recognized as an anti-bot block or challenge page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blocked {
+    pub vendor: BlockVendor,
+    /// What the page asks for, e.g. `"JS challenge"` or `"captcha"`
+    pub hint: String,
+    pub status: u16,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}, status {})", self.vendor, self.hint, self.status)
+    }
+}
+
+/// How to recognize one kind of block page. Every condition given must match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSignature {
+    pub vendor: BlockVendor,
+    pub hint: String,
+    /// Statuses the page is served with; any status when empty
+    pub statuses: Vec<u16>,
+    /// Response header whose value contains the given text (both case-insensitive)
+    pub header: Option<(String, String)>,
+    /// Text in the first 64 KiB of the body (case-insensitive)
+    pub body_contains: Option<String>,
+}
+
+impl BlockSignature {
+    fn matches(&self, status: u16, headers: &HashMap<String, String>, body: &str) -> bool {
+        let status_matches = self.statuses.is_empty() || self.statuses.contains(&status);
+        let header_matches = self.header.as_ref().is_none_or(|(name, value)| {
+            headers.iter().any(|(k, v)| {
+                k.eq_ignore_ascii_case(name)
+                    && v.to_ascii_lowercase().contains(&value.to_ascii_lowercase())
+            })
+        });
+        let body_matches = self
+            .body_contains
+            .as_ref()
+            .is_none_or(|marker| body.contains(&marker.to_ascii_lowercase()));
+        status_matches && header_matches && body_matches
+    }
+}
+
+/// Recognizes anti-bot block and challenge pages (Cloudflare, Akamai, PerimeterX,
+/// DataDome, Imperva) so callers can rotate proxy or fingerprint instead of treating
+/// them as ordinary errors.
+///
+/// `Default` has the built-in signatures; `add` puts custom ones in front of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeDetector {
+    signatures: Vec<BlockSignature>,
+}
+
+impl Default for ChallengeDetector {
+    fn default() -> Self {
+        let signature = |vendor: BlockVendor,
+                         hint: &str,
+                         statuses: &[u16],
+                         header: Option<(&str, &str)>,
+                         body: Option<&str>| {
+            BlockSignature {
+                vendor,
+                hint: hint.to_string(),
+                statuses: statuses.to_vec(),
+                header: header.map(|(name, value)| (name.to_string(), value.to_string())),
+                body_contains: body.map(str::to_string),
+            }
+        };
+        use BlockVendor::*;
+        ChallengeDetector {
+            signatures: vec![
+                signature(
+                    Cloudflare,
+                    "JS challenge",
+                    &[],
+                    Some(("cf-mitigated", "challenge")),
+                    None,
+                ),
+                signature(
+                    Cloudflare,
+                    "JS challenge",
+                    &[403, 429, 503],
+                    Some(("server", "cloudflare")),
+                    Some("/cdn-cgi/challenge-platform/"),
+                ),
+                signature(
+                    Cloudflare,
+                    "JS challenge",
+                    &[403, 503],
+                    Some(("server", "cloudflare")),
+                    Some("<title>just a moment...</title>"),
+                ),
+                signature(
+                    Cloudflare,
+                    "access denied",
+                    &[403],
+                    Some(("server", "cloudflare")),
+                    Some("cf-error-details"),
+                ),
+                signature(
+                    Akamai,
+                    "access denied",
+                    &[403],
+                    Some(("server", "akamaighost")),
+                    Some("access denied"),
+                ),
+                signature(
+                    Akamai,
+                    "bot manager challenge",
+                    &[403, 428],
+                    None,
+                    Some("_sec/cp_challenge"),
+                ),
+                signature(PerimeterX, "captcha", &[403], None, Some("px-captcha")),
+                signature(
+                    PerimeterX,
+                    "captcha",
+                    &[403],
+                    None,
+                    Some("captcha.px-cdn.net"),
+                ),
+                signature(DataDome, "captcha", &[403], Some(("x-datadome", "")), None),
+                signature(
+                    DataDome,
+                    "captcha",
+                    &[403],
+                    None,
+                    Some("captcha-delivery.com"),
+                ),
+                signature(
+                    Imperva,
+                    "incident page",
+                    &[],
+                    None,
+                    Some("_incapsula_resource"),
+                ),
+                signature(
+                    Imperva,
+                    "incident page",
+                    &[403],
+                    None,
+                    Some("incapsula incident id"),
+                ),
+            ],
+        }
+    }
+}
+
+impl ChallengeDetector {
+    /// A detector without signatures
+    pub fn empty() -> Self {
+        ChallengeDetector {
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Recognize another kind of block page, checked before the existing signatures
+    pub fn add(&mut self, signature: BlockSignature) -> &mut Self {
+        self.signatures.insert(0, signature);
+        self
+    }
+
+    /// The block a response represents, if any
+    pub fn detect(
+        &self,
+        status: u16,
+        headers: &HashMap<String, String>,
+        body: &[u8],
+    ) -> Option<Blocked> {
+        let body = String::from_utf8_lossy(body.get(..BODY_SCAN_BYTES).unwrap_or(body))
+            .to_ascii_lowercase();
+        self.signatures
+            .iter()
+            .find(|signature| signature.matches(status, headers, &body))
+            .map(|signature| Blocked {
+                vendor: signature.vendor.clone(),
+                hint: signature.hint.clone(),
+                status,
+            })
+    }
+
+    /// `detect` for a response
+    pub fn detect_response<T>(&self, response: &CuimpResponse<T>) -> Option<Blocked> {
+        self.detect(response.status, &response.headers, &response.raw_body)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_block_pages() {
+        let detector = ChallengeDetector::default();
+        let cloudflare = detector.detect(
+            403,
+            &headers(&[("Server", "cloudflare"), ("cf-mitigated", "challenge")]),
+            b"<html><title>Just a moment...</title>",
+        );
+        assert_eq!(
+            cloudflare.map(|b| (b.vendor, b.hint)),
+            Some((BlockVendor::Cloudflare, "JS challenge".to_string()))
+        );
+
+        let akamai = detector.detect(
+            403,
+            &headers(&[("Server", "AkamaiGHost")]),
+            b"<H1>Access Denied</H1>",
+        );
+        assert_eq!(akamai.unwrap().vendor, BlockVendor::Akamai);
+        let perimeterx = detector.detect(403, &HashMap::new(), br#"<div id="px-captcha"></div>"#);
+        assert_eq!(perimeterx.unwrap().vendor, BlockVendor::PerimeterX);
+        let datadome = detector.detect(403, &headers(&[("X-DataDome", "protected")]), b"");
+        assert_eq!(
+            datadome.unwrap().to_string(),
+            "DataDome (captcha, status 403)"
+        );
+
+        // Ordinary errors and pages served through a CDN are not blocks
+        assert_eq!(
+            detector.detect(404, &headers(&[("Server", "cloudflare")]), b"Not found"),
+            None
+        );
+        assert_eq!(
+            detector.detect(
+                200,
+                &headers(&[("Server", "AkamaiGHost")]),
+                b"Access denied"
+            ),
+            None
+        );
+
+        let mut custom = ChallengeDetector::empty();
+        custom.add(BlockSignature {
+            vendor: BlockVendor::Other("Shop WAF".to_string()),
+            hint: "rate limited".to_string(),
+            statuses: vec![429],
+            header: None,
+            body_contains: Some("Slow down".to_string()),
+        });
+        assert_eq!(
+            custom
+                .detect(429, &HashMap::new(), b"please SLOW DOWN")
+                .unwrap()
+                .hint,
+            "rate limited"
+        );
+        assert_eq!(
+            custom.detect(403, &HashMap::new(), br#"id="px-captcha""#),
+            None
+        );
+    }
+}
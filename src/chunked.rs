@@ -0,0 +1,343 @@
+use crate::checksum::HashingWriter;
+use crate::client::{file_response, with_attempts, CuimpHttp};
+use crate::error::{CuimpError, Result};
+use crate::into_url::IntoUrl;
+use crate::parallel::run_attempts;
+use crate::types::{CuimpRequestConfig, CuimpResponse, Method};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::task::JoinSet;
+
+impl CuimpHttp {
+    /// Download `url` to `path` as `chunks` byte ranges requested concurrently, then joined.
+    ///
+    /// A first request for one byte learns the size. When the server answers it with
+    /// anything but `206 Partial Content`, e.g. because it ignores ranges, or the file is
+    /// empty, that answer is downloaded to `path` as with `download_to` and returned as it
+    /// is. Otherwise every
+    /// range goes to `<path>.part<n>` and must come back as exactly that range; `If-Range`
+    /// with the first answer's `ETag` or `Last-Modified` fails the download if the file
+    /// changes in between. The response is the first range's, turned into `200 OK` with
+    /// the full `Content-Length`, and lists the attempts of every request. `sha256` is
+    /// always computed, for `CuimpResponse::verify_sha256`. Part files are always removed,
+    /// and `path` too when the download fails.
+    pub async fn download_parallel(
+        &mut self,
+        url: impl IntoUrl,
+        path: impl AsRef<Path>,
+        chunks: usize,
+    ) -> Result<CuimpResponse<PathBuf>> {
+        let path = path.as_ref();
+        let config = CuimpRequestConfig {
+            url: Some(url.into_url_text()?),
+            method: Some(Method::GET),
+            compute_sha256: true,
+            ..Default::default()
+        };
+        if chunks <= 1 {
+            return self.download_to(config, path).await;
+        }
+
+        let probe = self.download_to(with_range(&config, 0, 0), path).await?;
+        if probe.status != 206 {
+            return Ok(probe);
+        }
+        let total = match probe.header("content-range").and_then(content_range) {
+            Some((_, _, Some(0))) => return Ok(probe),
+            Some((_, _, Some(total))) => total,
+            _ => {
+                let _ = std::fs::remove_file(path);
+                return Err(CuimpError::InvalidResponse(format!(
+                    "{} answered a range request without the size of the file",
+                    probe.request.url
+                )));
+            }
+        };
+        if let Err(e) = check_range(&probe, (0, 0)) {
+            let _ = std::fs::remove_file(path);
+            return Err(e);
+        }
+
+        // Ranges go straight to where a custom redirect policy led the first request, and
+        // are hashed as they are joined
+        let mut config = CuimpRequestConfig {
+            url: Some(probe.request.url.to_string()),
+            compute_sha256: false,
+            ..config
+        };
+        let validator = probe
+            .header("etag")
+            .filter(|etag| !etag.starts_with("W/"))
+            .or_else(|| probe.header("last-modified"));
+        if let Some(validator) = validator {
+            config.headers = Some(HashMap::from([(
+                "If-Range".to_string(),
+                validator.to_string(),
+            )]));
+        }
+        let size = total.div_ceil(chunks as u64);
+        let ranges: Vec<(u64, u64)> = (0..total)
+            .step_by(size as usize)
+            .map(|start| (start, (start + size).min(total) - 1))
+            .collect();
+        let parts: Vec<PathBuf> = (0..ranges.len())
+            .map(|index| {
+                let mut part = path.as_os_str().to_owned();
+                part.push(format!(".part{}", index));
+                PathBuf::from(part)
+            })
+            .collect();
+
+        let fetched = match self.fetch_ranges(&config, &ranges, &parts).await {
+            Ok(responses) => join_parts(&parts, path).map(|sha256| (responses, sha256)),
+            Err(e) => Err(e),
+        };
+        for part in &parts {
+            let _ = std::fs::remove_file(part);
+        }
+        let (responses, sha256) = fetched.inspect_err(|_| {
+            let _ = std::fs::remove_file(path);
+        })?;
+
+        let mut attempts = probe.attempts;
+        let mut first = None;
+        for response in responses {
+            attempts.extend(response.attempts.iter().cloned());
+            first.get_or_insert(response);
+        }
+        let mut response =
+            first.ok_or_else(|| CuimpError::Other("No range was requested".to_string()))?;
+        response.status = 200;
+        response.status_text = "OK".to_string();
+        response.headers.retain(|name, _| {
+            !name.eq_ignore_ascii_case("content-range")
+                && !name.eq_ignore_ascii_case("content-length")
+        });
+        response
+            .headers
+            .insert("Content-Length".to_string(), total.to_string());
+        response.data = path.to_path_buf();
+        response.attempts = attempts;
+        response.sha256 = Some(sha256);
+        Ok(response)
+    }
+
+    /// Download every range of `config` to its part file concurrently, checking that
+    /// each came back as asked
+    async fn fetch_ranges(
+        &mut self,
+        config: &CuimpRequestConfig,
+        ranges: &[(u64, u64)],
+        parts: &[PathBuf],
+    ) -> Result<Vec<CuimpResponse<PathBuf>>> {
+        let mut in_flight = JoinSet::new();
+        let started = SystemTime::now();
+        for (index, (&(start, end), part)) in ranges.iter().zip(parts).enumerate() {
+            let output = part.to_str().ok_or_else(|| {
+                CuimpError::InvalidConfig(format!(
+                    "Download path is not valid UTF-8: {}",
+                    part.display()
+                ))
+            })?;
+            let config = with_range(config, start, end);
+            let mut prepared = self
+                .prepare(
+                    &config,
+                    &["--dump-header", "-", "--output", output, "--create-dirs"],
+                )
+                .await?;
+            prepared.download = Some(part.clone());
+            let invocation = self.invocation(&prepared)?;
+            let policy = self.retry_policy(&config);
+            let fingerprint = self.fingerprint();
+            let proxy = self.attempt_proxy(&prepared);
+            let permit = self.throttle(&prepared.url).await;
+            in_flight.spawn(async move {
+                let _permit = permit;
+                let (output, attempts) = run_attempts(
+                    &invocation,
+                    policy.as_ref(),
+                    proxy.as_ref(),
+                    fingerprint.as_ref(),
+                    None,
+                )
+                .await;
+                (index, prepared, output, attempts)
+            });
+        }
+
+        let mut responses: Vec<Option<CuimpResponse<PathBuf>>> =
+            ranges.iter().map(|_| None).collect();
+        while let Some(joined) = in_flight.join_next().await {
+            let Ok((index, prepared, output, attempts)) = joined else {
+                continue;
+            };
+            let result = output.and_then(|stdout| self.finish_raw(&prepared, &stdout));
+            self.observe(&prepared, started, &attempts, &result);
+            let response = file_response(
+                with_attempts(result?, attempts),
+                &prepared.download.clone().unwrap_or_default(),
+            );
+            if let (Some(&range), Some(slot)) = (ranges.get(index), responses.get_mut(index)) {
+                check_range(&response, range)?;
+                *slot = Some(response);
+            }
+        }
+        responses
+            .into_iter()
+            .map(|response| {
+                response.ok_or_else(|| CuimpError::Other("Range request task failed".to_string()))
+            })
+            .collect()
+    }
+}
+
+/// `config` asking for bytes `start` to `end`, inclusive
+fn with_range(config: &CuimpRequestConfig, start: u64, end: u64) -> CuimpRequestConfig {
+    let mut config = config.clone();
+    config
+        .headers
+        .get_or_insert_with(HashMap::new)
+        .insert("Range".to_string(), format!("bytes={}-{}", start, end));
+    config
+}
+
+/// Start, end and total size of a `Content-Range: bytes 0-99/1234` header; the size
+/// is `None` when unknown (`*`)
+fn content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start.parse().ok()?, end.parse().ok()?, total))
+}
+
+/// Fail unless `response` holds exactly the bytes `start` to `end`
+fn check_range(response: &CuimpResponse<PathBuf>, (start, end): (u64, u64)) -> Result<()> {
+    let received = response.header("content-range").and_then(content_range);
+    let written = std::fs::metadata(&response.data)
+        .map(|metadata| metadata.len())
+        .ok();
+    match (response.status, received) {
+        (206, Some((from, to, _)))
+            if (from, to) == (start, end) && written == Some(end - start + 1) =>
+        {
+            Ok(())
+        }
+        (status, _) => Err(CuimpError::InvalidResponse(format!(
+            "Range {}-{} of {} came back as {} {}",
+            start,
+            end,
+            response.request.url,
+            status,
+            response
+                .header("content-range")
+                .unwrap_or("without Content-Range")
+        ))),
+    }
+}
+
+/// Concatenate `parts` in order into `path`, returning the SHA-256 of the whole
+fn join_parts(parts: &[PathBuf], path: &Path) -> Result<String> {
+    let mut file = HashingWriter::new(std::fs::File::create(path)?);
+    for part in parts {
+        std::io::copy(&mut std::fs::File::open(part)?, &mut file)?;
+    }
+    Ok(file.finish())
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::fake_curl::FakeCurl;
+    use crate::types::CuimpOptions;
+
+    #[tokio::test]
+    async fn test_download_parallel() {
+        // Fake curl serving 20 bytes, honouring `Range` except on /plain; /empty is an
+        // empty file and /shifted answers with the wrong range
+        let curl = FakeCurl::new(
+            "chunked",
+            "#!/bin/sh\nbody=0123456789abcdefghij\nrange=\n\
+             while [ $# -gt 0 ]; do\n\
+             case \"$1\" in\n\
+             --output) out=\"$2\"; shift ;;\n\
+             'Range: bytes='*) range=\"${1#Range: bytes=}\" ;;\n\
+             *) url=\"$1\" ;;\n\
+             esac\n\
+             shift\n\
+             done\n\
+             case \"$url\" in\n\
+             *plain*) range= ;;\n\
+             *empty*) : > \"$out\"; printf 'HTTP/1.1 206 Partial Content\\r\\nContent-Range: bytes 0-0/0\\r\\n\\r\\n'; exit 0 ;;\n\
+             *shifted*) printf 1 > \"$out\"; printf 'HTTP/1.1 206 Partial Content\\r\\nContent-Range: bytes 1-1/20\\r\\n\\r\\n'; exit 0 ;;\n\
+             esac\n\
+             if [ -z \"$range\" ]; then printf '%s' \"$body\" > \"$out\"; printf 'HTTP/1.1 200 OK\\r\\nContent-Length: 20\\r\\n\\r\\n'; exit 0; fi\n\
+             start=${range%-*}; end=${range#*-}\n\
+             printf '%s' \"$(printf '%s' \"$body\" | cut -c$((start + 1))-$((end + 1)))\" > \"$out\"\n\
+             printf 'HTTP/1.1 206 Partial Content\\r\\nETag: \"v1\"\\r\\nContent-Range: bytes %s-%s/20\\r\\n\\r\\n' $start $end\n",
+        );
+
+        let mut client = curl.client(CuimpOptions::default());
+        let target = curl.dir().join("artifact.bin");
+        let response = client
+            .download_parallel("https://example.com/artifact.bin", &target, 3)
+            .await
+            .unwrap();
+        let contents = std::fs::read_to_string(&target).unwrap();
+        let left: Vec<_> = std::fs::read_dir(curl.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        let plain = curl.dir().join("plain.bin");
+        let fallback = client
+            .download_parallel("https://example.com/plain.bin", &plain, 3)
+            .await
+            .unwrap();
+        let plain_contents = std::fs::read_to_string(&plain).unwrap();
+        let empty = curl.dir().join("empty.bin");
+        let empty = client
+            .download_parallel("https://example.com/empty.bin", &empty, 3)
+            .await
+            .unwrap();
+        let shifted = curl.dir().join("shifted.bin");
+        let rejected = client
+            .download_parallel("https://example.com/shifted.bin", &shifted, 3)
+            .await;
+        let shifted_left = shifted.exists();
+
+        assert_eq!(contents, "0123456789abcdefghij");
+        assert_eq!(
+            (response.status, response.data.as_path()),
+            (200, target.as_path())
+        );
+        assert_eq!(response.header("content-length"), Some("20"));
+        assert_eq!(response.header("content-range"), None);
+        assert_eq!(response.attempts.len(), 4);
+        assert!(response
+            .verify_sha256(&crate::checksum::sha256_hex(b"0123456789abcdefghij"))
+            .is_ok());
+        assert!(response.request.command.contains(r#"-H 'If-Range: "v1"'"#));
+        assert!(response.request.command.contains("-H 'Range: bytes=0-6'"));
+        assert_eq!(left.len(), 2, "{:?}", left);
+
+        assert_eq!((fallback.status, fallback.attempts.len()), (200, 1));
+        assert_eq!(plain_contents, "0123456789abcdefghij");
+        assert_eq!(fallback.sha256, response.sha256);
+
+        assert_eq!((empty.status, empty.attempts.len()), (206, 1));
+        assert!(matches!(rejected, Err(CuimpError::InvalidResponse(_))));
+        assert!(!shifted_left);
+    }
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(content_range("bytes 0-99/1234"), Some((0, 99, Some(1234))));
+        assert_eq!(content_range("bytes 5-9/*"), Some((5, 9, None)));
+        assert_eq!(content_range("items 0-1/2"), None);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::client::{Deadline, Invocation, PreparedRequest};
+use crate::error::{CuimpError, Result};
+use crate::parallel::run_attempts;
+use crate::retry::{AttemptInfo, RetryPolicy};
+use crate::types::Method;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// curl output of a finished request, or its error, with its attempts
+type Outcome = (
+    std::result::Result<Vec<u8>, Arc<CuimpError>>,
+    Vec<AttemptInfo>,
+);
+
+/// Identical GET requests in flight, keyed by binary, arguments and timeout
+#[derive(Debug, Default)]
+pub(crate) struct Coalescer {
+    inflight: Mutex<HashMap<String, watch::Receiver<Option<Outcome>>>>,
+}
+
+/// Role of a request in a coalesced flight
+#[derive(Debug)]
+pub(crate) enum Flight {
+    /// Runs curl and shares its output
+    Leader(Leader),
+    /// Waits for the output of the leader
+    Follower(watch::Receiver<Option<Outcome>>),
+}
+
+#[derive(Debug)]
+pub(crate) struct Leader {
+    coalescer: Arc<Coalescer>,
+    key: String,
+    sender: watch::Sender<Option<Outcome>>,
+}
+
+impl Flight {
+    pub(crate) fn is_follower(&self) -> bool {
+        matches!(self, Flight::Follower(_))
+    }
+}
+
+impl Coalescer {
+    /// Join the flight of an identical request in flight, or lead a new one.
+    /// `None` for requests that are not coalesced (anything but a GET without body,
+    /// and debug, certificate-capturing or partial requests, whose reports are their own).
+    pub(crate) fn join(self: &Arc<Self>, prepared: &PreparedRequest) -> Option<Flight> {
+        if prepared.method != Method::GET
+            || prepared.stdin.is_some()
+            || prepared.debug
+            || prepared.capture_certificates
+            || prepared.allow_partial
+        {
+            return None;
+        }
+        // Everything the run depends on: the binary, all of its arguments and the timeout
+        let mut key = format!("{:?}\0{}", prepared.timeout_ms, prepared.bin);
+        for arg in &prepared.args {
+            key.push('\0');
+            key.push_str(arg);
+        }
+
+        let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(receiver) = inflight.get(&key) {
+            return Some(Flight::Follower(receiver.clone()));
+        }
+        let (sender, receiver) = watch::channel(None);
+        inflight.insert(key.clone(), receiver);
+        Some(Flight::Leader(Leader {
+            coalescer: Arc::clone(self),
+            key,
+            sender,
+        }))
+    }
+}
+
+impl Leader {
+    fn finish(self, output: &Result<Vec<u8>>, attempts: &[AttemptInfo]) {
+        let output = match output {
+            Ok(stdout) => Ok(stdout.clone()),
+            Err(e) => Err(Arc::new(e.duplicate())),
+        };
+        self.sender.send_replace(Some((output, attempts.to_vec())));
+    }
+}
+
+/// Later requests start their own flight once the leader is done or cancelled
+impl Drop for Leader {
+    fn drop(&mut self) {
+        let mut inflight = self
+            .coalescer
+            .inflight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        inflight.remove(&self.key);
+    }
+}
+
+/// `run_attempts`, sharing the output with the other requests of the flight.
+/// A follower whose leader was cancelled runs the request itself.
+pub(crate) async fn run_flight(
+    flight: Option<Flight>,
+    invocation: &Invocation,
+    policy: Option<&RetryPolicy>,
+    proxy: Option<&String>,
+    fingerprint: Option<&String>,
+    deadline: Option<&Deadline>,
+) -> (Result<Vec<u8>>, Vec<AttemptInfo>) {
+    match flight {
+        Some(Flight::Leader(leader)) => {
+            let (output, attempts) =
+                run_attempts(invocation, policy, proxy, fingerprint, deadline).await;
+            leader.finish(&output, &attempts);
+            (output, attempts)
+        }
+        Some(Flight::Follower(mut receiver)) => {
+            let outcome = match receiver.wait_for(Option::is_some).await {
+                Ok(outcome) => outcome.clone(),
+                Err(_) => None,
+            };
+            match outcome {
+                Some((output, attempts)) => (output.map_err(|e| e.duplicate()), attempts),
+                None => run_attempts(invocation, policy, proxy, fingerprint, deadline).await,
+            }
+        }
+        None => run_attempts(invocation, policy, proxy, fingerprint, deadline).await,
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::fake_curl::FakeCurl;
+    use crate::types::{CuimpOptions, CuimpRequestConfig};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_identical_gets_share_one_invocation() {
+        let curl = FakeCurl::with_dir("coalesce", |dir| {
+            format!(
+                "#!/bin/sh\necho x >> '{}'\nsleep 0.3\nprintf 'HTTP/1.1 200 OK\\r\\n\\r\\n{{\"ok\":true}}'\n",
+                dir.join("calls").display()
+            )
+        });
+
+        let mut client = curl.client(CuimpOptions {
+            coalesce_requests: true,
+            ..Default::default()
+        });
+        // Header maps built apart still make the same arguments; another timeout does not
+        let config = |path: &str, timeout: Option<u64>| CuimpRequestConfig {
+            url: Some(format!("https://example.com/{}", path)),
+            headers: Some(HashMap::from([
+                ("X-One".to_string(), "1".to_string()),
+                ("X-Two".to_string(), "2".to_string()),
+                ("X-Three".to_string(), "3".to_string()),
+            ])),
+            timeout,
+            ..Default::default()
+        };
+        let configs = vec![
+            config("a", None),
+            config("a", None),
+            config("b", None),
+            config("a", Some(5_000)),
+        ];
+        let results = client.run_all::<Value>(configs, 4).await;
+        let calls = std::fs::read_to_string(curl.dir().join("calls")).unwrap();
+
+        assert_eq!(calls.lines().count(), 3);
+        for result in results {
+            assert_eq!(result.unwrap().data["ok"], true);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_followers_share_the_error() {
+        let curl = FakeCurl::with_dir("coalesce-error", |dir| {
+            format!(
+                "#!/bin/sh\necho x >> '{}'\nsleep 0.3\necho 'curl: (28) Operation timed out' >&2\nexit 28\n",
+                dir.join("calls").display()
+            )
+        });
+
+        let mut client = curl.client(CuimpOptions {
+            coalesce_requests: true,
+            ..Default::default()
+        });
+        let results = client
+            .get_many::<Value, _>(["https://example.com/a", "https://example.com/a"], 2)
+            .await;
+        let calls = std::fs::read_to_string(curl.dir().join("calls")).unwrap();
+
+        assert_eq!(calls.lines().count(), 1);
+        for result in results {
+            assert!(matches!(result, Err(crate::error::CuimpError::Timeout(_))));
+        }
+    }
+}
@@ -0,0 +1,482 @@
+//! reqwest-style API over `CuimpHttp`.
+//!
+//! `Client`, `RequestBuilder` and `Response` mirror the commonly used parts of
+//! `reqwest::Client`, so code written against reqwest can switch to impersonated
+//! requests by changing imports:
+//!
+//! ```no_run
+//! # async fn run() -> cuimp::Result<()> {
+//! let client = cuimp::compat::Client::new();
+//! let user: serde_json::Value = client
+//!     .get("https://api.example.com/user")
+//!     .header("Accept", "application/json")
+//!     .bearer_auth("token")
+//!     .send()
+//!     .await?
+//!     .error_for_status()?
+//!     .json()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Status codes are plain `u16` and headers a `HashMap`, as in `CuimpResponse`.
+
+use crate::client::{append_query, CuimpHttp};
+use crate::cookie::Cookie;
+use crate::error::{CuimpError, Result};
+use crate::into_url::IntoUrl;
+use crate::parallel::send_shared;
+use crate::query::to_query_string;
+use crate::types::{CuimpOptions, CuimpRequestConfig, CuimpResponse, HttpVersion, Method};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use url::Url;
+
+/// Cheaply cloneable client; clones share one `CuimpHttp` (cookies, proxy rotation)
+/// and their requests run concurrently.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: Arc<Mutex<CuimpHttp>>,
+}
+
+impl Client {
+    /// Client with default options
+    ///
+    /// # Panics
+    ///
+    /// Panics if the default client cannot be created, like `reqwest::Client::new`;
+    /// use `Client::with_options` to handle the error.
+    #[allow(clippy::expect_used)]
+    pub fn new() -> Self {
+        Client::with_options(CuimpOptions::default()).expect("default CuimpHttp options are valid")
+    }
+
+    pub fn with_options(options: CuimpOptions) -> Result<Self> {
+        Ok(CuimpHttp::new(options)?.into())
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// The shared `CuimpHttp`, for features without a reqwest counterpart
+    pub fn inner(&self) -> &Arc<Mutex<CuimpHttp>> {
+        &self.inner
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    pub fn put(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    pub fn patch(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    pub fn delete(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    pub fn head(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.request(Method::HEAD, url)
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        let config = url.into_url_text().map(|url| CuimpRequestConfig {
+            url: Some(url),
+            method: Some(method),
+            ..Default::default()
+        });
+        RequestBuilder {
+            client: self.clone(),
+            config,
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new()
+    }
+}
+
+impl From<CuimpHttp> for Client {
+    fn from(client: CuimpHttp) -> Self {
+        Client {
+            inner: Arc::new(Mutex::new(client)),
+        }
+    }
+}
+
+/// `Client` under construction
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    options: CuimpOptions,
+}
+
+impl ClientBuilder {
+    /// Headers sent with every request, unless a request sets or removes them
+    pub fn default_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.options
+            .headers
+            .get_or_insert_with(HashMap::new)
+            .extend(headers);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.options.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Start from `options` for settings without a builder method; headers, timeout and
+    /// proxy set so far are replaced
+    pub fn options(mut self, options: CuimpOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        Client::with_options(self.options)
+    }
+}
+
+/// Request under construction; errors are reported by `send`
+#[derive(Debug)]
+pub struct RequestBuilder {
+    client: Client,
+    config: Result<CuimpRequestConfig>,
+}
+
+impl RequestBuilder {
+    pub fn header(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let (name, value) = (name.into(), value.into());
+        self.map(|config| {
+            config
+                .headers
+                .get_or_insert_with(HashMap::new)
+                .insert(name, value);
+            Ok(())
+        })
+    }
+
+    pub fn headers(self, headers: HashMap<String, String>) -> Self {
+        self.map(|config| {
+            config
+                .headers
+                .get_or_insert_with(HashMap::new)
+                .extend(headers);
+            Ok(())
+        })
+    }
+
+    /// Do not send an inherited header: a client default, a browser preset or one curl adds
+    pub fn header_remove(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.map(|config| {
+            if let Some(headers) = config.headers.as_mut() {
+                headers.retain(|key, _| !key.eq_ignore_ascii_case(&name));
+            }
+            config
+                .remove_headers
+                .get_or_insert_with(Vec::new)
+                .push(name);
+            Ok(())
+        })
+    }
+
+    pub fn bearer_auth(self, token: impl std::fmt::Display) -> Self {
+        self.header("Authorization", format!("Bearer {}", token))
+    }
+
+    pub fn basic_auth(
+        self,
+        username: impl std::fmt::Display,
+        password: Option<impl std::fmt::Display>,
+    ) -> Self {
+        let credentials = match password {
+            Some(password) => format!("{}:{}", username, password),
+            None => format!("{}:", username),
+        };
+        self.header(
+            "Authorization",
+            format!("Basic {}", STANDARD.encode(credentials)),
+        )
+    }
+
+    /// Append query parameters to the URL, leaving its query as it is: a struct or map,
+    /// with nested fields as `address[city]=..` and sequences as `ids[0]=..`, or pairs
+    /// such as `&[("q", "rust"), ("page", "2")]`
+    pub fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        self.map(|config| {
+            let query = to_query_string(query)?;
+            append_query(config.url.get_or_insert_with(String::new), &query);
+            Ok(())
+        })
+    }
+
+    /// JSON request body
+    pub fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        self.map(|config| {
+            config.data = Some(serde_json::to_value(json)?);
+            config.body = None;
+            Ok(())
+        })
+    }
+
+    /// `application/x-www-form-urlencoded` body from a map or struct of scalar fields
+    pub fn form<T: Serialize + ?Sized>(self, form: &T) -> Self {
+        self.map(|config| {
+            let serde_json::Value::Object(fields) = serde_json::to_value(form)? else {
+                return Err(CuimpError::InvalidConfig(
+                    "Form data must serialize to a map".to_string(),
+                ));
+            };
+            let mut encoded = url::form_urlencoded::Serializer::new(String::new());
+            for (name, value) in fields {
+                match value {
+                    serde_json::Value::Null => {}
+                    serde_json::Value::String(value) => {
+                        encoded.append_pair(&name, &value);
+                    }
+                    value => {
+                        encoded.append_pair(&name, &value.to_string());
+                    }
+                }
+            }
+            config.body = Some(encoded.finish().into_bytes());
+            config.headers.get_or_insert_with(HashMap::new).insert(
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            );
+            Ok(())
+        })
+    }
+
+    /// Raw request body
+    pub fn body(self, body: impl Into<Vec<u8>>) -> Self {
+        let body = body.into();
+        self.map(|config| {
+            config.body = Some(body);
+            Ok(())
+        })
+    }
+
+    /// Timeout for the whole request
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.map(|config| {
+            config.timeout = Some(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+            Ok(())
+        })
+    }
+
+    /// The request as a `CuimpRequestConfig`, for options without a builder method
+    pub fn build(self) -> Result<CuimpRequestConfig> {
+        self.config
+    }
+
+    pub async fn send(self) -> Result<Response> {
+        let response = send_shared(&self.client.inner, self.config?).await?;
+        Ok(Response { inner: response })
+    }
+
+    fn map(mut self, f: impl FnOnce(&mut CuimpRequestConfig) -> Result<()>) -> Self {
+        if let Ok(config) = &mut self.config {
+            if let Err(e) = f(config) {
+                self.config = Err(e);
+            }
+        }
+        self
+    }
+}
+
+/// Response with reqwest-style accessors
+#[derive(Debug, Clone)]
+pub struct Response {
+    inner: CuimpResponse<Vec<u8>>,
+}
+
+impl Response {
+    pub fn status(&self) -> u16 {
+        self.inner.status
+    }
+
+    pub fn version(&self) -> Option<HttpVersion> {
+        self.inner.http_version
+    }
+
+    pub fn headers(&self) -> &HashMap<String, String> {
+        &self.inner.headers
+    }
+
+    /// Cookies set by the response
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.inner.cookies
+    }
+
+    /// URL the request was sent to
+    pub fn url(&self) -> &Url {
+        &self.inner.request.url
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        Some(self.inner.raw_body.len() as u64)
+    }
+
+    /// `HttpError` for 4xx and 5xx responses
+    pub fn error_for_status(self) -> Result<Self> {
+        self.error_for_status_ref()?;
+        Ok(self)
+    }
+
+    pub fn error_for_status_ref(&self) -> Result<&Self> {
+        if (400..600).contains(&self.inner.status) {
+            return Err(CuimpError::HttpError(format!(
+                "{} {} for {}",
+                self.inner.status, self.inner.status_text, self.inner.request.url
+            )));
+        }
+        Ok(self)
+    }
+
+    /// Body as text, with invalid UTF-8 replaced
+    pub async fn text(self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.inner.raw_body).into_owned())
+    }
+
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.inner.raw_body)?)
+    }
+
+    pub async fn bytes(self) -> Result<Vec<u8>> {
+        Ok(self.inner.raw_body)
+    }
+
+    /// The underlying `CuimpResponse`, with the body bytes as data
+    pub fn into_inner(self) -> CuimpResponse<Vec<u8>> {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_builder() {
+        let client = Client::new();
+        let config = client
+            .post("https://example.com/search")
+            .query(&[("q", "rust lang"), ("page", "2")])
+            .basic_auth("user", Some("pass"))
+            .form(&serde_json::json!({ "a": "1 2", "b": true, "c": null }))
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.url.as_deref(),
+            Some("https://example.com/search?q=rust+lang&page=2")
+        );
+        assert_eq!(config.body.as_deref(), Some(&b"a=1+2&b=true"[..]));
+        let headers = config.headers.unwrap();
+        assert_eq!(headers["Authorization"], "Basic dXNlcjpwYXNz");
+        assert_eq!(headers["Content-Type"], "application/x-www-form-urlencoded");
+
+        let invalid = client.get("not a url").header("Accept", "*/*").build();
+        assert!(matches!(invalid, Err(CuimpError::InvalidUrl(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_send_json() {
+        use crate::fake_curl::FakeCurl;
+
+        let curl = FakeCurl::new(
+            "compat",
+            "#!/bin/sh\nprintf 'HTTP/1.1 404 Not Found\\r\\n\\r\\n{\"error\":\"missing\"}'\n",
+        );
+
+        let client = Client::with_options(CuimpOptions {
+            path: Some(curl.path()),
+            ..Default::default()
+        })
+        .unwrap();
+        let response = client.get("https://example.com/item").send().await;
+
+        let response = response.unwrap();
+        assert_eq!(response.status(), 404);
+        assert!(matches!(
+            response.error_for_status_ref(),
+            Err(CuimpError::HttpError(_))
+        ));
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "missing");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_default_headers() {
+        use crate::fake_curl::FakeCurl;
+
+        let curl = FakeCurl::new(
+            "compat-headers",
+            "#!/bin/sh\nprintf 'HTTP/1.1 200 OK\\r\\n\\r\\nok'\n",
+        );
+
+        let client = Client::builder()
+            .options(CuimpOptions {
+                path: Some(curl.path()),
+                ..Default::default()
+            })
+            .default_headers(HashMap::from([
+                ("Accept".to_string(), "application/json".to_string()),
+                ("X-Api-Version".to_string(), "2".to_string()),
+            ]))
+            .build()
+            .unwrap();
+        let inherited = client
+            .get("https://example.com/")
+            .send()
+            .await
+            .unwrap()
+            .into_inner();
+        let overridden = client
+            .get("https://example.com/")
+            .header("accept", "text/csv")
+            .header_remove("x-api-version")
+            .header_remove("User-Agent")
+            .send()
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(inherited.request.headers["Accept"], "application/json");
+        assert_eq!(inherited.request.headers["X-Api-Version"], "2");
+        assert_eq!(
+            overridden.request.headers,
+            HashMap::from([("accept".to_string(), "text/csv".to_string())])
+        );
+        assert!(overridden.request.command.ends_with(
+            " -H 'accept: text/csv' -H x-api-version: -H User-Agent: -i https://example.com/"
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+use crate::curl_command::shell_split;
+use crate::error::{CuimpError, Result};
+#[cfg(feature = "config")]
+use crate::profile::{DomainProfile, DomainProfiles};
+use crate::types::CuimpOptions;
+#[cfg(feature = "config")]
+use crate::types::{Arch, Browser, CuimpDescriptor, Platform};
+use std::collections::HashMap;
+
+/// Prefix of the environment variables holding default headers
+const HEADER_ENV_PREFIX: &str = "CUIMP_HEADER_";
+
+/// Options file layout (`cuimp.toml`)
+#[cfg(feature = "config")]
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    browser: Option<Browser>,
+    version: Option<String>,
+    release: Option<String>,
+    architecture: Option<Arch>,
+    platform: Option<Platform>,
+    sha256: Option<String>,
+    signing_key: Option<String>,
+    allow_unverified: Option<bool>,
+    path: Option<String>,
+    binaries_dir: Option<String>,
+    auto_download: Option<bool>,
+    proxy: Option<String>,
+    timeout_ms: Option<u64>,
+    extra_curl_args: Option<Vec<String>>,
+    headers: Option<HashMap<String, String>>,
+    /// `[domains."example.com"]` tables
+    domains: Option<HashMap<String, DomainProfile>>,
+    /// `[proxy_pools]` label -> proxies, referenced by `proxy_labels`
+    proxy_pools: Option<HashMap<String, Vec<String>>>,
+}
+
+impl CuimpOptions {
+    /// Build options from `CUIMP_*` environment variables.
+    ///
+    /// | Variable | Option |
+    /// |----------|--------|
+    /// | `CUIMP_BROWSER`, `CUIMP_BROWSER_VERSION` | `descriptor.browser`, `descriptor.version` |
+    /// | `CUIMP_RELEASE` | `descriptor.release` |
+    /// | `CUIMP_PATH` | `path` |
+    /// | `CUIMP_AUTO_DOWNLOAD` | `auto_download` (`true`/`false`, `1`/`0`) |
+    /// | `CUIMP_PROXY` | `proxy` |
+    /// | `CUIMP_TRUST_ENV` | `trust_env` (`true`/`false`, `1`/`0`) |
+    /// | `CUIMP_TIMEOUT_MS` | `timeout` |
+    /// | `CUIMP_EXTRA_CURL_ARGS` | `extra_curl_args` (shell syntax) |
+    /// | `CUIMP_HEADER_<NAME>` | `headers`, with `_` in the name mapped to `-` (`CUIMP_HEADER_ACCEPT_LANGUAGE`) |
+    pub fn from_env() -> Result<Self> {
+        CuimpOptions::default().apply_env()
+    }
+
+    /// Override these options with any `CUIMP_*` environment variables that are set.
+    ///
+    /// See [`CuimpOptions::from_env`] for the recognized variables.
+    pub fn apply_env(self) -> Result<Self> {
+        self.apply_vars(std::env::vars())
+    }
+
+    fn apply_vars(mut self, vars: impl Iterator<Item = (String, String)>) -> Result<Self> {
+        for (key, value) in vars {
+            match key.as_str() {
+                "CUIMP_BROWSER" => {
+                    self.descriptor.get_or_insert_with(Default::default).browser =
+                        Some(value.parse()?)
+                }
+                "CUIMP_BROWSER_VERSION" => {
+                    self.descriptor.get_or_insert_with(Default::default).version = Some(value)
+                }
+                "CUIMP_RELEASE" => {
+                    self.descriptor.get_or_insert_with(Default::default).release = Some(value)
+                }
+                "CUIMP_PATH" => self.path = Some(value),
+                "CUIMP_PROXY" => self.proxy = Some(value),
+                "CUIMP_AUTO_DOWNLOAD" => self.auto_download = env_bool(&key, &value)?,
+                "CUIMP_TRUST_ENV" => self.trust_env = env_bool(&key, &value)?,
+                "CUIMP_TIMEOUT_MS" => {
+                    self.timeout = Some(value.parse().map_err(|_| {
+                        CuimpError::InvalidConfig(format!(
+                            "CUIMP_TIMEOUT_MS must be an integer, got {:?}",
+                            value
+                        ))
+                    })?)
+                }
+                "CUIMP_EXTRA_CURL_ARGS" => self.extra_curl_args = Some(shell_split(&value)?),
+                _ => {
+                    if let Some(name) = key
+                        .strip_prefix(HEADER_ENV_PREFIX)
+                        .filter(|n| !n.is_empty())
+                    {
+                        self.headers
+                            .get_or_insert_with(HashMap::new)
+                            .insert(header_name_from_env(name), value);
+                    }
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Load options from a TOML file
+    ///
+    /// ```toml
+    /// browser = "chrome"
+    /// version = "124"
+    /// proxy = "socks5h://127.0.0.1:9050"
+    /// timeout_ms = 30000
+    ///
+    /// [headers]
+    /// Accept-Language = "en-US,en;q=0.9"
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let file: ConfigFile = toml::from_str(&text)
+            .map_err(|e| CuimpError::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+
+        let has_descriptor = file.browser.is_some()
+            || file.version.is_some()
+            || file.release.is_some()
+            || file.architecture.is_some()
+            || file.platform.is_some()
+            || file.sha256.is_some()
+            || file.signing_key.is_some()
+            || file.allow_unverified.is_some();
+
+        Ok(CuimpOptions {
+            descriptor: has_descriptor.then_some(CuimpDescriptor {
+                browser: file.browser,
+                version: file.version,
+                release: file.release,
+                architecture: file.architecture,
+                platform: file.platform,
+                sha256: file.sha256,
+                signing_key: file.signing_key,
+                allow_unverified: file.allow_unverified.unwrap_or_default(),
+            }),
+            path: file.path,
+            binaries_dir: file.binaries_dir,
+            auto_download: file.auto_download.unwrap_or(true),
+            proxy: file.proxy,
+            timeout: file.timeout_ms,
+            extra_curl_args: file.extra_curl_args,
+            headers: file.headers,
+            domain_profiles: domain_profiles(file.domains, file.proxy_pools),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(feature = "config")]
+fn domain_profiles(
+    domains: Option<HashMap<String, DomainProfile>>,
+    proxy_pools: Option<HashMap<String, Vec<String>>>,
+) -> Option<DomainProfiles> {
+    if domains.is_none() && proxy_pools.is_none() {
+        return None;
+    }
+    let profiles = domains
+        .into_iter()
+        .flatten()
+        .fold(DomainProfiles::new(), |profiles, (domain, profile)| {
+            profiles.domain(domain, profile)
+        });
+    Some(
+        proxy_pools
+            .into_iter()
+            .flatten()
+            .fold(profiles, |profiles, (label, proxies)| {
+                profiles.proxy_pool(label, proxies)
+            }),
+    )
+}
+
+/// Boolean variable: `true`/`false`, `1`/`0` or `yes`/`no`
+fn env_bool(key: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" => Ok(false),
+        _ => Err(CuimpError::InvalidConfig(format!(
+            "{} must be true or false, got {:?}",
+            key, value
+        ))),
+    }
+}
+
+/// `ACCEPT_LANGUAGE` -> `Accept-Language`
+fn header_name_from_env(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let lower = part.to_ascii_lowercase();
+            let mut chars = lower.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_vars() {
+        let vars = [
+            ("CUIMP_BROWSER", "firefox"),
+            ("CUIMP_TIMEOUT_MS", "1500"),
+            ("CUIMP_EXTRA_CURL_ARGS", "--compressed --max-redirs '3'"),
+            ("CUIMP_HEADER_ACCEPT_LANGUAGE", "de-DE"),
+            ("CUIMP_TRUST_ENV", "false"),
+            ("CUIMP_AUTO_DOWNLOAD", "0"),
+            ("UNRELATED", "x"),
+        ];
+        let options = CuimpOptions::default()
+            .apply_vars(vars.iter().map(|(k, v)| (k.to_string(), v.to_string())))
+            .unwrap();
+
+        assert_eq!(
+            options.descriptor.unwrap().browser,
+            Some(crate::types::Browser::Firefox)
+        );
+        assert_eq!(options.timeout, Some(1500));
+        assert!(!options.trust_env);
+        assert!(!options.auto_download);
+        assert_eq!(
+            options.extra_curl_args,
+            Some(vec![
+                "--compressed".to_string(),
+                "--max-redirs".to_string(),
+                "3".to_string()
+            ])
+        );
+        assert_eq!(
+            options
+                .headers
+                .unwrap()
+                .get("Accept-Language")
+                .map(String::as_str),
+            Some("de-DE")
+        );
+
+        let invalid = CuimpOptions::default().apply_vars(std::iter::once((
+            "CUIMP_TIMEOUT_MS".to_string(),
+            "soon".to_string(),
+        )));
+        assert!(matches!(invalid, Err(CuimpError::InvalidConfig(_))));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_from_file_domains() {
+        let path = std::env::temp_dir().join(format!("cuimp-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+                browser = "chrome"
+
+                [domains."example.com"]
+                rate = 0.5
+                concurrency = 2
+                headers = { Referer = "https://example.com/" }
+                fingerprint = { browser = "firefox" }
+                proxy_labels = ["residential"]
+
+                [proxy_pools]
+                residential = ["http://proxy:8080"]
+            "#,
+        )
+        .unwrap();
+        let options = CuimpOptions::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let profiles = options.unwrap().domain_profiles.unwrap();
+        let (domain, profile) = profiles.profile_for("shop.example.com").unwrap();
+        assert_eq!(
+            (domain, profile.rate, profile.concurrency),
+            ("example.com", Some(0.5), Some(2))
+        );
+        assert_eq!(
+            profile.fingerprint.as_ref().unwrap().browser,
+            Some(Browser::Firefox)
+        );
+        assert_eq!(
+            profiles.next_proxy(domain, profile).as_deref(),
+            Some("http://proxy:8080")
+        );
+    }
+}
@@ -0,0 +1,309 @@
+// Parses untrusted process output: never index or unwrap.
+#![deny(clippy::indexing_slicing, clippy::unwrap_used, clippy::expect_used)]
+
+use crate::types::HttpVersion;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Starts the `--write-out` trailer appended to curl's output
+const MARKER: &str = "\n--cuimp-connection--";
+
+/// Connection details of a response, from curl's `--write-out` variables
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    /// Negotiated protocol of the final transfer
+    pub http_version: Option<HttpVersion>,
+    pub remote_ip: Option<IpAddr>,
+    pub remote_port: Option<u16>,
+    pub local_ip: Option<IpAddr>,
+    pub local_port: Option<u16>,
+    /// Negotiated TLS version (e.g. `TLSv1.3`); curl only reports it in its verbose
+    /// trace, so this is set for `CuimpRequestConfig::debug` requests only
+    pub tls_version: Option<String>,
+    /// Negotiated cipher suite (e.g. `TLS_AES_128_GCM_SHA256`); `debug` requests only
+    pub cipher: Option<String>,
+    /// Subject of the server certificate (e.g. `CN = example.com`)
+    pub cert_subject: Option<String>,
+    /// Issuer of the server certificate
+    pub cert_issuer: Option<String>,
+    /// End of the server certificate's validity
+    pub cert_expires: Option<SystemTime>,
+    /// Server certificate chain, leaf first, when `CuimpRequestConfig::capture_certificates`
+    /// is set
+    pub certificates: Vec<Certificate>,
+}
+
+/// A certificate of the server's chain, with the fields curl reports for it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Certificate {
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    pub serial_number: Option<String>,
+    pub signature_algorithm: Option<String>,
+    pub public_key_algorithm: Option<String>,
+    pub not_before: Option<SystemTime>,
+    pub not_after: Option<SystemTime>,
+    /// Entries of the subjectAltName extension (e.g. `DNS:example.com`)
+    pub subject_alt_names: Vec<String>,
+    /// The certificate in PEM form
+    pub pem: Option<String>,
+    /// Every `(name, value)` pair curl reported, in order, including extensions
+    pub fields: Vec<(String, String)>,
+}
+
+impl Certificate {
+    /// Value of a field, matched case-insensitively (e.g. `X509v3 Key Usage`)
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parse the `Name:value` lines curl reports for one certificate; lines without a
+    /// name (the body of the PEM) continue the previous value
+    fn parse(text: &str) -> Self {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        for line in text.lines() {
+            match (line.split_once(':'), fields.last_mut()) {
+                (Some((name, value)), _) if !name.is_empty() && !name.starts_with('-') => {
+                    fields.push((name.trim().to_string(), value.trim().to_string()))
+                }
+                (_, Some((_, value))) => {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(line.trim_end());
+                }
+                (_, None) => {}
+            }
+        }
+
+        let text = |name: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Certificate {
+            subject: text("Subject"),
+            issuer: text("Issuer"),
+            serial_number: text("Serial Number"),
+            signature_algorithm: text("Signature Algorithm"),
+            public_key_algorithm: text("Public Key Algorithm"),
+            not_before: text("Start date").and_then(|v| parse_cert_time(&v)),
+            not_after: text("Expire date").and_then(|v| parse_cert_time(&v)),
+            subject_alt_names: text("X509v3 Subject Alternative Name")
+                .map(|names| {
+                    names
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            pem: text("Cert"),
+            fields,
+        }
+    }
+}
+
+/// Parse the output of `%{certs}`: one block of `Name:value` lines per certificate,
+/// leaf first, each followed by a blank line
+pub(crate) fn parse_certificates(text: &str) -> Vec<Certificate> {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(Certificate::parse)
+        .collect()
+}
+
+/// `--write-out` format producing the trailer parsed by `split_trailer`
+pub(crate) fn write_out() -> String {
+    trailer("%{json}", "%{certs}")
+}
+
+/// Trailer with the given `%{json}` and `%{certs}` output
+pub(crate) fn trailer(json: &str, certs: &str) -> String {
+    format!("{m}json:{}{m}certs:{}", json, certs, m = MARKER)
+}
+
+/// Split the `--write-out` trailer off curl's output, parsing it into `ConnectionInfo`
+/// that keeps the certificate chain when `keep_chain` is set. Output without a trailer
+/// is returned unchanged.
+pub(crate) fn split_trailer(stdout: &[u8], keep_chain: bool) -> (&[u8], Option<ConnectionInfo>) {
+    let json_marker = format!("{}json:", MARKER);
+    let Some(start) = rfind(stdout, json_marker.as_bytes()) else {
+        return (stdout, None);
+    };
+    let output = stdout.get(..start).unwrap_or_default();
+    let trailer =
+        String::from_utf8_lossy(stdout.get(start + json_marker.len()..).unwrap_or_default());
+    let (json, certs) = trailer
+        .split_once(&format!("{}certs:", MARKER))
+        .unwrap_or((trailer.as_ref(), ""));
+
+    let Ok(vars) = serde_json::from_str::<Value>(json.trim()) else {
+        return (output, None);
+    };
+    let text = |name: &str| {
+        vars.get(name)
+            .and_then(Value::as_str)
+            .filter(|value| !value.is_empty())
+    };
+    let port = |name: &str| {
+        vars.get(name)
+            .and_then(Value::as_u64)
+            .and_then(|port| u16::try_from(port).ok())
+            .filter(|port| *port != 0)
+    };
+
+    let mut info = ConnectionInfo {
+        http_version: text("http_version")
+            .and_then(|v| HttpVersion::from_status_token(&format!("HTTP/{}", v))),
+        remote_ip: text("remote_ip").and_then(|ip| ip.parse().ok()),
+        remote_port: port("remote_port"),
+        local_ip: text("local_ip").and_then(|ip| ip.parse().ok()),
+        local_port: port("local_port"),
+        ..Default::default()
+    };
+    let certificates = parse_certificates(certs);
+    if let Some(leaf) = certificates.first() {
+        info.cert_subject = leaf.subject.clone();
+        info.cert_issuer = leaf.issuer.clone();
+        info.cert_expires = leaf.not_after;
+    }
+    if keep_chain {
+        info.certificates = certificates;
+    }
+    (output, Some(info))
+}
+
+impl ConnectionInfo {
+    /// Fill in the TLS version and cipher from curl's verbose trace
+    /// (`* SSL connection using TLSv1.3 / TLS_AES_128_GCM_SHA256 / ...`)
+    pub(crate) fn apply_trace(&mut self, trace: &str) {
+        let Some(line) = trace
+            .lines()
+            .find_map(|line| line.strip_prefix("* SSL connection using "))
+        else {
+            return;
+        };
+        let mut parts = line.split(" / ").map(str::trim);
+        self.tls_version = parts.next().map(str::to_string);
+        self.cipher = parts.next().map(str::to_string);
+    }
+}
+
+/// Parse an OpenSSL certificate time (`Mar 14 23:59:59 2025 GMT`)
+fn parse_cert_time(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let mut parts = value.split_whitespace();
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let day: i64 = parts.next()?.parse().ok()?;
+    let mut clock = parts.next()?.split(':').map(|n| n.parse::<i64>().ok());
+    let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+    let year: i64 = parts.next()?.parse().ok()?;
+    if parts.next() != Some("GMT") || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since the Unix epoch of a proleptic Gregorian date
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_trailer() {
+        let stdout = "HTTP/2 200\r\n\r\nbody\n--cuimp-connection--json:{\"http_version\":\"2\",\
+            \"remote_ip\":\"93.184.216.34\",\"remote_port\":443,\"local_ip\":\"10.0.0.2\",\"local_port\":51234}\n\
+            --cuimp-connection--certs:Subject:CN = example.com\nIssuer:C = US, O = Example CA\n\
+            Expire date:Mar 14 23:59:59 2025 GMT\nCert:\n-----BEGIN CERTIFICATE-----\nMIIB\n\
+            -----END CERTIFICATE-----\n\nSubject:C = US, O = Example CA\n\n";
+        let (output, info) = split_trailer(stdout.as_bytes(), false);
+        let mut info = info.unwrap();
+        info.apply_trace("* ALPN: server accepted h2\n* SSL connection using TLSv1.3 / TLS_AES_128_GCM_SHA256 / X25519");
+
+        assert_eq!(output, b"HTTP/2 200\r\n\r\nbody");
+        assert_eq!(info.http_version, Some(HttpVersion::Http2));
+        assert_eq!(info.remote_ip, Some("93.184.216.34".parse().unwrap()));
+        assert_eq!(
+            (info.remote_port, info.local_port),
+            (Some(443), Some(51234))
+        );
+        assert_eq!(info.cert_subject.as_deref(), Some("CN = example.com"));
+        assert_eq!(info.cert_issuer.as_deref(), Some("C = US, O = Example CA"));
+        assert_eq!(
+            info.cert_expires,
+            Some(UNIX_EPOCH + Duration::from_secs(1_741_996_799))
+        );
+        assert_eq!(info.tls_version.as_deref(), Some("TLSv1.3"));
+        assert_eq!(info.cipher.as_deref(), Some("TLS_AES_128_GCM_SHA256"));
+        assert!(info.certificates.is_empty());
+
+        // No trailer, e.g. with a `--write-out` of the caller's own
+        assert_eq!(
+            split_trailer(b"HTTP/1.1 204 No Content\r\n\r\n", true),
+            (&b"HTTP/1.1 204 No Content\r\n\r\n"[..], None)
+        );
+    }
+
+    #[test]
+    fn test_certificate_chain() {
+        let certs = "Subject:CN = example.com\nIssuer:C = US, O = Example CA\nVersion:2\n\
+            Serial Number:0a:1b:2c\nSignature Algorithm:ecdsa-with-SHA256\nPublic Key Algorithm:id-ecPublicKey\n\
+            X509v3 Subject Alternative Name:DNS:example.com, DNS:www.example.com\n\
+            Start date:Jan 15 00:00:00 2025 GMT\nExpire date:Jan 15 23:59:59 2026 GMT\n\
+            Cert:-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n\n\
+            Subject:C = US, O = Example CA\nIssuer:C = US, O = Example Root\n\n";
+        let chain = parse_certificates(certs);
+
+        let [leaf, intermediate] = chain.as_slice() else {
+            panic!("expected two certificates")
+        };
+        assert_eq!(leaf.subject.as_deref(), Some("CN = example.com"));
+        assert_eq!(leaf.serial_number.as_deref(), Some("0a:1b:2c"));
+        assert_eq!(
+            leaf.subject_alt_names,
+            ["DNS:example.com", "DNS:www.example.com"]
+        );
+        assert_eq!(
+            leaf.not_before,
+            Some(UNIX_EPOCH + Duration::from_secs(1_736_899_200))
+        );
+        assert_eq!(leaf.field("version"), Some("2"));
+        assert_eq!(
+            leaf.pem.as_deref(),
+            Some("-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----")
+        );
+        assert_eq!(
+            intermediate.issuer.as_deref(),
+            Some("C = US, O = Example Root")
+        );
+    }
+}
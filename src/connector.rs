@@ -1,11 +1,102 @@
 use crate::error::{CuimpError, Result};
-use serde::Deserialize;
+use crate::events::{CuimpEvent, Events};
+use crate::parser::cuimp_home;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Deserialize)]
 struct GitHubRelease {
     tag_name: String,
 }
 
+/// `metadata.json` in the data directory (`CUIMP_HOME`, or `~/.cuimp`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Metadata {
+    #[serde(default)]
+    latest_release: Option<CachedRelease>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedRelease {
+    tag: String,
+    /// Unix time of the lookup
+    fetched_at: u64,
+}
+
+fn metadata_path() -> PathBuf {
+    cuimp_home().join("metadata.json")
+}
+
+fn read_metadata() -> Metadata {
+    std::fs::read_to_string(metadata_path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_metadata(metadata: &Metadata) -> Result<()> {
+    let path = metadata_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write then rename so concurrent readers never see a partial file
+    let temp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&temp, serde_json::to_vec_pretty(metadata)?)?;
+    std::fs::rename(&temp, &path)?;
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Latest release tag, served from the cached metadata while younger than `ttl`.
+///
+/// `refresh` skips the cache. If GitHub cannot be reached, a stale cached tag is used.
+pub(crate) async fn get_latest_release_cached(
+    ttl: Duration,
+    refresh: bool,
+    events: &Events,
+) -> Result<String> {
+    let mut metadata = read_metadata();
+    let cached = metadata.latest_release.clone();
+
+    if let Some(cached) = &cached {
+        let age = unix_now().saturating_sub(cached.fetched_at);
+        if !refresh && age < ttl.as_secs() {
+            return Ok(cached.tag.clone());
+        }
+    }
+
+    match get_latest_release().await {
+        Ok(tag) => {
+            metadata.latest_release = Some(CachedRelease {
+                tag: tag.clone(),
+                fetched_at: unix_now(),
+            });
+            if let Err(e) = write_metadata(&metadata) {
+                events.emit(CuimpEvent::ReleaseCacheWriteFailed {
+                    error: e.to_string(),
+                });
+            }
+            Ok(tag)
+        }
+        Err(e) => match cached {
+            Some(cached) => {
+                events.emit(CuimpEvent::ReleaseLookupFailed {
+                    error: e.to_string(),
+                    cached_tag: cached.tag.clone(),
+                });
+                Ok(cached.tag)
+            }
+            None => Err(e),
+        },
+    }
+}
+
 pub async fn get_latest_release() -> Result<String> {
     let url = "https://api.github.com/repos/lexiforest/curl-impersonate/releases/latest";
 
@@ -1,7 +1,3 @@
-pub const BROWSER_LIST: &[&str] = &["chrome", "firefox", "edge", "safari"];
-pub const ARCHITECTURE_LIST: &[&str] = &["x64", "arm64"];
-pub const PLATFORM_LIST: &[&str] = &["linux", "windows", "macos"];
-
 pub const BINARY_SEARCH_PATHS: &[&str] = &[
     "/usr/local/bin/",
     "/usr/bin/",
@@ -22,4 +18,43 @@ pub const BINARY_PATTERNS: &[&str] = &[
     "curl_firefox",
     "curl_edge",
     "curl_safari",
+    "curl_tor",
+    "curl_okhttp",
 ];
+
+/// Time `<binary> --version` may take when a found or downloaded binary is checked
+pub const VERSION_PROBE_TIMEOUT_MS: u64 = 10_000;
+
+/// File in the binaries directory locked while a release is downloaded and extracted
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+pub const DOWNLOAD_LOCK_FILE: &str = ".download.lock";
+
+/// How often a process waiting for another one's download retries the lock
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+pub const DOWNLOAD_LOCK_POLL_MS: u64 = 100;
+
+/// SHA-256 of curl-impersonate release archives by asset name, trusted ahead of the
+/// `.sha256` file published next to the archive. Releases listed here install even when
+/// GitHub serves no checksum; others need one, `CuimpDescriptor::sha256` or
+/// `CuimpDescriptor::allow_unverified`.
+#[cfg(feature = "download")]
+pub const RELEASE_DIGESTS: &[(&str, &str)] = &[];
+
+/// Filename prefix of the per-target wrapper scripts shipped in releases (e.g. `curl_chrome124`)
+pub const WRAPPER_PREFIX: &str = "curl_";
+
+/// Common server limits for the total size of request headers (nginx/Apache: 8 KiB, many CDNs: 16 KiB)
+pub const COMMON_HEADER_LIMITS: &[usize] = &[8 * 1024, 16 * 1024];
+
+/// Request bodies larger than this go to curl through a temporary file instead of argv,
+/// well below Linux's 128 KiB limit on a single argument
+pub const DEFAULT_BODY_FILE_THRESHOLD: usize = 64 * 1024;
+
+/// Schemes accepted for request URLs unless `CuimpOptions::allowed_url_schemes` says otherwise
+pub const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https", "ws", "wss"];
+
+/// Redirects a `RedirectPolicy::Custom` request follows before failing, whatever the callback says
+pub const MAX_CUSTOM_REDIRECTS: usize = 20;
+
+/// Maximum size of a single `Cookie` header line when splitting is enabled
+pub const COOKIE_SPLIT_BYTES: usize = 4 * 1024;
@@ -0,0 +1,588 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+/// Longest lifetime of a cookie: browsers cap `Expires` and `Max-Age` at 400 days
+const MAX_COOKIE_AGE: Duration = Duration::from_secs(400 * 24 * 60 * 60);
+
+/// `SameSite` cookie attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// An HTTP cookie
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    pub expires: Option<SystemTime>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Create a session cookie with only a name and value
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Cookie {
+            name: name.into(),
+            value: value.into(),
+            domain: None,
+            path: None,
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Parse a `Set-Cookie` header value.
+    ///
+    /// `Max-Age` takes precedence over `Expires` and is converted to an absolute time;
+    /// either is capped at 400 days from now. Returns `None` when the header has no
+    /// `name=value` pair.
+    pub fn parse(set_cookie: &str) -> Option<Cookie> {
+        let mut parts = set_cookie.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie::new(name, value.trim().trim_matches('"'));
+        let mut max_age = None;
+
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    cookie.domain = Some(value.trim_start_matches('.').to_ascii_lowercase())
+                }
+                "path" if value.starts_with('/') => cookie.path = Some(value.to_string()),
+                "expires" => cookie.expires = httpdate::parse_http_date(value).ok(),
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = match value.to_ascii_lowercase().as_str() {
+                        "strict" => Some(SameSite::Strict),
+                        "lax" => Some(SameSite::Lax),
+                        "none" => Some(SameSite::None),
+                        _ => None,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let now = SystemTime::now();
+        if let Some(max_age) = max_age {
+            cookie.expires = Some(if max_age <= 0 {
+                SystemTime::UNIX_EPOCH
+            } else {
+                now + Duration::from_secs(max_age.unsigned_abs()).min(MAX_COOKIE_AGE)
+            });
+        }
+        cookie.expires = cookie
+            .expires
+            .map(|expires| expires.min(now + MAX_COOKIE_AGE));
+
+        Some(cookie)
+    }
+
+    /// Whether the cookie has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| expires <= SystemTime::now())
+    }
+}
+
+/// Which cookies a single request sends and stores
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CookiePolicy {
+    /// Send matching cookies from the client's jar and store received ones
+    #[default]
+    JarDefault,
+    /// Neither send nor store cookies
+    None,
+    /// Send exactly these cookies and do not store received ones
+    Only(Vec<Cookie>),
+}
+
+#[derive(Debug, Clone)]
+struct StoredCookie {
+    cookie: Cookie,
+    /// Domain the cookie applies to (the request host when no Domain attribute was set)
+    domain: String,
+    host_only: bool,
+    path: String,
+}
+
+/// In-memory cookie store for a client session
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Store the cookies from `Set-Cookie` header values received for `url`
+    pub fn store_response_cookies(&mut self, set_cookies: &[String], url: &Url) {
+        for cookie in set_cookies
+            .iter()
+            .filter_map(|header| Cookie::parse(header))
+        {
+            self.insert_for_url(cookie, url);
+        }
+    }
+
+    /// Insert a cookie as if it had been received from `url`.
+    ///
+    /// Cookies a browser would refuse are dropped: a `Domain` the URL's host is not in or
+    /// that is a public suffix (`com`, or `co.uk` with the `public-suffix` feature), `Secure`
+    /// ones and `__Secure-`/`__Host-` prefixed names from plain http or without their
+    /// attributes, `SameSite=None` without `Secure`, and any that would replace a `Secure`
+    /// cookie from plain http.
+    pub fn insert_for_url(&mut self, cookie: Cookie, url: &Url) {
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return;
+        };
+        let secure = is_secure(url);
+        let host_prefixed = cookie.name.starts_with("__Host-");
+        let secure_only = cookie.secure || cookie.name.starts_with("__Secure-") || host_prefixed;
+        let refused = (secure_only && (!cookie.secure || !secure))
+            || (host_prefixed && (cookie.domain.is_some() || cookie.path.as_deref() != Some("/")))
+            || (cookie.same_site == Some(SameSite::None) && !cookie.secure);
+        if refused {
+            return;
+        }
+
+        let (domain, host_only) = match &cookie.domain {
+            // A server that is a public suffix itself may still set a cookie for its own host
+            Some(domain) if is_public_suffix(domain) && *domain == host => (host, true),
+            Some(domain) if !is_public_suffix(domain) && domain_matches(&host, domain) => {
+                (domain.clone(), false)
+            }
+            // Reject cookies for domains the response is not allowed to set
+            Some(_) => return,
+            None => (host, true),
+        };
+        let path = cookie.path.clone().unwrap_or_else(|| default_path(url));
+
+        let shadows_secure = |c: &StoredCookie| {
+            c.cookie.secure
+                && c.cookie.name == cookie.name
+                && (domain_matches(&domain, &c.domain) || domain_matches(&c.domain, &domain))
+                && path_matches(&path, &c.path)
+        };
+        if !secure && self.cookies.iter().any(shadows_secure) {
+            return;
+        }
+
+        self.cookies.retain(|c| !c.cookie.is_expired());
+        self.cookies
+            .retain(|c| !(c.cookie.name == cookie.name && c.domain == domain && c.path == path));
+        if !cookie.is_expired() {
+            self.cookies.push(StoredCookie {
+                cookie,
+                domain,
+                host_only,
+                path,
+            });
+        }
+    }
+
+    /// The `Cookie` header value to send to `url`, if any cookie matches.
+    ///
+    /// Matching follows RFC 6265: the host is the cookie's domain, or a subdomain of it
+    /// unless the cookie is host-only; the path is the cookie's or below it; `Secure`
+    /// cookies go only to https and wss; expired ones are never sent.
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let pairs: Vec<String> = self
+            .cookies_for(url)
+            .into_iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+
+        if pairs.is_empty() {
+            None
+        } else {
+            Some(pairs.join("; "))
+        }
+    }
+
+    /// The cookies `cookie_header` sends to `url`
+    pub fn cookies_for(&self, url: &Url) -> Vec<&Cookie> {
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return Vec::new();
+        };
+        let secure = is_secure(url);
+        self.cookies
+            .iter()
+            .filter(|c| !c.cookie.is_expired() && (secure || !c.cookie.secure))
+            .filter(|c| {
+                if c.host_only {
+                    host == c.domain
+                } else {
+                    domain_matches(&host, &c.domain)
+                }
+            })
+            .filter(|c| path_matches(url.path(), &c.path))
+            .map(|c| &c.cookie)
+            .collect()
+    }
+
+    /// All stored cookies that have not expired
+    pub fn cookies(&self) -> Vec<&Cookie> {
+        self.cookies
+            .iter()
+            .filter(|c| !c.cookie.is_expired())
+            .map(|c| &c.cookie)
+            .collect()
+    }
+
+    /// Remove all cookies
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Parse a Netscape cookie file, the format of curl's `--cookie`/`--cookie-jar`.
+    ///
+    /// Malformed and expired lines are skipped, as curl does. `#HttpOnly_` prefixes
+    /// are honoured; the format has no `SameSite`.
+    pub fn from_netscape(text: &str) -> Self {
+        let mut jar = CookieJar::new();
+        for line in text.lines() {
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => (line, true),
+                None if line.starts_with('#') => continue,
+                None => (line, false),
+            };
+            let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+            let [domain, include_subdomains, path, secure, expires, name, value] =
+                fields.as_slice()
+            else {
+                continue;
+            };
+            let Ok(expires) = expires.parse::<u64>() else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+
+            let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+            let mut cookie = Cookie::new(*name, *value);
+            cookie.domain = Some(domain.clone());
+            cookie.path = Some(path.to_string());
+            cookie.secure = secure.eq_ignore_ascii_case("TRUE");
+            cookie.http_only = http_only;
+            cookie.expires =
+                (expires > 0).then(|| SystemTime::UNIX_EPOCH + Duration::from_secs(expires));
+            if domain.is_empty() || cookie.is_expired() {
+                continue;
+            }
+            jar.cookies.retain(|c| {
+                !(c.cookie.name == cookie.name && c.domain == domain && c.path == *path)
+            });
+            jar.cookies.push(StoredCookie {
+                cookie,
+                domain,
+                host_only: !include_subdomains.eq_ignore_ascii_case("TRUE"),
+                path: path.to_string(),
+            });
+        }
+        jar
+    }
+
+    /// The jar in Netscape cookie file format; session cookies get expiry `0`
+    pub fn to_netscape(&self) -> String {
+        let mut text = String::from("# Netscape HTTP Cookie File\n# Written by cuimp\n\n");
+        for stored in self.cookies.iter().filter(|c| !c.cookie.is_expired()) {
+            let cookie = &stored.cookie;
+            let expires = cookie
+                .expires
+                .and_then(|expires| expires.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_secs());
+            text.push_str(&format!(
+                "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                if cookie.http_only { "#HttpOnly_" } else { "" },
+                if stored.host_only { "" } else { "." },
+                stored.domain,
+                if stored.host_only { "FALSE" } else { "TRUE" },
+                stored.path,
+                if cookie.secure { "TRUE" } else { "FALSE" },
+                expires,
+                cookie.name,
+                cookie.value,
+            ));
+        }
+        text
+    }
+
+    /// Load a Netscape cookie file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(CookieJar::from_netscape(&std::fs::read_to_string(path)?))
+    }
+
+    /// Write the jar to a Netscape cookie file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_netscape())?;
+        Ok(())
+    }
+}
+
+/// Whether `host` is `domain` or a subdomain of it; IP addresses only match themselves
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.parse::<Ipv4Addr>().is_err()
+            && !host.starts_with('[')
+            && host
+                .strip_suffix(domain)
+                .is_some_and(|prefix| prefix.ends_with('.')))
+}
+
+/// Whether `domain` is one no cookie may be set for: a top-level domain, or any public
+/// suffix of the list with the `public-suffix` feature
+#[cfg(feature = "public-suffix")]
+fn is_public_suffix(domain: &str) -> bool {
+    // Unlisted top-level domains match the list's implicit `*` rule
+    psl::suffix(domain.as_bytes()).is_some_and(|suffix| suffix.as_bytes() == domain.as_bytes())
+}
+
+#[cfg(not(feature = "public-suffix"))]
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.')
+}
+
+/// Whether `Secure` cookies are sent to and accepted from `url`
+fn is_secure(url: &Url) -> bool {
+    matches!(url.scheme(), "https" | "wss")
+}
+
+/// RFC 6265 path matching
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/')
+                || request_path.as_bytes().get(cookie_path.len()) == Some(&b'/')))
+}
+
+/// Default cookie path: the request path up to its last `/`
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path.get(..index).unwrap_or("/").to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "public-suffix")]
+    #[test]
+    fn test_public_suffixes() {
+        for suffix in [
+            "com",
+            "co.uk",
+            "github.io",
+            "anything.ck",
+            "xn--fiqs8s",
+            "unlisted",
+        ] {
+            assert!(is_public_suffix(suffix), "{}", suffix);
+        }
+        for domain in [
+            "example.com",
+            "example.co.uk",
+            "user.github.io",
+            "www.ck",
+            "localhost.localdomain",
+        ] {
+            assert!(!is_public_suffix(domain), "{}", domain);
+        }
+    }
+
+    #[test]
+    fn test_parse_set_cookie() {
+        let cookie = Cookie::parse(
+            "sid=abc; Domain=.Example.com; Path=/app; Secure; HttpOnly; SameSite=Lax; Expires=Wed, 21 Oct 2099 07:28:00 GMT",
+        )
+        .unwrap();
+        assert_eq!(cookie.name, "sid");
+        assert_eq!(cookie.value, "abc");
+        assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+        assert_eq!(cookie.path.as_deref(), Some("/app"));
+        assert!(cookie.secure && cookie.http_only);
+        assert_eq!(cookie.same_site, Some(SameSite::Lax));
+        assert!(!cookie.is_expired());
+        assert!(Cookie::parse("novalue").is_none());
+    }
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_jar_domain_and_path_matching() {
+        let mut jar = CookieJar::new();
+        jar.store_response_cookies(
+            &[
+                "a=1; Domain=example.com".to_string(),
+                "b=2".to_string(),
+                "c=3; Path=/admin".to_string(),
+                "evil=1; Domain=other.com".to_string(),
+            ],
+            &url("https://www.example.com/login"),
+        );
+
+        assert_eq!(
+            jar.cookie_header(&url("https://api.example.com/"))
+                .as_deref(),
+            Some("a=1")
+        );
+        assert_eq!(
+            jar.cookie_header(&url("https://www.example.com/x"))
+                .as_deref(),
+            Some("a=1; b=2")
+        );
+        assert_eq!(
+            jar.cookie_header(&url("https://www.example.com/admin/users"))
+                .as_deref(),
+            Some("a=1; b=2; c=3")
+        );
+        assert_eq!(jar.cookie_header(&url("https://other.com/")), None);
+        let names: Vec<&str> = jar
+            .cookies_for(&url("https://api.example.com/admin"))
+            .into_iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["a"]);
+
+        jar.store_response_cookies(
+            &["b=gone; Max-Age=0".to_string()],
+            &url("https://www.example.com/"),
+        );
+        assert_eq!(
+            jar.cookie_header(&url("https://www.example.com/x"))
+                .as_deref(),
+            Some("a=1")
+        );
+    }
+
+    #[test]
+    fn test_jar_enforces_cookie_attributes() {
+        let mut jar = CookieJar::new();
+        let set = |jar: &mut CookieJar, header: &str, from: &str| {
+            jar.store_response_cookies(&[header.to_string()], &url(from));
+        };
+        set(&mut jar, "sid=1; Secure", "https://shop.example.com/");
+        set(&mut jar, "plain=1", "https://shop.example.com/");
+        set(&mut jar, "sid=hijacked", "http://shop.example.com/");
+        set(&mut jar, "insecure=1; Secure", "http://shop.example.com/");
+        set(&mut jar, "__Secure-a=1", "https://shop.example.com/");
+        set(
+            &mut jar,
+            "__Host-b=1; Secure; Path=/; Domain=example.com",
+            "https://shop.example.com/",
+        );
+        set(
+            &mut jar,
+            "__Host-c=1; Secure; Path=/",
+            "https://shop.example.com/",
+        );
+        set(
+            &mut jar,
+            "embed=1; SameSite=None",
+            "https://shop.example.com/",
+        );
+        set(&mut jar, "super=1; Domain=com", "https://shop.example.com/");
+        set(&mut jar, "ip=1; Domain=0.0.1", "https://10.0.0.1/");
+        set(
+            &mut jar,
+            "forever=1; Max-Age=999999999999",
+            "https://shop.example.com/",
+        );
+
+        assert_eq!(
+            jar.cookie_header(&url("https://shop.example.com/"))
+                .as_deref(),
+            Some("sid=1; plain=1; __Host-c=1; forever=1")
+        );
+        assert_eq!(
+            jar.cookie_header(&url("http://shop.example.com/"))
+                .as_deref(),
+            Some("plain=1; forever=1")
+        );
+        assert_eq!(jar.cookie_header(&url("https://other.com/")), None);
+        let forever = jar
+            .cookies()
+            .into_iter()
+            .find(|c| c.name == "forever")
+            .unwrap();
+        assert!(forever.expires.unwrap() <= SystemTime::now() + MAX_COOKIE_AGE);
+
+        // A host that is a public suffix itself keeps its cookie to itself
+        set(&mut jar, "tld=1; Domain=localhost", "http://localhost/");
+        assert_eq!(
+            jar.cookie_header(&url("http://localhost/")).as_deref(),
+            Some("tld=1")
+        );
+
+        set(&mut jar, "region=1; Domain=co.uk", "https://shop.co.uk/");
+        let expected = if cfg!(feature = "public-suffix") {
+            None
+        } else {
+            Some("region=1")
+        };
+        assert_eq!(
+            jar.cookie_header(&url("https://bank.co.uk/")).as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_netscape_round_trip() {
+        let text = "# Netscape HTTP Cookie File\n\
+            .example.com\tTRUE\t/\tFALSE\t0\ta\t1\n\
+            #HttpOnly_www.example.com\tFALSE\t/app\tTRUE\t4102444800\tsid\tabc\n\
+            old.example.com\tFALSE\t/\tFALSE\t1\tgone\tx\n\
+            malformed line\n";
+        let jar = CookieJar::from_netscape(text);
+        assert_eq!(jar.cookies().len(), 2);
+        assert_eq!(
+            jar.cookie_header(&url("https://api.example.com/"))
+                .as_deref(),
+            Some("a=1")
+        );
+        assert_eq!(
+            jar.cookie_header(&url("https://www.example.com/app/x"))
+                .as_deref(),
+            Some("a=1; sid=abc")
+        );
+
+        let written = jar.to_netscape();
+        assert!(written.contains(".example.com\tTRUE\t/\tFALSE\t0\ta\t1\n"));
+        assert!(written
+            .contains("#HttpOnly_www.example.com\tFALSE\t/app\tTRUE\t4102444800\tsid\tabc\n"));
+        let reread = CookieJar::from_netscape(&written);
+        assert_eq!(reread.cookies(), jar.cookies());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::html::find_tags;
+use crate::types::{CuimpOptions, CuimpRequestConfig, CuimpResponse, Method};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use url::Url;
+
+/// Scope and limits of a crawl
+#[derive(Debug, Clone)]
+pub struct CrawlConfig {
+    pub seeds: Vec<String>,
+    /// Links followed from a seed (0 fetches only the seeds)
+    pub max_depth: u32,
+    /// Only follow links with the same origin as the seed they were reached from
+    pub same_origin: bool,
+    /// Regexes a URL must match at least one of, if any are given
+    pub include: Vec<String>,
+    /// Regexes that exclude a URL
+    pub exclude: Vec<String>,
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Minimum time between requests to the same host
+    pub per_host_delay_ms: u64,
+    /// Stop after this many pages
+    pub max_pages: Option<usize>,
+}
+
+impl Default for CrawlConfig {
+    fn default() -> Self {
+        CrawlConfig {
+            seeds: Vec::new(),
+            max_depth: 2,
+            same_origin: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            concurrency: 4,
+            per_host_delay_ms: 1000,
+            max_pages: None,
+        }
+    }
+}
+
+/// A fetched page passed to the crawl callback
+#[derive(Debug)]
+pub struct CrawledPage {
+    pub url: Url,
+    pub depth: u32,
+    pub response: Result<CuimpResponse<Value>>,
+    /// In-scope links found on the page, before de-duplication
+    pub links: Vec<Url>,
+}
+
+/// Totals of a finished crawl
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrawlStats {
+    pub fetched: usize,
+    pub failed: usize,
+    pub skipped_out_of_scope: usize,
+}
+
+/// Concurrency-limited recursive link crawler
+///
+/// Each concurrent slot has its own `CuimpHttp` built from the same options, so
+/// cookies are per slot.
+#[derive(Debug)]
+pub struct Crawler {
+    options: CuimpOptions,
+    config: CrawlConfig,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+struct Pending {
+    url: Url,
+    depth: u32,
+    /// Origin of the seed this URL was reached from
+    origin: url::Origin,
+}
+
+impl Crawler {
+    pub fn new(options: CuimpOptions, config: CrawlConfig) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| {
+                    Regex::new(p).map_err(|e| {
+                        CuimpError::InvalidConfig(format!("Invalid crawl pattern {:?}: {}", p, e))
+                    })
+                })
+                .collect()
+        };
+        if config.concurrency == 0 {
+            return Err(CuimpError::InvalidConfig(
+                "Crawl concurrency must be at least 1".to_string(),
+            ));
+        }
+
+        Ok(Crawler {
+            include: compile(&config.include)?,
+            exclude: compile(&config.exclude)?,
+            options,
+            config,
+        })
+    }
+
+    /// Crawl until the frontier is empty or `max_pages` is reached, calling `on_page` for every fetched page
+    pub async fn run<F>(&self, mut on_page: F) -> Result<CrawlStats>
+    where
+        F: FnMut(&CrawledPage),
+    {
+        let mut clients = (0..self.config.concurrency)
+            .map(|_| CuimpHttp::new(self.options.clone()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stats = CrawlStats::default();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut frontier: VecDeque<Pending> = VecDeque::new();
+        for seed in &self.config.seeds {
+            let url =
+                Url::parse(seed).map_err(|e| CuimpError::InvalidUrl(format!("{}: {}", seed, e)))?;
+            if seen.insert(url_key(&url)) {
+                frontier.push_back(Pending {
+                    origin: url.origin(),
+                    url,
+                    depth: 0,
+                });
+            }
+        }
+
+        let delay = Duration::from_millis(self.config.per_host_delay_ms);
+        let mut next_allowed: HashMap<String, Instant> = HashMap::new();
+        let mut in_flight = JoinSet::new();
+        let mut started = 0usize;
+
+        loop {
+            // Start every request a free client and the per-host delay allow
+            while self.config.max_pages.is_none_or(|max| started < max) && !clients.is_empty() {
+                let now = Instant::now();
+                let ready = frontier.iter().position(|p| {
+                    next_allowed
+                        .get(p.url.host_str().unwrap_or_default())
+                        .is_none_or(|at| *at <= now)
+                });
+                let Some(index) = ready else { break };
+                let Some(pending) = frontier.remove(index) else {
+                    break;
+                };
+                let Some(mut client) = clients.pop() else {
+                    break;
+                };
+
+                next_allowed.insert(
+                    pending.url.host_str().unwrap_or_default().to_string(),
+                    now + delay,
+                );
+                started += 1;
+                in_flight.spawn(async move {
+                    let config = CuimpRequestConfig {
+                        url: Some(pending.url.to_string()),
+                        method: Some(Method::GET),
+                        ..Default::default()
+                    };
+                    let response = client.request::<Value>(config).await;
+                    (client, pending, response)
+                });
+            }
+
+            // Earliest time a waiting, rate-limited URL may start
+            let can_start =
+                self.config.max_pages.is_none_or(|max| started < max) && !clients.is_empty();
+            let wake_at = frontier
+                .iter()
+                .filter_map(|p| next_allowed.get(p.url.host_str().unwrap_or_default()))
+                .min()
+                .copied()
+                .filter(|_| can_start);
+
+            if in_flight.is_empty() {
+                match wake_at {
+                    Some(at) => {
+                        tokio::time::sleep_until(at).await;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            // Wake up for the next finished request, or when a rate-limited host frees up
+            let finished = match wake_at {
+                Some(at) => tokio::select! {
+                    joined = in_flight.join_next() => joined,
+                    _ = tokio::time::sleep_until(at) => continue,
+                },
+                None => in_flight.join_next().await,
+            };
+            let Some(joined) = finished else { continue };
+            let (client, pending, response) =
+                joined.map_err(|e| CuimpError::Other(format!("Crawl task failed: {}", e)))?;
+            clients.push(client);
+
+            let links = match &response {
+                Ok(response) if pending.depth < self.config.max_depth => {
+                    let (links, skipped) = self.links_in_scope(response, &pending.origin);
+                    stats.skipped_out_of_scope += skipped;
+                    links
+                }
+                _ => Vec::new(),
+            };
+            if response.is_ok() {
+                stats.fetched += 1;
+            } else {
+                stats.failed += 1;
+            }
+
+            for link in &links {
+                if seen.insert(url_key(link)) {
+                    frontier.push_back(Pending {
+                        url: link.clone(),
+                        depth: pending.depth + 1,
+                        origin: pending.origin.clone(),
+                    });
+                }
+            }
+
+            on_page(&CrawledPage {
+                url: pending.url,
+                depth: pending.depth,
+                response,
+                links,
+            });
+        }
+
+        Ok(stats)
+    }
+
+    /// Links on an HTML page that fall within the crawl scope, and the number skipped
+    fn links_in_scope(
+        &self,
+        response: &CuimpResponse<Value>,
+        origin: &url::Origin,
+    ) -> (Vec<Url>, usize) {
+        let is_html = response
+            .header("content-type")
+            .is_none_or(|ct| ct.to_ascii_lowercase().contains("html"));
+        if !is_html {
+            return (Vec::new(), 0);
+        }
+
+        let html = String::from_utf8_lossy(&response.raw_body);
+        let mut skipped = 0;
+        let links = extract_links(&html, &response.request.url)
+            .into_iter()
+            .filter(|link| {
+                let in_scope = (!self.config.same_origin || link.origin() == *origin)
+                    && (self.include.is_empty()
+                        || self.include.iter().any(|r| r.is_match(link.as_str())))
+                    && !self.exclude.iter().any(|r| r.is_match(link.as_str()));
+                if !in_scope {
+                    skipped += 1;
+                }
+                in_scope
+            })
+            .collect();
+        (links, skipped)
+    }
+}
+
+/// Absolute http(s) links from `<a href>` and `<area href>`, without fragments
+fn extract_links(html: &str, base: &Url) -> Vec<Url> {
+    let base = find_tags(html, &["base"])
+        .first()
+        .and_then(|tag| tag.attr("href"))
+        .and_then(|href| base.join(href).ok())
+        .unwrap_or_else(|| base.clone());
+
+    find_tags(html, &["a", "area"])
+        .iter()
+        .filter(|tag| {
+            !tag.attr("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("nofollow"))
+            })
+        })
+        .filter_map(|tag| tag.attr("href"))
+        .filter_map(|href| base.join(href.trim()).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+/// De-duplication key: the normalized URL without fragment
+fn url_key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.into()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links() {
+        let base = Url::parse("https://example.com/docs/index.html").unwrap();
+        let html = r##"
+            <a href="intro.html#top">Intro</a>
+            <a href="/about">About</a>
+            <a href="https://other.com/">Other</a>
+            <a href="mailto:me@example.com">Mail</a>
+            <a href="/private" rel="nofollow">Private</a>
+        "##;
+        let links: Vec<String> = extract_links(html, &base)
+            .iter()
+            .map(Url::to_string)
+            .collect();
+        assert_eq!(
+            links,
+            [
+                "https://example.com/docs/intro.html",
+                "https://example.com/about",
+                "https://other.com/"
+            ]
+        );
+
+        assert_eq!(
+            url_key(&Url::parse("https://EXAMPLE.com:443/a#x").unwrap()),
+            "https://example.com/a"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::cookie::Cookie;
+use crate::html::find_tags;
+use crate::types::{CuimpRequestConfig, CuimpResponse};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Where a CSRF token was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsrfSource {
+    /// `<meta name="csrf-token" content="...">`
+    Meta,
+    /// `<input type="hidden" name="csrfmiddlewaretoken" value="...">`
+    HiddenInput,
+    /// `Set-Cookie: XSRF-TOKEN=...`
+    Cookie,
+}
+
+/// Names to look for and how to attach the token to a follow-up request
+#[derive(Debug, Clone)]
+pub struct CsrfConfig {
+    /// `<meta name=...>` values, case-insensitive
+    pub meta_names: Vec<String>,
+    /// Hidden `<input name=...>` values
+    pub field_names: Vec<String>,
+    /// Cookie names
+    pub cookie_names: Vec<String>,
+    /// Header the token is sent in
+    pub header_name: String,
+    /// Body field the token is sent in when the request has a JSON object body.
+    /// Defaults to the input name the token was found under.
+    pub field_name: Option<String>,
+}
+
+impl Default for CsrfConfig {
+    fn default() -> Self {
+        let names = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        CsrfConfig {
+            meta_names: names(&[
+                "csrf-token",
+                "csrf_token",
+                "_csrf",
+                "xsrf-token",
+                "csrf-param-token",
+            ]),
+            field_names: names(&[
+                "csrf_token",
+                "csrfmiddlewaretoken",
+                "_csrf",
+                "_token",
+                "authenticity_token",
+                "__RequestVerificationToken",
+            ]),
+            cookie_names: names(&["XSRF-TOKEN", "csrftoken", "_csrf", "csrf_token"]),
+            header_name: "X-CSRF-Token".to_string(),
+            field_name: None,
+        }
+    }
+}
+
+/// A CSRF token extracted from a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfToken {
+    /// Meta/input/cookie name the token was found under
+    pub name: String,
+    pub value: String,
+    pub source: CsrfSource,
+}
+
+impl CsrfToken {
+    /// Find a token in `html` and `cookies`, preferring meta tags, then hidden inputs, then cookies
+    pub fn find(html: &str, cookies: &[Cookie], config: &CsrfConfig) -> Option<CsrfToken> {
+        let matches =
+            |names: &[String], name: &str| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+        let tags = find_tags(html, &["meta", "input"]);
+
+        let meta = tags.iter().filter(|t| t.name == "meta").find_map(|t| {
+            let name = t
+                .attr("name")
+                .filter(|name| matches(&config.meta_names, name))?;
+            Some(CsrfToken {
+                name: name.to_string(),
+                value: t.attr("content")?.to_string(),
+                source: CsrfSource::Meta,
+            })
+        });
+
+        let input = || {
+            tags.iter().filter(|t| t.name == "input").find_map(|t| {
+                let name = t
+                    .attr("name")
+                    .filter(|name| matches(&config.field_names, name))?;
+                Some(CsrfToken {
+                    name: name.to_string(),
+                    value: t.attr("value")?.to_string(),
+                    source: CsrfSource::HiddenInput,
+                })
+            })
+        };
+
+        let cookie = || {
+            cookies
+                .iter()
+                .find(|c| matches(&config.cookie_names, &c.name))
+                .map(|c| CsrfToken {
+                    name: c.name.clone(),
+                    value: c.value.clone(),
+                    source: CsrfSource::Cookie,
+                })
+        };
+
+        meta.or_else(input)
+            .or_else(cookie)
+            .filter(|token| !token.value.is_empty())
+    }
+
+    /// Attach the token to a request: always as `config.header_name`, and as a body
+    /// field when the request body is a JSON object
+    pub fn apply(&self, request: &mut CuimpRequestConfig, config: &CsrfConfig) {
+        request
+            .headers
+            .get_or_insert_with(HashMap::new)
+            .insert(config.header_name.clone(), self.value.clone());
+
+        if let Some(Value::Object(body)) = request.data.as_mut() {
+            let field = config
+                .field_name
+                .clone()
+                .unwrap_or_else(|| match self.source {
+                    CsrfSource::HiddenInput => self.name.clone(),
+                    _ => config
+                        .field_names
+                        .first()
+                        .cloned()
+                        .unwrap_or_else(|| self.name.clone()),
+                });
+            body.insert(field, Value::String(self.value.clone()));
+        }
+    }
+}
+
+impl<T> CuimpResponse<T> {
+    /// CSRF token from this response using the default names
+    pub fn csrf_token(&self) -> Option<CsrfToken> {
+        self.csrf_token_with(&CsrfConfig::default())
+    }
+
+    /// CSRF token from this response using custom names
+    pub fn csrf_token_with(&self, config: &CsrfConfig) -> Option<CsrfToken> {
+        CsrfToken::find(
+            &String::from_utf8_lossy(&self.raw_body),
+            &self.cookies,
+            config,
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_and_apply() {
+        let config = CsrfConfig::default();
+        let html = r#"<form><input type="hidden" name="csrfmiddlewaretoken" value="abc"></form>"#;
+        let token = CsrfToken::find(html, &[], &config).unwrap();
+        assert_eq!(token.source, CsrfSource::HiddenInput);
+
+        let with_meta = format!(r#"<meta name="CSRF-Token" content="meta-token">{}"#, html);
+        assert_eq!(
+            CsrfToken::find(&with_meta, &[], &config).unwrap().value,
+            "meta-token"
+        );
+
+        let cookies = [
+            Cookie::new("session", "s1"),
+            Cookie::parse("XSRF-TOKEN=cookie-token; Path=/").unwrap(),
+        ];
+        let from_cookie = CsrfToken::find("<p>", &cookies, &config).unwrap();
+        assert_eq!(
+            (from_cookie.value.as_str(), from_cookie.source),
+            ("cookie-token", CsrfSource::Cookie)
+        );
+
+        let mut request = CuimpRequestConfig {
+            data: Some(json!({ "name": "x" })),
+            ..Default::default()
+        };
+        token.apply(&mut request, &config);
+        assert_eq!(request.headers.unwrap()["X-CSRF-Token"], "abc");
+        assert_eq!(
+            request.data,
+            Some(json!({ "name": "x", "csrfmiddlewaretoken": "abc" }))
+        );
+    }
+}
@@ -1,25 +1,36 @@
+use crate::curl_command::shell_join;
 use crate::error::{CuimpError, Result};
-use crate::parser::parse_descriptor;
-use crate::types::{BinaryInfo, CuimpDescriptor, CuimpOptions};
+use crate::events::CuimpEvent;
+use crate::parser::{
+    detect_available_targets, list_installed_binaries, parse_descriptor, parse_descriptor_cached,
+    prune_installed, remove_installed, ResolveOptions,
+};
+use crate::redact::Redaction;
+use crate::runner::{run_binary, RunResult};
+use crate::types::{BinaryInfo, CuimpDescriptor, CuimpOptions, InstalledBinary};
 use crate::validation::validate_descriptor;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Core Cuimp struct for managing curl-impersonate binaries
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Cuimp {
     descriptor: CuimpDescriptor,
     path: Option<String>,
     binary_info: Option<BinaryInfo>,
+    redaction: Redaction,
+    resolve: ResolveOptions,
 }
 
 impl Cuimp {
     /// Create a new Cuimp instance
     pub fn new(options: CuimpOptions) -> Result<Self> {
+        let resolve = ResolveOptions::from(&options);
         let descriptor = options.descriptor.unwrap_or_default();
 
         // Validate descriptor if provided
         if descriptor.browser.is_some()
             || descriptor.version.is_some()
+            || descriptor.release.is_some()
             || descriptor.architecture.is_some()
             || descriptor.platform.is_some()
         {
@@ -30,6 +41,8 @@ impl Cuimp {
             descriptor,
             path: options.path,
             binary_info: None,
+            redaction: options.redaction.unwrap_or_default(),
+            resolve,
         })
     }
 
@@ -42,13 +55,13 @@ impl Cuimp {
             }
         }
 
-        // Parse descriptor to get binary info
-        self.binary_info = Some(parse_descriptor(&self.descriptor).await?);
+        // Parse descriptor to get binary info, shared with other instances in this process
+        self.binary_info = Some(parse_descriptor_cached(&self.descriptor, &self.resolve).await?);
 
         let binary_path = self
             .binary_info
             .as_ref()
-            .and_then(|info| Some(info.binary_path.clone()))
+            .map(|info| info.binary_path.clone())
             .ok_or_else(|| CuimpError::BinaryNotFound("Binary path not found".to_string()))?;
 
         // Verify the binary is executable
@@ -59,15 +72,9 @@ impl Cuimp {
         // Update the path
         self.path = Some(binary_path.clone());
 
-        println!("Binary verified: {}", binary_path);
-        if let Some(info) = &self.binary_info {
-            if info.is_downloaded {
-                println!(
-                    "Binary downloaded successfully (version: {})",
-                    info.version.as_deref().unwrap_or("unknown")
-                );
-            }
-        }
+        self.resolve.events.emit(CuimpEvent::BinaryVerified {
+            path: binary_path.clone(),
+        });
 
         Ok(binary_path)
     }
@@ -108,9 +115,64 @@ impl Cuimp {
             return Err(CuimpError::Other("Method must be non-empty".to_string()));
         }
 
-        let command = format!("{} -X {} \"{}\"", binary_path, method.to_uppercase(), url);
-        println!("Command preview: {}", command);
-        Ok(command)
+        Ok(format!(
+            "{} -X {} \"{}\"",
+            binary_path,
+            method.to_uppercase(),
+            url
+        ))
+    }
+
+    /// List the impersonation targets (e.g. `chrome124`, `tor145`) provided by installed releases
+    pub fn available_targets(&self) -> Vec<String> {
+        detect_available_targets(&self.resolve.binaries_dir)
+    }
+
+    /// Binaries and wrapper scripts in the binaries directory and system search paths
+    pub fn installed_binaries(&self) -> Vec<InstalledBinary> {
+        list_installed_binaries(&self.resolve.binaries_dir)
+    }
+
+    /// Run the managed binary with arbitrary arguments, for curl features the typed API
+    /// doesn't cover. The binary is resolved (and downloaded if needed) like for requests.
+    pub async fn exec(&mut self, args: &[String], timeout_ms: Option<u64>) -> Result<RunResult> {
+        let binary_path = self.ensure_path().await?;
+        self.resolve.events.emit(CuimpEvent::CommandSpawned {
+            command: shell_join(&binary_path, &self.redaction.redact_args(args)),
+        });
+        run_binary(&binary_path, args, timeout_ms).await
+    }
+
+    /// Delete a target from the binaries directory and return the removed files.
+    ///
+    /// `target` is a wrapper (`chrome124`), a browser whose wrappers are all removed
+    /// (`chrome`), or `curl` for the main curl-impersonate binary. System paths are never touched.
+    pub fn remove_binary(&mut self, target: &str) -> Result<Vec<PathBuf>> {
+        let removed = remove_installed(&self.resolve.binaries_dir, target)?;
+        self.forget_removed(&removed);
+        Ok(removed)
+    }
+
+    /// Delete outdated installs from the binaries directory and return the removed files.
+    ///
+    /// With `keep_latest`, only the newest wrapper of each browser and the main binary are
+    /// kept; otherwise the binaries directory is emptied.
+    pub fn prune(&mut self, keep_latest: bool) -> Result<Vec<PathBuf>> {
+        let removed = prune_installed(&self.resolve.binaries_dir, keep_latest)?;
+        self.forget_removed(&removed);
+        Ok(removed)
+    }
+
+    /// Drop the cached binary path if it was deleted
+    fn forget_removed(&mut self, removed: &[PathBuf]) {
+        if self
+            .path
+            .as_ref()
+            .is_some_and(|path| removed.iter().any(|r| r == Path::new(path)))
+        {
+            self.path = None;
+            self.binary_info = None;
+        }
     }
 
     /// Get the current binary path
@@ -123,6 +185,11 @@ impl Cuimp {
         &self.descriptor
     }
 
+    /// Impersonation target as `browser version` (e.g. `chrome 123`), if a browser is set
+    pub fn fingerprint(&self) -> Option<String> {
+        self.descriptor.target()
+    }
+
     /// Get binary information if available
     pub fn get_binary_info(&self) -> Option<&BinaryInfo> {
         self.binary_info.as_ref()
@@ -153,6 +220,7 @@ impl Cuimp {
         // Validate descriptor if provided
         if self.descriptor.browser.is_some()
             || self.descriptor.version.is_some()
+            || self.descriptor.release.is_some()
             || self.descriptor.architecture.is_some()
             || self.descriptor.platform.is_some()
         {
@@ -160,35 +228,39 @@ impl Cuimp {
         }
 
         // Parse descriptor to download binary
-        self.binary_info = Some(parse_descriptor(&self.descriptor).await?);
+        self.binary_info = Some(parse_descriptor(&self.descriptor, &self.resolve).await?);
 
         let binary_info = self
             .binary_info
             .ok_or_else(|| CuimpError::BinaryNotFound("Binary info not available".to_string()))?;
 
-        println!("Binary ready: {}", binary_info.binary_path);
-        if binary_info.is_downloaded {
-            println!(
-                "Download completed (version: {})",
-                binary_info.version.as_deref().unwrap_or("unknown")
-            );
-        } else {
-            println!(
-                "Using existing binary (version: {})",
-                binary_info.version.as_deref().unwrap_or("unknown")
-            );
-        }
-
         Ok(binary_info)
     }
+
+    /// `download`, fetching the release archive again even when a binary is installed
+    pub async fn download_force(mut self) -> Result<BinaryInfo> {
+        self.resolve.force = true;
+        self.download().await
+    }
 }
 
-impl Default for Cuimp {
-    fn default() -> Self {
-        Cuimp {
-            descriptor: CuimpDescriptor::default(),
-            path: None,
-            binary_info: None,
-        }
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_exec_uses_managed_binary() {
+        let mut cuimp = Cuimp::new(CuimpOptions {
+            path: Some("/bin/echo".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        let result = cuimp
+            .exec(&["--version-check".to_string()], Some(5000))
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.stdout, b"--version-check\n");
     }
 }
@@ -0,0 +1,458 @@
+use crate::client::{encode_params, join_url, literal_url, set_raw_query, url_args};
+use crate::error::{CuimpError, Result};
+use crate::into_url::IntoUrl;
+use crate::redirect::RedirectPolicy;
+use crate::types::{CuimpRequestConfig, Method};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Quote a single argument for POSIX shells
+pub(crate) fn shell_quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+    if is_safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Render a program and its arguments as a copy-pasteable shell command
+pub(crate) fn shell_join(program: &str, args: &[String]) -> String {
+    std::iter::once(shell_quote(program))
+        .chain(args.iter().map(|arg| shell_quote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split a shell command line into words.
+///
+/// Supports single quotes, double quotes, ANSI-C `$'...'` quoting (as emitted by
+/// Chrome's "Copy as cURL"), backslash escapes and line continuations.
+pub(crate) fn shell_split(input: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(escaped) => {
+                    current.push(escaped);
+                    in_word = true;
+                }
+                None => return Err(CuimpError::ParseError("Trailing backslash".to_string())),
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => current.push(ch),
+                        None => {
+                            return Err(CuimpError::ParseError(
+                                "Unterminated single quote".to_string(),
+                            ))
+                        }
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(ch @ ('"' | '\\' | '$' | '`')) => current.push(ch),
+                            Some('\n') => {}
+                            Some(ch) => {
+                                current.push('\\');
+                                current.push(ch);
+                            }
+                            None => break,
+                        },
+                        Some(ch) => current.push(ch),
+                        None => {
+                            return Err(CuimpError::ParseError(
+                                "Unterminated double quote".to_string(),
+                            ))
+                        }
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                parse_ansi_c_quoted(&mut chars, &mut current)?;
+            }
+            other => {
+                current.push(other);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+fn parse_ansi_c_quoted(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    out: &mut String,
+) -> Result<()> {
+    let unterminated = || CuimpError::ParseError("Unterminated $'...' quote".to_string());
+    loop {
+        match chars.next().ok_or_else(unterminated)? {
+            '\'' => return Ok(()),
+            '\\' => match chars.next().ok_or_else(unterminated)? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                '0' => out.push('\0'),
+                'x' => out.push(read_hex_char(chars, 2)?),
+                'u' => out.push(read_hex_char(chars, 4)?),
+                'U' => out.push(read_hex_char(chars, 8)?),
+                other => out.push(other),
+            },
+            other => out.push(other),
+        }
+    }
+}
+
+fn read_hex_char(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    max_digits: usize,
+) -> Result<char> {
+    let mut digits = String::new();
+    while digits.len() < max_digits {
+        match chars.peek() {
+            Some(c) if c.is_ascii_hexdigit() => {
+                digits.push(*c);
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| CuimpError::ParseError(format!("Invalid escape sequence: {}", digits)))
+}
+
+impl CuimpRequestConfig {
+    /// Parse a curl command line (e.g. a browser's "Copy as cURL" output) into a request config.
+    ///
+    /// Options that have no typed equivalent but do not take a value
+    /// (such as `--compressed`) are kept in `extra_curl_args`; unknown options
+    /// that may take a value are rejected.
+    pub fn from_curl_command(command: &str) -> Result<Self> {
+        let words = shell_split(command)?;
+        let mut words = words.into_iter();
+
+        match words.next() {
+            Some(program)
+                if program
+                    .rsplit('/')
+                    .next()
+                    .is_some_and(|name| name.starts_with("curl")) => {}
+            _ => {
+                return Err(CuimpError::ParseError(
+                    "Command must start with curl".to_string(),
+                ))
+            }
+        }
+
+        let mut config = CuimpRequestConfig::default();
+        let mut headers: HashMap<String, String> = HashMap::new();
+        let mut body: Option<String> = None;
+        let mut extra_args = Vec::new();
+
+        while let Some(word) = words.next() {
+            // Support --option=value
+            let (flag, inline_value) = match word.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (word.clone(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| words.next())
+                    .ok_or_else(|| CuimpError::ParseError(format!("Missing value for {}", flag)))
+            };
+
+            match flag.as_str() {
+                "-X" | "--request" => config.method = Some(value()?.parse()?),
+                "-H" | "--header" => {
+                    let header = value()?;
+                    if let Some((key, val)) = header.split_once(':') {
+                        headers.insert(key.trim().to_string(), val.trim().to_string());
+                    }
+                }
+                "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => {
+                    let data = value()?;
+                    body = Some(match body {
+                        Some(existing) => format!("{}&{}", existing, data),
+                        None => data,
+                    });
+                }
+                "-b" | "--cookie" => {
+                    headers.insert("Cookie".to_string(), value()?);
+                }
+                "-A" | "--user-agent" => {
+                    headers.insert("User-Agent".to_string(), value()?);
+                }
+                "-e" | "--referer" => {
+                    headers.insert("Referer".to_string(), value()?);
+                }
+                "-x" | "--proxy" => config.proxy = Some(value()?),
+                "-k" | "--insecure" => config.insecure_tls = Some(true),
+                "--max-redirs" => {
+                    config.redirect =
+                        Some(RedirectPolicy::Limited(value()?.parse().map_err(|_| {
+                            CuimpError::ParseError("Invalid --max-redirs value".to_string())
+                        })?))
+                }
+                "-m" | "--max-time" => {
+                    let seconds: f64 = value()?.parse().map_err(|_| {
+                        CuimpError::ParseError("Invalid --max-time value".to_string())
+                    })?;
+                    config.timeout = Some((seconds * 1000.0) as u64);
+                }
+                "--url" => config.url = Some(value()?),
+                "-v" | "--verbose" => config.debug = true,
+                "-L" | "--location" | "-s" | "--silent" | "-S" | "--show-error" | "-i"
+                | "--include" | "-g" | "--globoff" | "--path-as-is" => {}
+                "--compressed"
+                | "--http1.1"
+                | "--http2"
+                | "--http2-prior-knowledge"
+                | "--tlsv1.2"
+                | "--tlsv1.3" => extra_args.push(flag.clone()),
+                other if other.starts_with('-') && other.len() > 1 => {
+                    return Err(CuimpError::ParseError(format!(
+                        "Unsupported curl option: {}",
+                        other
+                    )))
+                }
+                _ => config.url = Some(word),
+            }
+        }
+
+        if config.url.is_none() {
+            return Err(CuimpError::ParseError(
+                "No URL found in curl command".to_string(),
+            ));
+        }
+        if config.method.is_none() && body.is_some() {
+            config.method = Some(Method::POST);
+        }
+        if !headers.is_empty() {
+            config.headers = Some(headers);
+        }
+        config.data = body.map(Value::String);
+        if !extra_args.is_empty() {
+            config.extra_curl_args = Some(extra_args);
+        }
+
+        Ok(config)
+    }
+
+    /// Render this config as a shell-safe `curl` command line.
+    ///
+    /// The output round-trips through [`CuimpRequestConfig::from_curl_command`].
+    pub fn to_curl_command(&self) -> Result<String> {
+        let raw_url = self
+            .url
+            .as_ref()
+            .ok_or_else(|| CuimpError::InvalidUrl("URL is required".to_string()))?;
+        let mut url = match &self.base_url {
+            Some(base) => join_url(base, raw_url)?,
+            None => raw_url.as_str().into_url()?,
+        };
+        let mut url_text = literal_url(raw_url);
+        if let Some(query) = &self.raw_query {
+            url_text = Some(set_raw_query(&mut url, url_text.take(), query)?);
+        }
+        if let Some(params) = &self.params {
+            encode_params(&mut url, url_text.as_mut(), params);
+        }
+
+        let mut args: Vec<String> = Vec::new();
+        if let Some(method) = self.method {
+            args.push("-X".to_string());
+            args.push(method.to_string());
+        }
+
+        let mut headers: Vec<_> = self.headers.iter().flatten().collect();
+        headers.sort();
+        for (key, value) in headers {
+            args.push("-H".to_string());
+            args.push(format!("{}: {}", key, value));
+        }
+
+        if let Some(body) = &self.body {
+            let text = std::str::from_utf8(body).map_err(|_| {
+                CuimpError::InvalidConfig(
+                    "A binary body cannot be written as a curl command".to_string(),
+                )
+            })?;
+            args.push("--data-binary".to_string());
+            args.push(text.to_string());
+        } else if let Some(data) = &self.data {
+            args.push("--data-raw".to_string());
+            args.push(match data.as_str() {
+                Some(text) => text.to_string(),
+                None => serde_json::to_string(data)?,
+            });
+        }
+
+        if let Some(proxy) = &self.proxy {
+            args.push("--proxy".to_string());
+            args.push(proxy.clone());
+        }
+        if self.insecure_tls.unwrap_or(false) {
+            args.push("--insecure".to_string());
+        }
+        match &self.redirect {
+            Some(RedirectPolicy::Limited(max)) => {
+                args.push("--max-redirs".to_string());
+                args.push(max.to_string());
+            }
+            Some(RedirectPolicy::None) => {
+                args.push("--max-redirs".to_string());
+                args.push("0".to_string());
+            }
+            Some(RedirectPolicy::Custom(_)) => {
+                return Err(CuimpError::InvalidConfig(
+                    "A custom redirect policy cannot be written as a curl command".to_string(),
+                ))
+            }
+            None => {}
+        }
+        if let Some(timeout) = self.timeout {
+            args.push("--max-time".to_string());
+            args.push(format!("{}", timeout as f64 / 1000.0));
+        }
+        if self.debug {
+            args.push("--verbose".to_string());
+        }
+        if let Some(extra_args) = &self.extra_curl_args {
+            args.extend_from_slice(extra_args);
+        }
+        args.extend(url_args(&url, url_text));
+
+        Ok(shell_join("curl", &args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(
+            shell_quote("https://example.com/a"),
+            "https://example.com/a"
+        );
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(
+            shell_split(&shell_quote("it's $HOME")).unwrap(),
+            vec!["it's $HOME"]
+        );
+    }
+
+    #[test]
+    fn test_from_chrome_copy_as_curl() {
+        let command = "curl 'https://example.com/api?x=1' \\\n  -H 'accept: application/json' \\\n  -b 'sid=abc; theme=dark' \\\n  --data-raw $'{\"msg\":\"it\\'s\\\\n\"}' \\\n  --compressed";
+        let config = CuimpRequestConfig::from_curl_command(command).unwrap();
+        assert_eq!(config.url.as_deref(), Some("https://example.com/api?x=1"));
+        assert_eq!(config.method, Some(Method::POST));
+        let headers = config.headers.unwrap();
+        assert_eq!(
+            headers.get("accept").map(String::as_str),
+            Some("application/json")
+        );
+        assert_eq!(
+            headers.get("Cookie").map(String::as_str),
+            Some("sid=abc; theme=dark")
+        );
+        assert_eq!(
+            config.data,
+            Some(Value::String("{\"msg\":\"it's\\n\"}".to_string()))
+        );
+        assert_eq!(
+            config.extra_curl_args,
+            Some(vec!["--compressed".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_curl_command_round_trip() {
+        let config = CuimpRequestConfig {
+            url: Some("https://example.com/it's%20here".to_string()),
+            method: Some(Method::PUT),
+            headers: Some(HashMap::from([(
+                "X-Quote".to_string(),
+                "a 'b' \"c\"".to_string(),
+            )])),
+            data: Some(Value::String("body with spaces".to_string())),
+            timeout: Some(1500),
+            ..Default::default()
+        };
+        let parsed =
+            CuimpRequestConfig::from_curl_command(&config.to_curl_command().unwrap()).unwrap();
+        assert_eq!(
+            parsed.url.as_deref(),
+            Some("https://example.com/it's%20here")
+        );
+        assert_eq!(parsed.method, config.method);
+        assert_eq!(parsed.headers, config.headers);
+        assert_eq!(parsed.data, config.data);
+        assert_eq!(parsed.timeout, config.timeout);
+
+        let raw = CuimpRequestConfig {
+            url: Some("https://example.com/q?ids=[1,2]".to_string()),
+            raw_query: Some("ids=[1,2]&sig=a%2Fb".to_string()),
+            ..Default::default()
+        };
+        let command = raw.to_curl_command().unwrap();
+        assert_eq!(
+            command,
+            "curl --globoff 'https://example.com/q?ids=[1,2]&sig=a%2Fb'"
+        );
+        let parsed = CuimpRequestConfig::from_curl_command(&command).unwrap();
+        assert_eq!(
+            parsed.url.as_deref(),
+            Some("https://example.com/q?ids=[1,2]&sig=a%2Fb")
+        );
+    }
+
+    #[test]
+    fn test_from_curl_command_rejects_unknown_options() {
+        assert!(
+            CuimpRequestConfig::from_curl_command("curl --unknown-flag x https://a.b").is_err()
+        );
+        assert!(CuimpRequestConfig::from_curl_command("wget https://a.b").is_err());
+        assert!(CuimpRequestConfig::from_curl_command("curl 'unterminated").is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,9 +12,23 @@ pub enum CuimpError {
     #[error("Download failed: {0}")]
     DownloadFailed(String),
 
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+
+    /// No SHA-256 is known for a downloaded release archive and
+    /// `CuimpDescriptor::allow_unverified` is off
+    #[error("Checksum unknown: {0}")]
+    ChecksumUnknown(String),
+
+    #[error("Signature verification failed: {0}")]
+    SignatureInvalid(String),
+
     #[error("Extraction failed: {0}")]
     ExtractionFailed(String),
 
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
     #[error("Invalid descriptor: {0}")]
     InvalidDescriptor(String),
 
@@ -32,6 +47,19 @@ pub enum CuimpError {
     #[error("Timeout: {0}")]
     Timeout(String),
 
+    /// `CuimpRequestConfig::deadline` (in milliseconds) passed during the phase
+    #[error("Deadline of {1} ms exceeded while {0}")]
+    DeadlineExceeded(RequestPhase, u64),
+
+    #[error("Request headers too large: {0}")]
+    HeadersTooLarge(String),
+
+    #[error("Request signing failed: {0}")]
+    SigningFailed(String),
+
+    #[error("Redirect rejected: {0}")]
+    RedirectRejected(String),
+
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
@@ -47,6 +75,9 @@ pub enum CuimpError {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("Blocked by {0}")]
+    Blocked(crate::challenge::Blocked),
+
     #[error("Parse error: {0}")]
     ParseError(String),
 
@@ -55,3 +86,69 @@ pub enum CuimpError {
 }
 
 pub type Result<T> = std::result::Result<T, CuimpError>;
+
+impl CuimpError {
+    /// The same error for another request that shared its cause; wrapped I/O and JSON
+    /// errors keep their kind and message
+    pub(crate) fn duplicate(&self) -> CuimpError {
+        match self {
+            CuimpError::BinaryNotFound(s) => CuimpError::BinaryNotFound(s.clone()),
+            CuimpError::BinaryNotExecutable(s) => CuimpError::BinaryNotExecutable(s.clone()),
+            CuimpError::DownloadFailed(s) => CuimpError::DownloadFailed(s.clone()),
+            CuimpError::ChecksumMismatch(s) => CuimpError::ChecksumMismatch(s.clone()),
+            CuimpError::ChecksumUnknown(s) => CuimpError::ChecksumUnknown(s.clone()),
+            CuimpError::SignatureInvalid(s) => CuimpError::SignatureInvalid(s.clone()),
+            CuimpError::ExtractionFailed(s) => CuimpError::ExtractionFailed(s.clone()),
+            CuimpError::InvalidConfig(s) => CuimpError::InvalidConfig(s.clone()),
+            CuimpError::InvalidDescriptor(s) => CuimpError::InvalidDescriptor(s.clone()),
+            CuimpError::UnsupportedBrowser(s) => CuimpError::UnsupportedBrowser(s.clone()),
+            CuimpError::UnsupportedPlatform(s) => CuimpError::UnsupportedPlatform(s.clone()),
+            CuimpError::UnsupportedArchitecture(s) => {
+                CuimpError::UnsupportedArchitecture(s.clone())
+            }
+            CuimpError::RequestFailed(s) => CuimpError::RequestFailed(s.clone()),
+            CuimpError::Timeout(s) => CuimpError::Timeout(s.clone()),
+            CuimpError::DeadlineExceeded(phase, ms) => CuimpError::DeadlineExceeded(*phase, *ms),
+            CuimpError::HeadersTooLarge(s) => CuimpError::HeadersTooLarge(s.clone()),
+            CuimpError::SigningFailed(s) => CuimpError::SigningFailed(s.clone()),
+            CuimpError::RedirectRejected(s) => CuimpError::RedirectRejected(s.clone()),
+            CuimpError::InvalidUrl(s) => CuimpError::InvalidUrl(s.clone()),
+            CuimpError::InvalidResponse(s) => CuimpError::InvalidResponse(s.clone()),
+            CuimpError::IoError(e) => {
+                CuimpError::IoError(std::io::Error::new(e.kind(), e.to_string()))
+            }
+            CuimpError::HttpError(s) => CuimpError::HttpError(s.clone()),
+            CuimpError::JsonError(e) => CuimpError::JsonError(serde::de::Error::custom(e)),
+            CuimpError::Blocked(blocked) => CuimpError::Blocked(blocked.clone()),
+            CuimpError::ParseError(s) => CuimpError::ParseError(s.clone()),
+            CuimpError::Other(s) => CuimpError::Other(s.clone()),
+        }
+    }
+}
+
+/// What a request was doing when its deadline passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestPhase {
+    /// Finding, downloading or extracting the curl-impersonate binary
+    ResolveBinary,
+    /// Waiting for a domain profile's rate or concurrency limit before starting curl
+    Throttle,
+    /// Launching curl, when the deadline passed before the process could be started
+    Spawn,
+    /// curl running: connecting, sending the request and receiving the response
+    Transfer,
+    /// `CuimpOptions::on_unauthorized` renewing credentials after a 401
+    RefreshCredentials,
+}
+
+impl fmt::Display for RequestPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RequestPhase::ResolveBinary => "resolving the curl-impersonate binary",
+            RequestPhase::Throttle => "waiting for the domain's rate or concurrency limit",
+            RequestPhase::Spawn => "starting curl",
+            RequestPhase::Transfer => "transferring",
+            RequestPhase::RefreshCredentials => "refreshing credentials",
+        })
+    }
+}
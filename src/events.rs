@@ -0,0 +1,133 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Something cuimp did or noticed, delivered to `CuimpOptions::on_event` instead of
+/// being printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CuimpEvent {
+    /// An installed binary was found for the descriptor
+    BinaryFound {
+        path: String,
+        version: Option<String>,
+    },
+    /// Another process or task is downloading into the binaries directory; waiting for it
+    DownloadWaiting { binaries_dir: PathBuf },
+    /// Downloading a release archive
+    DownloadStarted { url: String },
+    /// Bytes of the archive received so far; `total` is its `Content-Length`, if sent
+    DownloadProgress { downloaded: u64, total: Option<u64> },
+    /// No checksum is known for the archive and `allow_unverified` installed it anyway
+    ChecksumUnavailable { asset: String },
+    /// The archive was unpacked into `binaries_dir`; `path` is the binary to use
+    Extracted { binaries_dir: PathBuf, path: String },
+    /// The binary is executable and ready for requests
+    BinaryVerified { path: String },
+    /// The latest release could not be looked up, so the cached tag is used
+    ReleaseLookupFailed { error: String, cached_tag: String },
+    /// The resolved latest release could not be written to the metadata cache
+    ReleaseCacheWriteFailed { error: String },
+    /// curl is about to run; `command` is redacted like `RequestInfo::command`
+    CommandSpawned { command: String },
+    /// Request headers are `size` bytes, above the common server limit `limit`;
+    /// expect 431 Request Header Fields Too Large
+    HeadersTooLarge { size: usize, limit: usize },
+}
+
+/// Receives the events of a `Cuimp` or `CuimpHttp`, e.g. to log them or show download
+/// progress.
+///
+/// Called on the task doing the work, so implementations should not block.
+/// Closures taking `&CuimpEvent` implement this trait.
+pub trait EventHandler: Send + Sync {
+    fn handle(&self, event: &CuimpEvent);
+}
+
+impl<F> EventHandler for F
+where
+    F: Fn(&CuimpEvent) + Send + Sync,
+{
+    fn handle(&self, event: &CuimpEvent) {
+        self(event)
+    }
+}
+
+impl fmt::Debug for dyn EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventHandler(..)")
+    }
+}
+
+/// The optional handler events go to
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Events(pub Option<Arc<dyn EventHandler>>);
+
+impl Events {
+    pub fn emit(&self, event: CuimpEvent) {
+        if let Some(handler) = &self.0 {
+            handler.handle(&event);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::client::CuimpHttp;
+    use crate::fake_curl::FakeCurl;
+    use crate::types::{Browser, CuimpDescriptor, CuimpOptions, CuimpRequestConfig};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_event_handler() {
+        let curl = FakeCurl::new(
+            "events",
+            "#!/bin/sh\n[ \"$1\" = --version ] && echo 'curl 8.13.0 (x86_64-pc-linux-gnu)' && exit\n\
+             printf 'HTTP/1.1 200 OK\\r\\n\\r\\nok'\n",
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handler = Arc::clone(&events);
+        let mut client = CuimpHttp::new(CuimpOptions {
+            descriptor: Some(CuimpDescriptor {
+                browser: Some(Browser::Chrome),
+                version: Some("124".to_string()),
+                ..Default::default()
+            }),
+            binaries_dir: Some(curl.dir().to_string_lossy().into_owned()),
+            on_event: Some(Arc::new(move |event: &CuimpEvent| {
+                handler.lock().unwrap().push(event.clone())
+            })),
+            ..Default::default()
+        })
+        .unwrap();
+        let response = client
+            .request_raw(CuimpRequestConfig {
+                url: Some("https://example.com/".to_string()),
+                headers: Some(HashMap::from([(
+                    "Authorization".to_string(),
+                    "Bearer secret".to_string(),
+                )])),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(response.unwrap().data, b"ok");
+
+        let path = curl.path();
+        let events = events.lock().unwrap();
+        let [found, verified, CuimpEvent::CommandSpawned { command }] = events.as_slice() else {
+            panic!("unexpected events: {:?}", events)
+        };
+        assert_eq!(
+            found,
+            &CuimpEvent::BinaryFound {
+                path: path.clone(),
+                version: Some("8.13.0".to_string())
+            }
+        );
+        assert_eq!(verified, &CuimpEvent::BinaryVerified { path });
+        assert!(command.contains("Authorization: [REDACTED]") && !command.contains("secret"));
+    }
+}
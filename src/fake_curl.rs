@@ -0,0 +1,73 @@
+//! Fake curl binaries for the unit tests
+#![allow(clippy::unwrap_used)]
+
+use crate::client::CuimpHttp;
+use crate::types::CuimpOptions;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes directories of tests running in the same process
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A shell script installed as `curl_chrome124` in a temporary directory.
+///
+/// The directory is removed on drop, so a failing test does not leave it behind.
+pub(crate) struct FakeCurl {
+    dir: PathBuf,
+    script: PathBuf,
+}
+
+impl FakeCurl {
+    /// Install `script` in a new directory named after `name`
+    pub(crate) fn new(name: &str, script: &str) -> FakeCurl {
+        FakeCurl::with_dir(name, |_| script.to_string())
+    }
+
+    /// Install the script built from the directory's path, for scripts that keep state in it
+    pub(crate) fn with_dir(name: &str, script: impl FnOnce(&Path) -> String) -> FakeCurl {
+        let dir = std::env::temp_dir().join(format!(
+            "cuimp-{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("curl_chrome124");
+        std::fs::write(&path, script(&dir)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        FakeCurl { dir, script: path }
+    }
+
+    /// The directory holding the script
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The script's path, as given in `CuimpOptions::path`
+    pub(crate) fn path(&self) -> String {
+        self.script.to_string_lossy().into_owned()
+    }
+
+    /// A client running the script, with `options` otherwise
+    pub(crate) fn client(&self, options: CuimpOptions) -> CuimpHttp {
+        CuimpHttp::new(CuimpOptions {
+            path: Some(self.path()),
+            ..options
+        })
+        .unwrap()
+    }
+}
+
+impl Drop for FakeCurl {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A client running `script` with default options, and the fake curl to keep alive alongside it
+pub(crate) fn fake_client(name: &str, script: &str) -> (CuimpHttp, FakeCurl) {
+    let curl = FakeCurl::new(name, script);
+    (curl.client(CuimpOptions::default()), curl)
+}
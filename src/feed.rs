@@ -0,0 +1,451 @@
+//! RSS, Atom, JSON Feed and sitemap parsing with conditional polling.
+
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::html::decode_entities;
+use crate::types::{CuimpRequestConfig, Method};
+use futures_core::Stream;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Format a feed document was parsed as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    Rss,
+    Atom,
+    JsonFeed,
+    /// `<urlset>` or `<sitemapindex>`
+    Sitemap,
+}
+
+/// One item of a feed or one URL of a sitemap
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedEntry {
+    /// `guid`/`id` when present, otherwise the link
+    pub id: String,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    /// Publication date as written in the feed
+    pub published: Option<String>,
+    /// Last modification date as written in the feed (`lastmod` for sitemaps)
+    pub updated: Option<String>,
+    pub summary: Option<String>,
+}
+
+/// A parsed feed document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feed {
+    pub kind: FeedKind,
+    pub title: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+impl Feed {
+    /// Parse an RSS, Atom, JSON Feed or sitemap document
+    pub fn parse(body: &[u8]) -> Result<Feed> {
+        let text = String::from_utf8_lossy(body);
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with('{') {
+            parse_json_feed(text)
+        } else {
+            parse_xml_feed(text)
+        }
+    }
+}
+
+fn parse_json_feed(text: &str) -> Result<Feed> {
+    let doc: Value = serde_json::from_str(text)?;
+    let string = |value: &Value, key: &str| match value.get(key) {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+
+    let entries = doc
+        .get("items")
+        .and_then(Value::as_array)
+        .ok_or_else(|| CuimpError::ParseError("JSON Feed has no items array".to_string()))?
+        .iter()
+        .filter_map(|item| {
+            let link = string(item, "url");
+            Some(FeedEntry {
+                id: string(item, "id").or_else(|| link.clone())?,
+                title: string(item, "title"),
+                published: string(item, "date_published"),
+                updated: string(item, "date_modified"),
+                summary: string(item, "summary").or_else(|| string(item, "content_text")),
+                link,
+            })
+        })
+        .collect();
+
+    Ok(Feed {
+        kind: FeedKind::JsonFeed,
+        title: string(&doc, "title"),
+        entries,
+    })
+}
+
+/// Entry being collected while walking the XML
+#[derive(Default)]
+struct Partial {
+    depth: usize,
+    guid: Option<String>,
+    entry: FeedEntry,
+}
+
+impl Partial {
+    fn finish(self) -> Option<FeedEntry> {
+        let mut entry = self.entry;
+        entry.id = self.guid.or_else(|| entry.link.clone())?;
+        Some(entry)
+    }
+}
+
+fn parse_xml_feed(text: &str) -> Result<Feed> {
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+
+    let mut kind = None;
+    let mut title = None;
+    let mut entries = Vec::new();
+    let mut depth = 0usize;
+    let mut current: Option<Partial> = None;
+    // Element whose text is being collected and the text so far
+    let mut field: Option<(String, usize, String)> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| CuimpError::ParseError(format!("Invalid feed XML: {}", e)))?;
+        match event {
+            Event::Start(start) => {
+                depth += 1;
+                let name = local_name(&start);
+                if kind.is_none() {
+                    kind = Some(match name.as_str() {
+                        "rss" | "rdf" => FeedKind::Rss,
+                        "feed" => FeedKind::Atom,
+                        "urlset" | "sitemapindex" => FeedKind::Sitemap,
+                        other => {
+                            return Err(CuimpError::ParseError(format!(
+                                "Unknown feed root element <{}>",
+                                other
+                            )))
+                        }
+                    });
+                }
+
+                match (&mut current, name.as_str()) {
+                    (None, "item" | "entry" | "url" | "sitemap") => {
+                        current = Some(Partial {
+                            depth,
+                            ..Default::default()
+                        })
+                    }
+                    (Some(partial), "link") if depth == partial.depth + 1 => {
+                        set_atom_link(&mut partial.entry, &start);
+                        field = Some((name, depth, String::new()));
+                    }
+                    (Some(partial), _) if depth == partial.depth + 1 => {
+                        field = Some((name, depth, String::new()))
+                    }
+                    (None, "title") if title.is_none() => {
+                        field = Some((name, depth, String::new()))
+                    }
+                    _ => {}
+                }
+            }
+            Event::Empty(start) => {
+                if let Some(partial) = current.as_mut().filter(|p| depth == p.depth) {
+                    if local_name(&start) == "link" {
+                        set_atom_link(&mut partial.entry, &start);
+                    }
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, _, value)) = field.as_mut() {
+                    value.push_str(&decode_entities(&String::from_utf8_lossy(&text)));
+                }
+            }
+            Event::CData(data) => {
+                if let Some((_, _, value)) = field.as_mut() {
+                    value.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::End(_) => {
+                if field.as_ref().is_some_and(|(_, at, _)| *at == depth) {
+                    if let Some((name, _, value)) = field.take() {
+                        let value = value.trim().to_string();
+                        match current.as_mut() {
+                            Some(partial) => set_field(partial, &name, value),
+                            None => title = Some(value).filter(|t| !t.is_empty()),
+                        }
+                    }
+                }
+                if current.as_ref().is_some_and(|p| p.depth == depth) {
+                    entries.extend(current.take().and_then(Partial::finish));
+                }
+                depth = depth.saturating_sub(1);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(Feed {
+        kind: kind.ok_or_else(|| CuimpError::ParseError("Empty feed document".to_string()))?,
+        title,
+        entries,
+    })
+}
+
+fn local_name(start: &BytesStart) -> String {
+    String::from_utf8_lossy(start.local_name().as_ref()).to_ascii_lowercase()
+}
+
+/// Atom `<link href>`: the first alternate (or rel-less) link wins
+fn set_atom_link(entry: &mut FeedEntry, start: &BytesStart) {
+    let mut href = None;
+    let mut rel = None;
+    for attr in start.attributes().flatten() {
+        let value = attr.unescape_value().map(|v| v.into_owned()).ok();
+        match attr.key.local_name().as_ref() {
+            b"href" => href = value,
+            b"rel" => rel = value,
+            _ => {}
+        }
+    }
+    if entry.link.is_none() && rel.is_none_or(|rel| rel == "alternate") {
+        entry.link = href;
+    }
+}
+
+fn set_field(partial: &mut Partial, name: &str, value: String) {
+    if value.is_empty() {
+        return;
+    }
+    let entry = &mut partial.entry;
+    let slot = match name {
+        "guid" | "id" => &mut partial.guid,
+        "title" => &mut entry.title,
+        "link" | "loc" => &mut entry.link,
+        "pubdate" | "published" | "date" | "issued" => &mut entry.published,
+        "updated" | "lastmod" | "modified" => &mut entry.updated,
+        "description" | "summary" | "content" | "encoded" => &mut entry.summary,
+        _ => return,
+    };
+    if slot.is_none() {
+        *slot = Some(value);
+    }
+}
+
+/// Revalidation state for polling one feed
+///
+/// Remembers the `ETag`/`Last-Modified` validators and the ids already returned,
+/// so each poll yields only entries that were not seen before.
+#[derive(Debug, Clone)]
+pub struct FeedPoller {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    seen: HashSet<String>,
+}
+
+impl FeedPoller {
+    pub fn new(url: impl Into<String>) -> Self {
+        FeedPoller {
+            url: url.into(),
+            etag: None,
+            last_modified: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Entries of `feed` not returned before, in document order
+    fn take_new(&mut self, feed: Feed) -> Vec<FeedEntry> {
+        feed.entries
+            .into_iter()
+            .filter(|entry| self.seen.insert(entry.id.clone()))
+            .collect()
+    }
+}
+
+impl CuimpHttp {
+    /// Fetch the feed once with conditional headers and return the entries not seen before.
+    ///
+    /// A `304 Not Modified` response yields no entries.
+    pub async fn poll_feed_once(&mut self, poller: &mut FeedPoller) -> Result<Vec<FeedEntry>> {
+        let mut headers = HashMap::new();
+        if let Some(etag) = &poller.etag {
+            headers.insert("If-None-Match".to_string(), etag.clone());
+        }
+        if let Some(last_modified) = &poller.last_modified {
+            headers.insert("If-Modified-Since".to_string(), last_modified.clone());
+        }
+        let config = CuimpRequestConfig {
+            url: Some(poller.url.clone()),
+            method: Some(Method::GET),
+            headers: Some(headers),
+            ..Default::default()
+        };
+        let response = self.request::<Value>(config).await?;
+
+        if response.status == 304 {
+            return Ok(Vec::new());
+        }
+        if !(200..300).contains(&response.status) {
+            return Err(CuimpError::HttpError(format!(
+                "Feed {} returned {} {}",
+                poller.url, response.status, response.status_text
+            )));
+        }
+
+        let feed = Feed::parse(&response.raw_body)?;
+        poller.etag = response.header("etag").map(str::to_string);
+        poller.last_modified = response.header("last-modified").map(str::to_string);
+        Ok(poller.take_new(feed))
+    }
+
+    /// Poll a feed every `interval`, yielding each new entry once.
+    ///
+    /// The first poll returns every entry currently in the feed. The stream never
+    /// ends on its own; a failed poll yields an error and polling continues.
+    pub fn poll_feed(&mut self, url: impl Into<String>, interval: Duration) -> FeedStream<'_> {
+        FeedStream {
+            idle: Some((self, FeedPoller::new(url))),
+            polling: None,
+            buffered: VecDeque::new(),
+            interval,
+            first: true,
+        }
+    }
+}
+
+type PollFuture<'a> = Pin<
+    Box<dyn Future<Output = (&'a mut CuimpHttp, FeedPoller, Result<Vec<FeedEntry>>)> + Send + 'a>,
+>;
+
+/// Stream of new feed entries returned by `CuimpHttp::poll_feed`
+pub struct FeedStream<'a> {
+    idle: Option<(&'a mut CuimpHttp, FeedPoller)>,
+    polling: Option<PollFuture<'a>>,
+    buffered: VecDeque<FeedEntry>,
+    interval: Duration,
+    first: bool,
+}
+
+impl std::fmt::Debug for FeedStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeedStream")
+            .field("buffered", &self.buffered.len())
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for FeedStream<'_> {
+    type Item = Result<FeedEntry>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(entry) = self.buffered.pop_front() {
+                return Poll::Ready(Some(Ok(entry)));
+            }
+
+            if self.polling.is_none() {
+                let Some((client, mut poller)) = self.idle.take() else {
+                    return Poll::Ready(None);
+                };
+                let delay = if self.first {
+                    Duration::ZERO
+                } else {
+                    self.interval
+                };
+                self.first = false;
+                self.polling = Some(Box::pin(async move {
+                    tokio::time::sleep(delay).await;
+                    let result = client.poll_feed_once(&mut poller).await;
+                    (client, poller, result)
+                }));
+            }
+
+            let Some(polling) = self.polling.as_mut() else {
+                return Poll::Ready(None);
+            };
+            let (client, poller, result) = match polling.as_mut().poll(cx) {
+                Poll::Ready(output) => output,
+                Poll::Pending => return Poll::Pending,
+            };
+            self.polling = None;
+            self.idle = Some((client, poller));
+            match result {
+                Ok(entries) => self.buffered.extend(entries),
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        let rss = br#"<?xml version="1.0"?><rss version="2.0"><channel><title>News</title>
+            <item><title>First &amp; best</title><link>https://example.com/1</link><guid>a1</guid>
+            <pubDate>Mon, 06 Sep 2021 16:45:00 GMT</pubDate><description><![CDATA[<p>Hi</p>]]></description></item>
+            <item><title>Second</title><link>https://example.com/2</link></item>
+            </channel></rss>"#;
+        let feed = Feed::parse(rss).unwrap();
+        assert_eq!(
+            (feed.kind, feed.title.as_deref()),
+            (FeedKind::Rss, Some("News"))
+        );
+        assert_eq!(feed.entries[0].id, "a1");
+        assert_eq!(feed.entries[0].title.as_deref(), Some("First & best"));
+        assert_eq!(feed.entries[0].summary.as_deref(), Some("<p>Hi</p>"));
+        assert_eq!(feed.entries[1].id, "https://example.com/2");
+
+        let atom = br#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+            <entry><id>urn:1</id><title>Post</title><link rel="self" href="https://example.com/self"/>
+            <link href="https://example.com/post"/><updated>2024-01-01T00:00:00Z</updated></entry></feed>"#;
+        let feed = Feed::parse(atom).unwrap();
+        assert_eq!(feed.kind, FeedKind::Atom);
+        assert_eq!(
+            feed.entries[0].link.as_deref(),
+            Some("https://example.com/post")
+        );
+        assert_eq!(
+            feed.entries[0].updated.as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+
+        let sitemap = br#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+            <url><loc>https://example.com/</loc><lastmod>2024-02-02</lastmod></url></urlset>"#;
+        let feed = Feed::parse(sitemap).unwrap();
+        assert_eq!(
+            (feed.kind, feed.entries[0].id.as_str()),
+            (FeedKind::Sitemap, "https://example.com/")
+        );
+
+        let json = br#"{"version":"https://jsonfeed.org/version/1.1","title":"J","items":[{"id":7,"url":"https://example.com/7"}]}"#;
+        let feed = Feed::parse(json).unwrap();
+        assert_eq!(
+            (feed.kind, feed.entries[0].id.as_str()),
+            (FeedKind::JsonFeed, "7")
+        );
+
+        let mut poller = FeedPoller::new("https://example.com/feed");
+        assert_eq!(poller.take_new(feed.clone()).len(), 1);
+        assert!(poller.take_new(feed).is_empty());
+    }
+}
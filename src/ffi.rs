@@ -0,0 +1,943 @@
+//! In-process requests through libcurl-impersonate.
+//!
+//! With `Backend::Ffi`, `CuimpHttp` loads the libcurl-impersonate shared library at
+//! runtime and runs requests on a libcurl easy handle instead of spawning the binary.
+//! Easy handles are pooled per client and attached to one share handle, so sequential
+//! and concurrent requests reuse connections, DNS lookups and TLS sessions.
+//! Requests are still prepared as curl arguments; the arguments `CuimpHttp` generates
+//! are translated to easy options, and any other argument is rejected.
+
+use crate::connection;
+use crate::constants::WRAPPER_PREFIX;
+use crate::error::{CuimpError, Result};
+use libloading::Library;
+use std::ffi::{c_char, c_int, c_long, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+type CurlCode = c_int;
+
+#[repr(C)]
+struct CurlHandle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct CurlSlist {
+    data: *mut c_char,
+    next: *mut CurlSlist,
+}
+
+/// `struct curl_certinfo`: one list of `Name:value` entries per certificate
+#[repr(C)]
+struct CurlCertInfo {
+    num_of_certs: c_int,
+    certinfo: *mut *mut CurlSlist,
+}
+
+#[repr(C)]
+struct CurlShare {
+    _private: [u8; 0],
+}
+
+type WriteCallback = extern "C" fn(*const c_char, usize, usize, *mut c_void) -> usize;
+type LockCallback = extern "C" fn(*mut CurlHandle, c_int, c_int, *mut c_void);
+type UnlockCallback = extern "C" fn(*mut CurlHandle, c_int, *mut c_void);
+
+const CURL_GLOBAL_DEFAULT: c_long = 3;
+const CURLE_OK: CurlCode = 0;
+const CURLE_OPERATION_TIMEDOUT: CurlCode = 28;
+const CURL_ERROR_SIZE: usize = 256;
+
+const CURLSHOPT_SHARE: c_int = 1;
+const CURLSHOPT_LOCKFUNC: c_int = 3;
+const CURLSHOPT_UNLOCKFUNC: c_int = 4;
+const CURLSHOPT_USERDATA: c_int = 5;
+const CURL_LOCK_DATA_DNS: c_int = 3;
+const CURL_LOCK_DATA_SSL_SESSION: c_int = 4;
+const CURL_LOCK_DATA_CONNECT: c_int = 5;
+/// Lock slots, one per `curl_lock_data` value libcurl may ask for
+const LOCK_KINDS: usize = 8;
+
+const CURLOPT_NOBODY: c_int = 44;
+const CURLOPT_LOW_SPEED_LIMIT: c_int = 19;
+const CURLOPT_LOW_SPEED_TIME: c_int = 20;
+const CURLOPT_FOLLOWLOCATION: c_int = 52;
+const CURLOPT_SSL_VERIFYPEER: c_int = 64;
+const CURLOPT_MAXREDIRS: c_int = 68;
+const CURLOPT_SSL_VERIFYHOST: c_int = 81;
+const CURLOPT_NOSIGNAL: c_int = 99;
+const CURLOPT_UNRESTRICTED_AUTH: c_int = 105;
+const CURLOPT_TIMEOUT_MS: c_int = 155;
+const CURLOPT_CERTINFO: c_int = 172;
+const CURLOPT_PATH_AS_IS: c_int = 234;
+const CURLOPT_WRITEDATA: c_int = 10001;
+const CURLOPT_URL: c_int = 10002;
+const CURLOPT_PROXY: c_int = 10004;
+const CURLOPT_ERRORBUFFER: c_int = 10010;
+const CURLOPT_SSL_CIPHER_LIST: c_int = 10083;
+const CURLOPT_SHARE: c_int = 10100;
+const CURLOPT_NOPROXY: c_int = 10177;
+const CURLOPT_HTTPHEADER: c_int = 10023;
+const CURLOPT_HEADERDATA: c_int = 10029;
+const CURLOPT_CUSTOMREQUEST: c_int = 10036;
+const CURLOPT_COPYPOSTFIELDS: c_int = 10165;
+const CURLOPT_SSL_EC_CURVES: c_int = 10298;
+const CURLOPT_REDIR_PROTOCOLS_STR: c_int = 10319;
+const CURLOPT_WRITEFUNCTION: c_int = 20011;
+const CURLOPT_HEADERFUNCTION: c_int = 20079;
+const CURLOPT_POSTFIELDSIZE_LARGE: c_int = 30120;
+const CURLOPT_MAX_SEND_SPEED_LARGE: c_int = 30145;
+const CURLOPT_MAX_RECV_SPEED_LARGE: c_int = 30146;
+const CURLINFO_CERTINFO: c_int = 0x40_0000 + 34;
+
+#[cfg(target_os = "windows")]
+const LIBRARY_NAMES: &[&str] = &["libcurl-impersonate.dll", "libcurl.dll"];
+#[cfg(target_os = "macos")]
+const LIBRARY_NAMES: &[&str] = &["libcurl-impersonate.dylib", "libcurl-impersonate.4.dylib"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_NAMES: &[&str] = &[
+    "libcurl-impersonate.so",
+    "libcurl-impersonate.so.4",
+    "libcurl-impersonate-chrome.so",
+];
+
+/// libcurl entry points resolved from the shared library
+struct Api {
+    easy_init: unsafe extern "C" fn() -> *mut CurlHandle,
+    easy_cleanup: unsafe extern "C" fn(*mut CurlHandle),
+    easy_reset: unsafe extern "C" fn(*mut CurlHandle),
+    easy_perform: unsafe extern "C" fn(*mut CurlHandle) -> CurlCode,
+    easy_setopt: unsafe extern "C" fn(*mut CurlHandle, c_int, ...) -> CurlCode,
+    easy_getinfo: unsafe extern "C" fn(*mut CurlHandle, c_int, ...) -> CurlCode,
+    easy_impersonate: unsafe extern "C" fn(*mut CurlHandle, *const c_char, c_int) -> CurlCode,
+    easy_strerror: unsafe extern "C" fn(CurlCode) -> *const c_char,
+    slist_append: unsafe extern "C" fn(*mut CurlSlist, *const c_char) -> *mut CurlSlist,
+    slist_free_all: unsafe extern "C" fn(*mut CurlSlist),
+    share_init: unsafe extern "C" fn() -> *mut CurlShare,
+    share_setopt: unsafe extern "C" fn(*mut CurlShare, c_int, ...) -> c_int,
+    share_cleanup: unsafe extern "C" fn(*mut CurlShare) -> c_int,
+    /// Keeps the function pointers above valid
+    _library: Library,
+}
+
+impl Api {
+    fn load(path: &Path) -> std::result::Result<Self, libloading::Error> {
+        // SAFETY: loading libcurl runs no initialisers with preconditions, and the
+        // symbol types below match the libcurl / libcurl-impersonate C API.
+        unsafe {
+            let library = Library::new(path)?;
+            let global_init =
+                *library.get::<unsafe extern "C" fn(c_long) -> CurlCode>(b"curl_global_init\0")?;
+            let api = Api {
+                easy_init: *library.get(b"curl_easy_init\0")?,
+                easy_cleanup: *library.get(b"curl_easy_cleanup\0")?,
+                easy_reset: *library.get(b"curl_easy_reset\0")?,
+                easy_perform: *library.get(b"curl_easy_perform\0")?,
+                easy_setopt: *library.get(b"curl_easy_setopt\0")?,
+                easy_getinfo: *library.get(b"curl_easy_getinfo\0")?,
+                easy_impersonate: *library.get(b"curl_easy_impersonate\0")?,
+                easy_strerror: *library.get(b"curl_easy_strerror\0")?,
+                slist_append: *library.get(b"curl_slist_append\0")?,
+                slist_free_all: *library.get(b"curl_slist_free_all\0")?,
+                share_init: *library.get(b"curl_share_init\0")?,
+                share_setopt: *library.get(b"curl_share_setopt\0")?,
+                share_cleanup: *library.get(b"curl_share_cleanup\0")?,
+                _library: library,
+            };
+            global_init(CURL_GLOBAL_DEFAULT);
+            Ok(api)
+        }
+    }
+
+    fn error_message(&self, code: CurlCode) -> String {
+        // SAFETY: curl_easy_strerror returns a static NUL-terminated string
+        let message = unsafe { (self.easy_strerror)(code) };
+        if message.is_null() {
+            return format!("libcurl error {}", code);
+        }
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// A request translated from the curl arguments built by `CuimpHttp::prepare`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct EasyRequest {
+    pub url: String,
+    pub method: Option<String>,
+    pub follow_redirects: bool,
+    pub max_redirects: Option<c_long>,
+    /// Keep credentials on redirects to other hosts (`--location-trusted`)
+    pub location_trusted: bool,
+    /// Protocols redirects may switch to (`--proto-redir`), comma-separated
+    pub redirect_protocols: Option<String>,
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub insecure: bool,
+    pub headers: Vec<String>,
+    pub body: Option<Vec<u8>>,
+    pub timeout_ms: Option<u64>,
+    pub ciphers: Option<String>,
+    pub curves: Option<String>,
+    pub low_speed_limit: Option<c_long>,
+    pub low_speed_time: Option<c_long>,
+    pub max_rate: Option<i64>,
+    /// Collect the server certificate chain (`CURLOPT_CERTINFO`)
+    pub cert_info: bool,
+    /// Keep `.` and `..` path segments (`--path-as-is`)
+    pub path_as_is: bool,
+}
+
+impl EasyRequest {
+    pub(crate) fn from_args(
+        args: &[String],
+        stdin: Option<&[u8]>,
+        timeout_ms: Option<u64>,
+    ) -> Result<Self> {
+        let mut request = EasyRequest {
+            timeout_ms,
+            ..Default::default()
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| CuimpError::InvalidConfig(format!("{} requires a value", flag)))
+            };
+            match arg.as_str() {
+                "-X" | "--request" => request.method = Some(value(arg)?),
+                "-L" | "--location" => request.follow_redirects = true,
+                "--max-redirs" => request.max_redirects = Some(number(arg, value(arg)?)?),
+                "--location-trusted" => {
+                    request.follow_redirects = true;
+                    request.location_trusted = true;
+                }
+                // Only the `=` form `prepare` builds: exactly the protocols listed
+                "--proto-redir" => {
+                    request.redirect_protocols =
+                        Some(value(arg)?.trim_start_matches('=').to_string())
+                }
+                "-x" | "--proxy" => request.proxy = Some(value(arg)?),
+                "--noproxy" => request.no_proxy = Some(value(arg)?),
+                "-k" | "--insecure" => request.insecure = true,
+                "--path-as-is" => request.path_as_is = true,
+                "-H" | "--header" => request.headers.push(value(arg)?),
+                "--data-raw" => request.body = Some(value(arg)?.into_bytes()),
+                "--data-binary" => {
+                    let data = value(arg)?;
+                    request.body = Some(match data.as_str() {
+                        "@-" => stdin.map(<[u8]>::to_vec).unwrap_or_default(),
+                        _ => data.into_bytes(),
+                    });
+                }
+                "--max-time" => {
+                    let secs = value(arg)?;
+                    let secs: f64 = secs.parse().map_err(|_| {
+                        CuimpError::InvalidConfig(format!("Invalid --max-time: {}", secs))
+                    })?;
+                    request.timeout_ms = Some((secs * 1000.0) as u64);
+                }
+                "--ciphers" => request.ciphers = Some(value(arg)?),
+                "--curves" => request.curves = Some(value(arg)?),
+                "-Y" | "--speed-limit" => request.low_speed_limit = Some(number(arg, value(arg)?)?),
+                "-y" | "--speed-time" => request.low_speed_time = Some(number(arg, value(arg)?)?),
+                "--limit-rate" => request.max_rate = Some(number(arg, value(arg)?)?),
+                // libcurl does not glob URLs
+                "-i" | "--include" | "--no-buffer" | "--compressed" | "--globoff" => {}
+                flag if flag.starts_with('-') => {
+                    return Err(CuimpError::InvalidConfig(format!(
+                        "`{}` is not supported by the ffi backend",
+                        flag
+                    )))
+                }
+                url => request.url = url.to_string(),
+            }
+        }
+
+        if request.url.is_empty() {
+            return Err(CuimpError::InvalidUrl("URL is required".to_string()));
+        }
+        Ok(request)
+    }
+}
+
+fn number<N: std::str::FromStr>(flag: &str, value: String) -> Result<N> {
+    value
+        .parse()
+        .map_err(|_| CuimpError::InvalidConfig(format!("Invalid {}: {}", flag, value)))
+}
+
+/// libcurl-impersonate target (`chrome124`) for a resolved binary, falling back to
+/// the descriptor's fingerprint (`chrome 124`)
+pub(crate) fn impersonate_target(bin: &str, fingerprint: Option<String>) -> Result<String> {
+    let wrapper = Path::new(bin)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(WRAPPER_PREFIX))
+        .filter(|target| !target.is_empty());
+    match (wrapper, fingerprint) {
+        (Some(target), _) => Ok(target.to_string()),
+        (None, Some(fingerprint)) if fingerprint.contains(' ') => Ok(fingerprint.replace(' ', "")),
+        _ => Err(CuimpError::InvalidConfig(
+            "The ffi backend needs a browser and version to impersonate (e.g. chrome 124)"
+                .to_string(),
+        )),
+    }
+}
+
+/// Easy handles of a `CuimpHttp` using the ffi backend, created on first use
+///
+/// A request takes an idle handle (or opens one) and returns it afterwards, so there
+/// are as many handles as requests that ran at the same time. All of them use the
+/// same share handle and so the same connection pool.
+#[derive(Debug)]
+pub(crate) struct FfiSession {
+    library: Option<PathBuf>,
+    shared: Mutex<Option<Arc<Shared>>>,
+    idle: Mutex<Vec<Easy>>,
+}
+
+/// The loaded library and the share handle holding the connection, DNS and TLS session caches
+struct Shared {
+    api: Api,
+    share: *mut CurlShare,
+    /// Locks taken by libcurl through `lock_data`/`unlock_data`
+    locks: [DataLock; LOCK_KINDS],
+}
+
+// SAFETY: the share handle is only touched by libcurl, which serialises access
+// through the lock callbacks, and by `Drop` once no easy handle refers to it.
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+struct Easy {
+    shared: Arc<Shared>,
+    handle: *mut CurlHandle,
+}
+
+// SAFETY: an easy handle may move between threads as long as it is used by one
+// thread at a time; a handle is either in the idle pool or owned by one request.
+unsafe impl Send for Easy {}
+
+impl std::fmt::Debug for Shared {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shared").finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for Easy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Easy").finish_non_exhaustive()
+    }
+}
+
+impl FfiSession {
+    pub(crate) fn new(library: Option<PathBuf>) -> Self {
+        FfiSession {
+            library,
+            shared: Mutex::new(None),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Run a request and return the output `curl -i` would have written
+    pub(crate) async fn perform(
+        self: Arc<Self>,
+        bin: String,
+        target: String,
+        request: EasyRequest,
+    ) -> Result<Vec<u8>> {
+        tokio::task::spawn_blocking(move || {
+            let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            let mut easy = match idle {
+                Some(easy) => easy,
+                None => Easy::new(self.shared(&bin)?)?,
+            };
+            let result = easy.perform(&target, &request);
+            self.idle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(easy);
+            result
+        })
+        .await
+        .map_err(|e| CuimpError::RequestFailed(format!("libcurl worker failed: {}", e)))?
+    }
+
+    fn shared(&self, bin: &str) -> Result<Arc<Shared>> {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.as_ref() {
+            Some(shared) => Ok(Arc::clone(shared)),
+            None => Ok(Arc::clone(
+                shared.insert(Shared::open(self.library.as_deref(), bin)?),
+            )),
+        }
+    }
+}
+
+impl Shared {
+    /// Load the configured library, or look for it next to `bin` and on the loader path
+    fn open(library: Option<&Path>, bin: &str) -> Result<Arc<Self>> {
+        let candidates: Vec<PathBuf> = match library {
+            Some(path) => vec![path.to_path_buf()],
+            None => {
+                let dir = Path::new(bin)
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty());
+                dir.into_iter()
+                    .flat_map(|dir| LIBRARY_NAMES.iter().map(move |name| dir.join(name)))
+                    .chain(LIBRARY_NAMES.iter().map(PathBuf::from))
+                    .collect()
+            }
+        };
+
+        let mut errors = Vec::new();
+        for candidate in candidates {
+            match Api::load(&candidate) {
+                Ok(api) => return Self::with_api(api),
+                Err(e) => errors.push(format!("{}: {}", candidate.display(), e)),
+            }
+        }
+        Err(CuimpError::BinaryNotFound(format!(
+            "libcurl-impersonate could not be loaded ({})",
+            errors.join("; ")
+        )))
+    }
+
+    fn with_api(api: Api) -> Result<Arc<Self>> {
+        // SAFETY: curl_share_init has no preconditions
+        let share = unsafe { (api.share_init)() };
+        if share.is_null() {
+            return Err(CuimpError::RequestFailed(
+                "curl_share_init failed".to_string(),
+            ));
+        }
+        let shared = Arc::new(Shared {
+            api,
+            share,
+            locks: Default::default(),
+        });
+
+        // SAFETY: the lock array lives as long as the share handle, which `Drop` cleans
+        // up before the array is freed
+        let code = unsafe {
+            let setopt = shared.api.share_setopt;
+            let locks: *const [DataLock; LOCK_KINDS] = &shared.locks;
+            let mut code = setopt(share, CURLSHOPT_LOCKFUNC, lock_data as LockCallback);
+            for option in [
+                (
+                    CURLSHOPT_UNLOCKFUNC,
+                    unlock_data as UnlockCallback as *const c_void,
+                ),
+                (CURLSHOPT_USERDATA, locks.cast()),
+            ] {
+                if code == 0 {
+                    code = setopt(share, option.0, option.1);
+                }
+            }
+            for data in [
+                CURL_LOCK_DATA_CONNECT,
+                CURL_LOCK_DATA_DNS,
+                CURL_LOCK_DATA_SSL_SESSION,
+            ] {
+                if code == 0 {
+                    code = setopt(share, CURLSHOPT_SHARE, data);
+                }
+            }
+            code
+        };
+        if code != 0 {
+            return Err(CuimpError::RequestFailed(format!(
+                "curl_share_setopt failed ({})",
+                code
+            )));
+        }
+        Ok(shared)
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // SAFETY: every easy handle holds an `Arc` to this value, so none is still attached
+        unsafe { (self.api.share_cleanup)(self.share) };
+    }
+}
+
+impl Easy {
+    fn new(shared: Arc<Shared>) -> Result<Self> {
+        // SAFETY: curl_easy_init has no preconditions
+        let handle = unsafe { (shared.api.easy_init)() };
+        if handle.is_null() {
+            return Err(CuimpError::RequestFailed(
+                "curl_easy_init failed".to_string(),
+            ));
+        }
+        Ok(Easy { shared, handle })
+    }
+
+    fn perform(&mut self, target: &str, request: &EasyRequest) -> Result<Vec<u8>> {
+        let handle = self.handle;
+        let api = &self.shared.api;
+        let mut head: Vec<u8> = Vec::new();
+        let mut body: Vec<u8> = Vec::new();
+        let mut error_buffer = [0 as c_char; CURL_ERROR_SIZE];
+
+        let target = c_string(target)?;
+        let url = c_string(&request.url)?;
+        let method = request.method.as_deref().map(c_string).transpose()?;
+        let proxy = request.proxy.as_deref().map(c_string).transpose()?;
+        let no_proxy = request.no_proxy.as_deref().map(c_string).transpose()?;
+        let redirect_protocols = request
+            .redirect_protocols
+            .as_deref()
+            .map(c_string)
+            .transpose()?;
+        let ciphers = request.ciphers.as_deref().map(c_string).transpose()?;
+        let curves = request.curves.as_deref().map(c_string).transpose()?;
+        let headers = HeaderList::new(api, &request.headers)?;
+
+        let setopt_long =
+            |option: c_int, value: c_long| unsafe { (api.easy_setopt)(handle, option, value) };
+        let setopt_ptr = |option: c_int, value: *const c_void| unsafe {
+            (api.easy_setopt)(handle, option, value)
+        };
+
+        // SAFETY: every pointer handed to libcurl below outlives `easy_perform`, and the
+        // reset at the start leaves no option from an earlier request pointing at freed data.
+        let code = unsafe {
+            (api.easy_reset)(handle);
+            let mut code = (api.easy_impersonate)(handle, target.as_ptr(), 1);
+            let mut set = |result: CurlCode| {
+                if code == CURLE_OK {
+                    code = result;
+                }
+            };
+            set(setopt_ptr(CURLOPT_SHARE, self.shared.share.cast()));
+            set(setopt_long(CURLOPT_NOSIGNAL, 1));
+            set(setopt_ptr(
+                CURLOPT_ERRORBUFFER,
+                error_buffer.as_mut_ptr().cast(),
+            ));
+            set(setopt_ptr(CURLOPT_URL, url.as_ptr().cast()));
+            set((api.easy_setopt)(
+                handle,
+                CURLOPT_WRITEFUNCTION,
+                append as WriteCallback,
+            ));
+            set(setopt_ptr(
+                CURLOPT_WRITEDATA,
+                (&mut body as *mut Vec<u8>).cast(),
+            ));
+            set((api.easy_setopt)(
+                handle,
+                CURLOPT_HEADERFUNCTION,
+                append as WriteCallback,
+            ));
+            set(setopt_ptr(
+                CURLOPT_HEADERDATA,
+                (&mut head as *mut Vec<u8>).cast(),
+            ));
+            if !headers.list.is_null() {
+                set(setopt_ptr(CURLOPT_HTTPHEADER, headers.list.cast()));
+            }
+            if let Some(data) = &request.body {
+                set((api.easy_setopt)(
+                    handle,
+                    CURLOPT_POSTFIELDSIZE_LARGE,
+                    data.len() as i64,
+                ));
+                set(setopt_ptr(CURLOPT_COPYPOSTFIELDS, data.as_ptr().cast()));
+            }
+            match &method {
+                Some(method) if method.as_bytes() == b"HEAD" => set(setopt_long(CURLOPT_NOBODY, 1)),
+                Some(method) => set(setopt_ptr(CURLOPT_CUSTOMREQUEST, method.as_ptr().cast())),
+                None => {}
+            }
+            if request.follow_redirects {
+                set(setopt_long(CURLOPT_FOLLOWLOCATION, 1));
+                if let Some(max) = request.max_redirects {
+                    set(setopt_long(CURLOPT_MAXREDIRS, max));
+                }
+                if request.location_trusted {
+                    set(setopt_long(CURLOPT_UNRESTRICTED_AUTH, 1));
+                }
+                if let Some(protocols) = &redirect_protocols {
+                    set(setopt_ptr(
+                        CURLOPT_REDIR_PROTOCOLS_STR,
+                        protocols.as_ptr().cast(),
+                    ));
+                }
+            }
+            if let Some(proxy) = &proxy {
+                set(setopt_ptr(CURLOPT_PROXY, proxy.as_ptr().cast()));
+            }
+            if let Some(no_proxy) = &no_proxy {
+                set(setopt_ptr(CURLOPT_NOPROXY, no_proxy.as_ptr().cast()));
+            }
+            if let Some(ciphers) = &ciphers {
+                set(setopt_ptr(CURLOPT_SSL_CIPHER_LIST, ciphers.as_ptr().cast()));
+            }
+            if let Some(curves) = &curves {
+                set(setopt_ptr(CURLOPT_SSL_EC_CURVES, curves.as_ptr().cast()));
+            }
+            if request.path_as_is {
+                set(setopt_long(CURLOPT_PATH_AS_IS, 1));
+            }
+            if request.insecure {
+                set(setopt_long(CURLOPT_SSL_VERIFYPEER, 0));
+                set(setopt_long(CURLOPT_SSL_VERIFYHOST, 0));
+            }
+            if let Some(limit) = request.low_speed_limit {
+                set(setopt_long(CURLOPT_LOW_SPEED_LIMIT, limit));
+                set(setopt_long(
+                    CURLOPT_LOW_SPEED_TIME,
+                    request.low_speed_time.unwrap_or(30),
+                ));
+            }
+            if let Some(rate) = request.max_rate {
+                set((api.easy_setopt)(
+                    handle,
+                    CURLOPT_MAX_SEND_SPEED_LARGE,
+                    rate,
+                ));
+                set((api.easy_setopt)(
+                    handle,
+                    CURLOPT_MAX_RECV_SPEED_LARGE,
+                    rate,
+                ));
+            }
+            if let Some(timeout_ms) = request.timeout_ms {
+                set(setopt_long(
+                    CURLOPT_TIMEOUT_MS,
+                    c_long::try_from(timeout_ms).unwrap_or(c_long::MAX),
+                ));
+            }
+            if request.cert_info {
+                set(setopt_long(CURLOPT_CERTINFO, 1));
+            }
+            if code == CURLE_OK {
+                code = (api.easy_perform)(handle);
+            }
+            code
+        };
+        drop(headers);
+
+        if code != CURLE_OK {
+            // SAFETY: libcurl NUL-terminates the error buffer, which starts zeroed
+            let detail = unsafe { CStr::from_ptr(error_buffer.as_ptr()) }
+                .to_string_lossy()
+                .into_owned();
+            let message = if detail.is_empty() {
+                api.error_message(code)
+            } else {
+                detail
+            };
+            return Err(match code {
+                CURLE_OPERATION_TIMEDOUT => CuimpError::Timeout(message),
+                _ => CuimpError::RequestFailed(format!("libcurl error {}: {}", code, message)),
+            });
+        }
+
+        head.extend_from_slice(&body);
+        if request.cert_info {
+            head.extend_from_slice(connection::trailer("{}", &self.certificates()).as_bytes());
+        }
+        Ok(head)
+    }
+
+    /// Certificate chain of the last transfer, in the text format of `%{certs}`
+    fn certificates(&self) -> String {
+        let mut text = String::new();
+        let mut info: *mut CurlCertInfo = std::ptr::null_mut();
+        // SAFETY: CURLINFO_CERTINFO stores a pointer to a `curl_certinfo` owned by the
+        // handle, which stays valid until the next transfer on it
+        unsafe {
+            let code = (self.shared.api.easy_getinfo)(
+                self.handle,
+                CURLINFO_CERTINFO,
+                &mut info as *mut *mut CurlCertInfo,
+            );
+            if code != CURLE_OK || info.is_null() || (*info).certinfo.is_null() {
+                return text;
+            }
+            for i in 0..usize::try_from((*info).num_of_certs).unwrap_or(0) {
+                let mut node = *(*info).certinfo.add(i);
+                while !node.is_null() {
+                    if !(*node).data.is_null() {
+                        text.push_str(&CStr::from_ptr((*node).data).to_string_lossy());
+                        text.push('\n');
+                    }
+                    node = (*node).next;
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+impl Drop for Easy {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from curl_easy_init and is not used afterwards
+        unsafe { (self.shared.api.easy_cleanup)(self.handle) };
+    }
+}
+
+/// Lock for one kind of shared data, held between libcurl's lock and unlock callbacks
+#[derive(Default)]
+struct DataLock {
+    locked: Mutex<bool>,
+    released: Condvar,
+}
+
+impl DataLock {
+    fn lock(&self) {
+        let mut locked = self.locked.lock().unwrap_or_else(|e| e.into_inner());
+        while *locked {
+            locked = self
+                .released
+                .wait(locked)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *locked = true;
+    }
+
+    fn unlock(&self) {
+        *self.locked.lock().unwrap_or_else(|e| e.into_inner()) = false;
+        self.released.notify_one();
+    }
+}
+
+/// Share lock callback; `userptr` is the `[DataLock; LOCK_KINDS]` set with CURLSHOPT_USERDATA
+extern "C" fn lock_data(
+    _handle: *mut CurlHandle,
+    data: c_int,
+    _access: c_int,
+    userptr: *mut c_void,
+) {
+    // SAFETY: libcurl passes back the lock array of a live `Shared`
+    let locks = unsafe { &*userptr.cast::<[DataLock; LOCK_KINDS]>() };
+    if let Some(lock) = usize::try_from(data).ok().and_then(|data| locks.get(data)) {
+        lock.lock();
+    }
+}
+
+/// Share unlock callback, the counterpart of `lock_data`
+extern "C" fn unlock_data(_handle: *mut CurlHandle, data: c_int, userptr: *mut c_void) {
+    // SAFETY: as in `lock_data`
+    let locks = unsafe { &*userptr.cast::<[DataLock; LOCK_KINDS]>() };
+    if let Some(lock) = usize::try_from(data).ok().and_then(|data| locks.get(data)) {
+        lock.unlock();
+    }
+}
+
+/// `curl_slist` of request headers, freed on drop
+struct HeaderList<'a> {
+    api: &'a Api,
+    list: *mut CurlSlist,
+}
+
+impl<'a> HeaderList<'a> {
+    fn new(api: &'a Api, headers: &[String]) -> Result<Self> {
+        let mut list = HeaderList {
+            api,
+            list: std::ptr::null_mut(),
+        };
+        for header in headers {
+            let header = c_string(header)?;
+            // SAFETY: curl_slist_append copies the string
+            let appended = unsafe { (api.slist_append)(list.list, header.as_ptr()) };
+            if appended.is_null() {
+                return Err(CuimpError::RequestFailed(
+                    "curl_slist_append failed".to_string(),
+                ));
+            }
+            list.list = appended;
+        }
+        Ok(list)
+    }
+}
+
+impl Drop for HeaderList<'_> {
+    fn drop(&mut self) {
+        if !self.list.is_null() {
+            // SAFETY: the list was built with curl_slist_append
+            unsafe { (self.api.slist_free_all)(self.list) };
+        }
+    }
+}
+
+/// Write and header callback collecting data into the `Vec<u8>` passed as userdata
+extern "C" fn append(
+    data: *const c_char,
+    size: usize,
+    count: usize,
+    userdata: *mut c_void,
+) -> usize {
+    let len = size.saturating_mul(count);
+    if data.is_null() || userdata.is_null() {
+        return 0;
+    }
+    // SAFETY: userdata is the `Vec<u8>` set with CURLOPT_WRITEDATA/HEADERDATA and
+    // libcurl passes `len` readable bytes at `data`
+    unsafe {
+        let buffer = &mut *userdata.cast::<Vec<u8>>();
+        buffer.extend_from_slice(std::slice::from_raw_parts(data.cast::<u8>(), len));
+    }
+    len
+}
+
+fn c_string(value: &str) -> Result<CString> {
+    CString::new(value)
+        .map_err(|_| CuimpError::InvalidConfig(format!("Value contains a NUL byte: {:?}", value)))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easy_request_from_args() {
+        let args: Vec<String> = [
+            "-X",
+            "PUT",
+            "--location",
+            "--max-redirs",
+            "5",
+            "--proxy",
+            "http://p:8080",
+            "-k",
+            "-H",
+            "Accept: */*",
+            "--data-binary",
+            "@-",
+            "--speed-limit",
+            "1000",
+            "--speed-time",
+            "15",
+            "--limit-rate",
+            "50000",
+            "-i",
+            "https://example.com/",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let request = EasyRequest::from_args(&args, Some(b"\0bytes"), Some(1500)).unwrap();
+        assert_eq!(
+            request,
+            EasyRequest {
+                url: "https://example.com/".to_string(),
+                method: Some("PUT".to_string()),
+                follow_redirects: true,
+                max_redirects: Some(5),
+                proxy: Some("http://p:8080".to_string()),
+                insecure: true,
+                headers: vec!["Accept: */*".to_string()],
+                body: Some(b"\0bytes".to_vec()),
+                timeout_ms: Some(1500),
+                low_speed_limit: Some(1000),
+                low_speed_time: Some(15),
+                max_rate: Some(50000),
+                ..Default::default()
+            }
+        );
+
+        let args: Vec<String> = [
+            "--location",
+            "--location-trusted",
+            "--proto-redir",
+            "=https",
+            "--path-as-is",
+            "https://example.com/a/../b",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let request = EasyRequest::from_args(&args, None, None).unwrap();
+        assert!(request.follow_redirects && request.location_trusted && request.path_as_is);
+        assert_eq!(request.redirect_protocols.as_deref(), Some("https"));
+
+        let unsupported = [
+            "--retry".to_string(),
+            "3".to_string(),
+            "https://example.com/".to_string(),
+        ];
+        assert!(matches!(
+            EasyRequest::from_args(&unsupported, None, None),
+            Err(CuimpError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_impersonate_target() {
+        assert_eq!(
+            impersonate_target("/opt/bin/curl_chrome124", None).unwrap(),
+            "chrome124"
+        );
+        assert_eq!(
+            impersonate_target("/opt/bin/curl-impersonate", Some("firefox 133".to_string()))
+                .unwrap(),
+            "firefox133"
+        );
+        assert!(
+            impersonate_target("/opt/bin/curl-impersonate", Some("chrome".to_string())).is_err()
+        );
+    }
+
+    #[test]
+    fn test_missing_library() {
+        let err = Shared::open(
+            Some(Path::new("/nonexistent/libcurl-impersonate.so")),
+            "curl",
+        )
+        .unwrap_err();
+        assert!(matches!(err, CuimpError::BinaryNotFound(_)));
+    }
+
+    #[test]
+    fn test_share_locks() {
+        let locks: Arc<[DataLock; LOCK_KINDS]> = Arc::new(Default::default());
+        let userptr =
+            |locks: &Arc<[DataLock; LOCK_KINDS]>| Arc::as_ptr(locks).cast_mut().cast::<c_void>();
+        lock_data(
+            std::ptr::null_mut(),
+            CURL_LOCK_DATA_CONNECT,
+            0,
+            userptr(&locks),
+        );
+
+        let waiter = {
+            let locks = Arc::clone(&locks);
+            std::thread::spawn(move || {
+                lock_data(
+                    std::ptr::null_mut(),
+                    CURL_LOCK_DATA_CONNECT,
+                    0,
+                    userptr(&locks),
+                );
+                unlock_data(
+                    std::ptr::null_mut(),
+                    CURL_LOCK_DATA_CONNECT,
+                    userptr(&locks),
+                );
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+        // Other kinds and out-of-range values do not block
+        lock_data(std::ptr::null_mut(), CURL_LOCK_DATA_DNS, 0, userptr(&locks));
+        lock_data(std::ptr::null_mut(), 99, 0, userptr(&locks));
+
+        unlock_data(
+            std::ptr::null_mut(),
+            CURL_LOCK_DATA_CONNECT,
+            userptr(&locks),
+        );
+        waiter.join().unwrap();
+    }
+}
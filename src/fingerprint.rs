@@ -0,0 +1,250 @@
+use crate::error::{CuimpError, Result};
+
+/// ClientHello overrides applied on top of the impersonation target's TLS preset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsOptions {
+    /// Cipher suites in order, by OpenSSL/BoringSSL name (`--ciphers`)
+    pub ciphers: Option<Vec<String>>,
+    /// Supported groups in order, e.g. `X25519`, `P-256` (`--curves`)
+    pub curves: Option<Vec<String>>,
+    /// Shuffle the TLS extension order on every connection, as Chrome 110+ does
+    /// (`--tls-permute-extensions`)
+    pub permute_extensions: bool,
+}
+
+impl TlsOptions {
+    pub(crate) fn to_args(&self) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(ciphers) = &self.ciphers {
+            args.push("--ciphers".to_string());
+            args.push(join_names("cipher", ciphers)?);
+        }
+        if let Some(curves) = &self.curves {
+            args.push("--curves".to_string());
+            args.push(join_names("curve", curves)?);
+        }
+        if self.permute_extensions {
+            args.push("--tls-permute-extensions".to_string());
+        }
+        Ok(args)
+    }
+}
+
+/// HTTP/2 connection preface and header-frame settings (the Akamai HTTP/2 fingerprint)
+///
+/// `Http2Fingerprint::from_akamai("1:65536;2:0;4:6291456;6:262144|15663105|0|m,a,s,p")`
+/// reads a fingerprint in Akamai's `SETTINGS|WINDOW_UPDATE|PRIORITY|PSEUDO_HEADERS` format.
+/// curl-impersonate cannot send standalone PRIORITY frames, so that part must be `0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Http2Fingerprint {
+    /// SETTINGS frame entries in order, as `(identifier, value)` (`--http2-settings`)
+    pub settings: Vec<(u16, u32)>,
+    /// Connection WINDOW_UPDATE increment (`--http2-window-update`)
+    pub window_update: Option<u32>,
+    /// Pseudo-header order as letters of `m`ethod, `a`uthority, `s`cheme and `p`ath,
+    /// e.g. `"masp"` (`--http2-pseudo-headers-order`)
+    pub pseudo_header_order: Option<String>,
+    /// Weight (1-256) of the request stream's HEADERS priority (`--http2-stream-weight`)
+    pub stream_weight: Option<u16>,
+    /// Exclusive flag of the request stream's HEADERS priority (`--http2-stream-exclusive`)
+    pub stream_exclusive: Option<bool>,
+    /// Disable server push in SETTINGS (`--http2-no-server-push`)
+    pub no_server_push: bool,
+}
+
+impl Http2Fingerprint {
+    /// Parse an Akamai fingerprint string
+    pub fn from_akamai(fingerprint: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            CuimpError::InvalidConfig(format!(
+                "Invalid HTTP/2 fingerprint {:?}: {}",
+                fingerprint, reason
+            ))
+        };
+        let parts: Vec<&str> = fingerprint.trim().split('|').collect();
+        let [settings, window_update, priority, pseudo_headers] = parts.as_slice() else {
+            return Err(invalid(
+                "expected SETTINGS|WINDOW_UPDATE|PRIORITY|PSEUDO_HEADERS",
+            ));
+        };
+
+        let settings = settings
+            .split(';')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (id, value) = entry
+                    .split_once(':')
+                    .ok_or_else(|| invalid("SETTINGS entries are id:value"))?;
+                Ok((
+                    id.parse().map_err(|_| invalid("bad SETTINGS identifier"))?,
+                    value.parse().map_err(|_| invalid("bad SETTINGS value"))?,
+                ))
+            })
+            .collect::<Result<Vec<(u16, u32)>>>()?;
+        let window_update = match window_update.parse::<u32>() {
+            Ok(0) => None,
+            Ok(increment) => Some(increment),
+            Err(_) => return Err(invalid("bad WINDOW_UPDATE increment")),
+        };
+        if *priority != "0" {
+            return Err(invalid("PRIORITY frames are not supported"));
+        }
+        let pseudo_header_order: String = pseudo_headers.split(',').collect();
+
+        Ok(Http2Fingerprint {
+            settings,
+            window_update,
+            pseudo_header_order: Some(pseudo_header_order).filter(|order| !order.is_empty()),
+            ..Default::default()
+        })
+    }
+
+    /// This fingerprint in Akamai's format
+    pub fn to_akamai(&self) -> String {
+        let settings: Vec<String> = self
+            .settings
+            .iter()
+            .map(|(id, value)| format!("{}:{}", id, value))
+            .collect();
+        let pseudo_headers: Vec<String> = self
+            .pseudo_header_order
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .map(String::from)
+            .collect();
+        format!(
+            "{}|{}|0|{}",
+            settings.join(";"),
+            self.window_update.unwrap_or(0),
+            pseudo_headers.join(",")
+        )
+    }
+
+    pub(crate) fn to_args(&self) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        if !self.settings.is_empty() {
+            let settings: Vec<String> = self
+                .settings
+                .iter()
+                .map(|(id, value)| format!("{}:{}", id, value))
+                .collect();
+            args.push("--http2-settings".to_string());
+            args.push(settings.join(";"));
+        }
+        if let Some(increment) = self.window_update {
+            args.push("--http2-window-update".to_string());
+            args.push(increment.to_string());
+        }
+        if let Some(order) = &self.pseudo_header_order {
+            let mut letters: Vec<char> = order.chars().collect();
+            letters.sort_unstable();
+            if letters != ['a', 'm', 'p', 's'] {
+                return Err(CuimpError::InvalidConfig(format!(
+                    "Pseudo-header order must use each of m, a, s and p once: {:?}",
+                    order
+                )));
+            }
+            args.push("--http2-pseudo-headers-order".to_string());
+            args.push(order.clone());
+        }
+        if let Some(weight) = self.stream_weight {
+            if !(1..=256).contains(&weight) {
+                return Err(CuimpError::InvalidConfig(format!(
+                    "HTTP/2 stream weight must be 1-256: {}",
+                    weight
+                )));
+            }
+            args.push("--http2-stream-weight".to_string());
+            args.push(weight.to_string());
+        }
+        if let Some(exclusive) = self.stream_exclusive {
+            args.push("--http2-stream-exclusive".to_string());
+            args.push(u8::from(exclusive).to_string());
+        }
+        if self.no_server_push {
+            args.push("--http2-no-server-push".to_string());
+        }
+        Ok(args)
+    }
+}
+
+/// Colon-separated list as curl expects it
+fn join_names(kind: &str, names: &[String]) -> Result<String> {
+    if names.is_empty() {
+        return Err(CuimpError::InvalidConfig(format!("Empty {} list", kind)));
+    }
+    if let Some(bad) = names
+        .iter()
+        .find(|name| name.is_empty() || name.contains(':') || name.chars().any(char::is_whitespace))
+    {
+        return Err(CuimpError::InvalidConfig(format!(
+            "Invalid {} name: {:?}",
+            kind, bad
+        )));
+    }
+    Ok(names.join(":"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_options_args() {
+        let tls = TlsOptions {
+            ciphers: Some(vec![
+                "TLS_AES_128_GCM_SHA256".to_string(),
+                "ECDHE-ECDSA-AES128-GCM-SHA256".to_string(),
+            ]),
+            curves: Some(vec!["X25519".to_string(), "P-256".to_string()]),
+            permute_extensions: true,
+        };
+        assert_eq!(
+            tls.to_args().unwrap(),
+            [
+                "--ciphers",
+                "TLS_AES_128_GCM_SHA256:ECDHE-ECDSA-AES128-GCM-SHA256",
+                "--curves",
+                "X25519:P-256",
+                "--tls-permute-extensions"
+            ]
+        );
+
+        let bad = TlsOptions {
+            curves: Some(vec!["X25519:P-256".to_string()]),
+            ..Default::default()
+        };
+        assert!(matches!(bad.to_args(), Err(CuimpError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_http2_fingerprint() {
+        let akamai = "1:65536;2:0;4:6291456;6:262144|15663105|0|m,a,s,p";
+        let fingerprint = Http2Fingerprint::from_akamai(akamai).unwrap();
+        assert_eq!(
+            fingerprint.settings,
+            [(1, 65536), (2, 0), (4, 6291456), (6, 262144)]
+        );
+        assert_eq!(fingerprint.to_akamai(), akamai);
+        assert_eq!(
+            fingerprint.to_args().unwrap(),
+            [
+                "--http2-settings",
+                "1:65536;2:0;4:6291456;6:262144",
+                "--http2-window-update",
+                "15663105",
+                "--http2-pseudo-headers-order",
+                "masp"
+            ]
+        );
+
+        assert!(Http2Fingerprint::from_akamai("1:65536|0|3:0:0:201|m,p,a,s").is_err());
+        let bad_order = Http2Fingerprint {
+            pseudo_header_order: Some("mass".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_order.to_args().is_err());
+    }
+}
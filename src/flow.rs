@@ -0,0 +1,279 @@
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::types::{CuimpRequestConfig, CuimpResponse};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Where a flow variable is read from in a step's response
+#[derive(Debug, Clone)]
+pub enum Extract {
+    /// JSON pointer into the parsed body, e.g. `/data/token`
+    JsonPointer(String),
+    /// Regex over the body text; yields the first capture group, or the whole match
+    Regex(String),
+    /// Cookie value by name, from the client's jar cookies for the step's URL or else
+    /// the cookies the response set
+    Cookie(String),
+    /// Response header value (case-insensitive)
+    Header(String),
+}
+
+/// One request in a [`Flow`]
+///
+/// `{{name}}` placeholders in the URL, header and param values, and string values
+/// of `data` are replaced with flow variables before the request is sent.
+#[derive(Debug, Clone)]
+pub struct FlowStep {
+    pub config: CuimpRequestConfig,
+    pub extracts: Vec<(String, Extract)>,
+}
+
+impl FlowStep {
+    pub fn new(config: CuimpRequestConfig) -> Self {
+        FlowStep {
+            config,
+            extracts: Vec::new(),
+        }
+    }
+
+    /// Store a value from this step's response as variable `name`
+    pub fn extract(mut self, name: impl Into<String>, extract: Extract) -> Self {
+        self.extracts.push((name.into(), extract));
+        self
+    }
+}
+
+/// Result of a completed flow
+#[derive(Debug, Clone)]
+pub struct FlowResult {
+    /// Initial variables plus everything extracted along the way
+    pub vars: HashMap<String, String>,
+    /// Response of every step, in order
+    pub responses: Vec<CuimpResponse<Value>>,
+}
+
+/// Sequence of dependent requests, e.g. login → fetch CSRF token → submit
+///
+/// ```rust,no_run
+/// # use cuimp::{CuimpHttp, CuimpRequestConfig, Extract, Flow, FlowStep, Method};
+/// # async fn run(client: &mut CuimpHttp) -> cuimp::Result<()> {
+/// let result = Flow::new()
+///     .var("user", "alice")
+///     .step(
+///         FlowStep::new(CuimpRequestConfig {
+///             url: Some("https://example.com/login".to_string()),
+///             ..Default::default()
+///         })
+///         .extract("csrf", Extract::Regex(r#"name="csrf" value="([^"]+)""#.to_string())),
+///     )
+///     .step(FlowStep::new(CuimpRequestConfig {
+///         url: Some("https://example.com/login".to_string()),
+///         method: Some(Method::POST),
+///         data: Some(serde_json::json!({ "user": "{{user}}", "csrf": "{{csrf}}" })),
+///         ..Default::default()
+///     }))
+///     .run(client)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Flow {
+    steps: Vec<FlowStep>,
+    vars: HashMap<String, String>,
+}
+
+impl Flow {
+    pub fn new() -> Self {
+        Flow::default()
+    }
+
+    /// Set an initial variable
+    pub fn var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Append a step
+    pub fn step(mut self, step: FlowStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Run the steps in order, stopping at the first failed request or extraction
+    pub async fn run(self, client: &mut CuimpHttp) -> Result<FlowResult> {
+        let mut vars = self.vars;
+        let mut responses = Vec::with_capacity(self.steps.len());
+
+        for (index, step) in self.steps.into_iter().enumerate() {
+            let config = render_config(step.config, &vars).map_err(|e| {
+                CuimpError::InvalidConfig(format!("Flow step {}: {}", index + 1, e))
+            })?;
+            let response = client.request::<Value>(config).await?;
+
+            for (name, extract) in &step.extracts {
+                let value = extract_value(extract, &response, client).ok_or_else(|| {
+                    CuimpError::ParseError(format!(
+                        "Flow step {}: could not extract `{}` ({:?})",
+                        index + 1,
+                        name,
+                        extract
+                    ))
+                })??;
+                vars.insert(name.clone(), value);
+            }
+            responses.push(response);
+        }
+
+        Ok(FlowResult { vars, responses })
+    }
+}
+
+/// `Some(Err)` for an invalid extractor, `None` when the value is absent
+fn extract_value(
+    extract: &Extract,
+    response: &CuimpResponse<Value>,
+    client: &CuimpHttp,
+) -> Option<Result<String>> {
+    match extract {
+        Extract::JsonPointer(pointer) => response.data.pointer(pointer).map(|value| {
+            Ok(match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+        }),
+        Extract::Regex(pattern) => {
+            let regex = match Regex::new(pattern) {
+                Ok(regex) => regex,
+                Err(e) => {
+                    return Some(Err(CuimpError::InvalidConfig(format!(
+                        "Invalid regex {:?}: {}",
+                        pattern, e
+                    ))))
+                }
+            };
+            let body = String::from_utf8_lossy(&response.raw_body);
+            let captures = regex.captures(&body)?;
+            captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|m| Ok(m.as_str().to_string()))
+        }
+        Extract::Cookie(name) => client
+            .cookie_jar()
+            .and_then(|jar| {
+                let cookies = jar.cookies_for(&response.request.url);
+                cookies
+                    .into_iter()
+                    .find(|c| &c.name == name)
+                    .map(|c| c.value.clone())
+            })
+            .or_else(|| {
+                response
+                    .cookies
+                    .iter()
+                    .find(|c| &c.name == name)
+                    .map(|c| c.value.clone())
+            })
+            .map(Ok),
+        Extract::Header(name) => response
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| Ok(v.clone())),
+    }
+}
+
+fn render_config(
+    mut config: CuimpRequestConfig,
+    vars: &HashMap<String, String>,
+) -> Result<CuimpRequestConfig> {
+    if let Some(url) = config.url.as_mut() {
+        *url = render(url, vars)?;
+    }
+    for map in [config.headers.as_mut(), config.params.as_mut()]
+        .into_iter()
+        .flatten()
+    {
+        for value in map.values_mut() {
+            *value = render(value, vars)?;
+        }
+    }
+    if let Some(data) = config.data.as_mut() {
+        render_value(data, vars)?;
+    }
+    Ok(config)
+}
+
+fn render_value(value: &mut Value, vars: &HashMap<String, String>) -> Result<()> {
+    match value {
+        Value::String(s) => *s = render(s, vars)?,
+        Value::Array(items) => items
+            .iter_mut()
+            .try_for_each(|item| render_value(item, vars))?,
+        Value::Object(map) => map
+            .values_mut()
+            .try_for_each(|item| render_value(item, vars))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replace `{{name}}` placeholders; unknown variables are an error
+fn render(template: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let (before, after_open) = rest.split_at(start);
+        out.push_str(before);
+        let after_open = after_open.get(2..).unwrap_or_default();
+        let end = after_open.find("}}").ok_or_else(|| {
+            CuimpError::InvalidConfig(format!("Unclosed placeholder in {:?}", template))
+        })?;
+        let (name, after_close) = after_open.split_at(end);
+        let value = vars.get(name.trim()).ok_or_else(|| {
+            CuimpError::InvalidConfig(format!("Unknown flow variable `{}`", name.trim()))
+        })?;
+        out.push_str(value);
+        rest = after_close.get(2..).unwrap_or_default();
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_config() {
+        let vars = HashMap::from([
+            ("csrf".to_string(), "t0k".to_string()),
+            ("id".to_string(), "7".to_string()),
+        ]);
+        let config = CuimpRequestConfig {
+            url: Some("https://example.com/items/{{id}}".to_string()),
+            headers: Some(HashMap::from([(
+                "X-CSRF-Token".to_string(),
+                "{{ csrf }}".to_string(),
+            )])),
+            data: Some(json!({ "token": "{{csrf}}", "list": ["{{id}}", 1] })),
+            ..Default::default()
+        };
+
+        let rendered = render_config(config, &vars).unwrap();
+        assert_eq!(rendered.url.as_deref(), Some("https://example.com/items/7"));
+        assert_eq!(rendered.headers.unwrap()["X-CSRF-Token"], "t0k");
+        assert_eq!(
+            rendered.data,
+            Some(json!({ "token": "t0k", "list": ["7", 1] }))
+        );
+
+        assert!(render("{{missing}}", &vars).is_err());
+        assert!(render("{{csrf", &vars).is_err());
+    }
+}
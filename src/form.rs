@@ -0,0 +1,422 @@
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::html::{decode_entities, find_tags, Tag};
+use crate::types::{CuimpRequestConfig, CuimpResponse, ExtraArgsMerge, Method};
+use serde_json::Value;
+use std::collections::HashMap;
+use url::Url;
+
+/// An HTML form parsed from a response, ready to be filled in and submitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    /// Absolute submission URL
+    pub action: Url,
+    pub method: Method,
+    /// `application/x-www-form-urlencoded` or `multipart/form-data`
+    pub enctype: String,
+    /// Successful controls in document order, as a browser would submit them
+    pub fields: Vec<(String, String)>,
+    /// Page the form came from, sent as `Referer`
+    pub page_url: Url,
+}
+
+impl<T> CuimpResponse<T> {
+    /// Parse the first `<form>` matching `selector`.
+    ///
+    /// Supports compound selectors made of a tag name, `#id`, `.class` and
+    /// `[attr]`/`[attr=value]` parts, e.g. `form#login` or `form[action="/search"]`.
+    pub fn form(&self, selector: &str) -> Result<Form> {
+        let html = String::from_utf8_lossy(&self.raw_body);
+        Form::parse(&html, &self.request.url, selector)
+    }
+}
+
+impl Form {
+    /// Parse the first form matching `selector` in `html` served from `page_url`
+    pub fn parse(html: &str, page_url: &Url, selector: &str) -> Result<Form> {
+        let selector = Selector::parse(selector)?;
+        let form_tag = find_tags(html, &["form"])
+            .into_iter()
+            .find(|tag| selector.matches(tag))
+            .ok_or_else(|| {
+                CuimpError::ParseError(format!("No form matches selector {:?}", selector.source))
+            })?;
+
+        let body_end = find_ascii_ci(html, "</form", form_tag.end).unwrap_or(html.len());
+        let body = html.get(form_tag.end..body_end).unwrap_or_default();
+
+        let action = match form_tag
+            .attr("action")
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+        {
+            Some(action) => page_url
+                .join(action)
+                .map_err(|e| CuimpError::InvalidUrl(format!("Form action {:?}: {}", action, e)))?,
+            None => page_url.clone(),
+        };
+        let method = match form_tag
+            .attr("method")
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("post") => Method::POST,
+            _ => Method::GET,
+        };
+        let enctype = match form_tag
+            .attr("enctype")
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("multipart/form-data") => "multipart/form-data",
+            _ => "application/x-www-form-urlencoded",
+        };
+
+        Ok(Form {
+            action,
+            method,
+            enctype: enctype.to_string(),
+            fields: collect_fields(body),
+            page_url: page_url.clone(),
+        })
+    }
+
+    /// Set field values, replacing the first existing field of the same name or appending
+    pub fn fill<I, K, V>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        for (name, value) in fields {
+            self.set(name, value);
+        }
+        self
+    }
+
+    /// Set one field value
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.fields.iter_mut().find(|(n, _)| *n == name) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((name, value)),
+        }
+    }
+
+    /// Value of the first field named `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Request configuration that submits this form
+    pub fn to_request(&self) -> CuimpRequestConfig {
+        let mut headers = HashMap::from([("Referer".to_string(), self.page_url.to_string())]);
+
+        if self.method == Method::GET {
+            let mut url = self.action.clone();
+            url.set_fragment(None);
+            url.set_query(None);
+            if !self.fields.is_empty() {
+                url.query_pairs_mut().extend_pairs(&self.fields);
+            }
+            return CuimpRequestConfig {
+                url: Some(url.to_string()),
+                method: Some(Method::GET),
+                headers: Some(headers),
+                ..Default::default()
+            };
+        }
+
+        headers.insert(
+            "Origin".to_string(),
+            self.page_url.origin().ascii_serialization(),
+        );
+        let mut config = CuimpRequestConfig {
+            url: Some(self.action.to_string()),
+            method: Some(Method::POST),
+            ..Default::default()
+        };
+
+        if self.enctype == "multipart/form-data" {
+            // --form-string never interprets `@file` or `<file` in values
+            let args = self
+                .fields
+                .iter()
+                .flat_map(|(name, value)| {
+                    ["--form-string".to_string(), format!("{}={}", name, value)]
+                })
+                .collect();
+            config.extra_curl_args = Some(args);
+            config.extra_curl_args_merge = Some(ExtraArgsMerge::Append);
+        } else {
+            let body = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.fields)
+                .finish();
+            headers.insert(
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            );
+            config.data = Some(Value::String(body));
+        }
+
+        config.headers = Some(headers);
+        config
+    }
+
+    /// Submit the form through `client`
+    pub async fn submit(&self, client: &mut CuimpHttp) -> Result<CuimpResponse<Value>> {
+        client.request(self.to_request()).await
+    }
+}
+
+/// Controls a browser would include in the form data set
+fn collect_fields(body: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+
+    for tag in find_tags(body, &["input", "select", "textarea"]) {
+        let Some(name) = tag.attr("name").filter(|n| !n.is_empty()) else {
+            continue;
+        };
+        if tag.attrs.contains_key("disabled") {
+            continue;
+        }
+
+        match tag.name.as_str() {
+            "input" => {
+                let kind = tag.attr("type").unwrap_or("text").to_ascii_lowercase();
+                let value = tag.attr("value");
+                match kind.as_str() {
+                    "submit" | "button" | "image" | "reset" | "file" => {}
+                    "checkbox" | "radio" => {
+                        if tag.attrs.contains_key("checked") {
+                            fields.push((name.to_string(), value.unwrap_or("on").to_string()));
+                        }
+                    }
+                    _ => fields.push((name.to_string(), value.unwrap_or_default().to_string())),
+                }
+            }
+            "textarea" => {
+                let end = find_ascii_ci(body, "</textarea", tag.end).unwrap_or(body.len());
+                let text = body.get(tag.end..end).unwrap_or_default();
+                // A leading newline right after the start tag is ignored by browsers
+                let text = text
+                    .strip_prefix("\r\n")
+                    .or_else(|| text.strip_prefix('\n'))
+                    .unwrap_or(text);
+                fields.push((name.to_string(), decode_entities(text)));
+            }
+            "select" => {
+                let end = find_ascii_ci(body, "</select", tag.end).unwrap_or(body.len());
+                let options_html = body.get(tag.end..end).unwrap_or_default();
+                let options: Vec<(Tag, String)> = find_tags(options_html, &["option"])
+                    .into_iter()
+                    .map(|option| {
+                        let value = option.attr("value").map(str::to_string).unwrap_or_else(|| {
+                            let text_end = options_html
+                                .get(option.end..)
+                                .and_then(|rest| rest.find('<'))
+                                .map_or(options_html.len(), |i| option.end + i);
+                            decode_entities(
+                                options_html
+                                    .get(option.end..text_end)
+                                    .unwrap_or_default()
+                                    .trim(),
+                            )
+                        });
+                        (option, value)
+                    })
+                    .filter(|(option, _)| !option.attrs.contains_key("disabled"))
+                    .collect();
+
+                let selected: Vec<&String> = options
+                    .iter()
+                    .filter(|(option, _)| option.attrs.contains_key("selected"))
+                    .map(|(_, value)| value)
+                    .collect();
+                if tag.attrs.contains_key("multiple") {
+                    fields.extend(
+                        selected
+                            .into_iter()
+                            .map(|value| (name.to_string(), value.clone())),
+                    );
+                } else if let Some(value) = selected
+                    .last()
+                    .copied()
+                    .or_else(|| options.first().map(|(_, v)| v))
+                {
+                    fields.push((name.to_string(), value.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+/// Case-insensitive search for an ASCII `needle` at or after `from`
+fn find_ascii_ci(haystack: &str, needle: &str, from: usize) -> Option<usize> {
+    let bytes = haystack.as_bytes().get(from..)?;
+    bytes
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+        .map(|i| from + i)
+}
+
+/// Compound CSS selector: `tag#id.class[attr=value]`
+#[derive(Debug)]
+struct Selector {
+    source: String,
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<(String, Option<String>)>,
+}
+
+impl Selector {
+    fn parse(source: &str) -> Result<Selector> {
+        let invalid =
+            || CuimpError::InvalidConfig(format!("Unsupported form selector {:?}", source));
+        let mut selector = Selector {
+            source: source.to_string(),
+            tag: None,
+            id: None,
+            classes: Vec::new(),
+            attrs: Vec::new(),
+        };
+
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        let take_ident = |s: &str| -> (String, usize) {
+            let len = s.find(|c: char| !is_ident(c)).unwrap_or(s.len());
+            (s.get(..len).unwrap_or_default().to_string(), len)
+        };
+
+        let mut rest = source.trim();
+        let (tag, len) = take_ident(rest);
+        if !tag.is_empty() {
+            selector.tag = Some(tag.to_ascii_lowercase());
+            rest = rest.get(len..).unwrap_or_default();
+        }
+
+        while let Some(first) = rest.chars().next() {
+            let after = rest.get(1..).unwrap_or_default();
+            match first {
+                '#' | '.' => {
+                    let (ident, len) = take_ident(after);
+                    if ident.is_empty() {
+                        return Err(invalid());
+                    }
+                    if first == '#' {
+                        selector.id = Some(ident);
+                    } else {
+                        selector.classes.push(ident);
+                    }
+                    rest = after.get(len..).unwrap_or_default();
+                }
+                '[' => {
+                    let close = after.find(']').ok_or_else(invalid)?;
+                    let inner = after.get(..close).unwrap_or_default();
+                    let attr = match inner.split_once('=') {
+                        Some((name, value)) => (
+                            name.trim().to_ascii_lowercase(),
+                            Some(value.trim().trim_matches(['"', '\'']).to_string()),
+                        ),
+                        None => (inner.trim().to_ascii_lowercase(), None),
+                    };
+                    if attr.0.is_empty() || !attr.0.chars().all(is_ident) {
+                        return Err(invalid());
+                    }
+                    selector.attrs.push(attr);
+                    rest = after.get(close + 1..).unwrap_or_default();
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(selector)
+    }
+
+    fn matches(&self, tag: &Tag) -> bool {
+        self.tag.as_ref().is_none_or(|name| *name == tag.name)
+            && self.id.as_ref().is_none_or(|id| tag.attr("id") == Some(id))
+            && self.classes.iter().all(|class| {
+                tag.attr("class")
+                    .is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+            })
+            && self.attrs.iter().all(|(name, value)| match value {
+                Some(value) => tag.attr(name) == Some(value),
+                None => tag.attrs.contains_key(name),
+            })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"
+        <form id="search" action="/search"><input name="q"></form>
+        <form id="login" class="auth wide" method="POST" action="session?x=1">
+          <input type="hidden" name="token" value="t&amp;1">
+          <input name="user" value="default">
+          <input type="password" name="pass">
+          <input type="checkbox" name="remember" checked>
+          <input type="checkbox" name="newsletter" value="yes">
+          <input type="radio" name="plan" value="free">
+          <input type="radio" name="plan" value="pro" checked>
+          <input name="gone" value="x" disabled>
+          <select name="lang"><option value="en">English<option selected>Deutsch</select>
+          <textarea name="bio">
+hello &lt;world&gt;</textarea>
+          <input type="submit" name="go" value="Sign in">
+        </form>"#;
+
+    #[test]
+    fn test_parse_form() {
+        let page = Url::parse("https://example.com/account/login").unwrap();
+        let form = Form::parse(PAGE, &page, "form.auth[method=POST]").unwrap();
+
+        assert_eq!(
+            form.action.as_str(),
+            "https://example.com/account/session?x=1"
+        );
+        assert_eq!(form.method, Method::POST);
+        assert_eq!(
+            form.fields,
+            [
+                ("token", "t&1"),
+                ("user", "default"),
+                ("pass", ""),
+                ("remember", "on"),
+                ("plan", "pro"),
+                ("lang", "Deutsch"),
+                ("bio", "hello <world>"),
+            ]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+
+        let request = form
+            .fill([("user", "alice"), ("pass", "s3cret")])
+            .to_request();
+        assert_eq!(
+            request.data,
+            Some(Value::String(
+                "token=t%261&user=alice&pass=s3cret&remember=on&plan=pro&lang=Deutsch&bio=hello+%3Cworld%3E".to_string()
+            ))
+        );
+        assert_eq!(request.headers.unwrap()["Origin"], "https://example.com");
+
+        let search = Form::parse(PAGE, &page, "#search")
+            .unwrap()
+            .fill([("q", "a b")])
+            .to_request();
+        assert_eq!(
+            search.url.as_deref(),
+            Some("https://example.com/search?q=a+b")
+        );
+        assert!(Form::parse(PAGE, &page, "form#missing").is_err());
+        assert!(Form::parse(PAGE, &page, "div > form").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Rendering through an external headless browser.
+//!
+//! Pages that need JavaScript can be sent to a [`Renderer`] with
+//! `CuimpHttp::render`. The request carries the session's headers, cookies and
+//! proxy, and cookies set while rendering are stored back in the session jar, so
+//! static and rendered fetches share one session and one response type.
+
+use crate::client::CuimpHttp;
+use crate::cookie::Cookie;
+use crate::error::Result;
+use crate::into_url::unicode_host;
+use crate::types::{CuimpRequestConfig, CuimpResponse, RequestInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+
+/// A page to render, with the session context of the client that sends it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderRequest {
+    pub url: String,
+    /// Headers the client would have sent, without `Cookie`
+    pub headers: HashMap<String, String>,
+    /// Session cookies for the URL
+    pub cookies: Vec<Cookie>,
+    pub proxy: Option<String>,
+    /// CSS selector to wait for before taking the DOM
+    pub wait_for: Option<String>,
+    pub timeout_ms: Option<u64>,
+}
+
+/// The DOM of a rendered page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedPage {
+    /// URL after redirects and client-side navigation
+    pub url: String,
+    /// Status of the main document, if the renderer reports it
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Serialized DOM after scripts ran
+    pub html: String,
+    /// Cookies of the browser context after rendering
+    #[serde(default)]
+    pub cookies: Vec<Cookie>,
+}
+
+/// A headless browser that renders pages
+pub trait Renderer {
+    fn render(&self, request: RenderRequest) -> impl Future<Output = Result<RenderedPage>> + Send;
+}
+
+/// Renderer for an HTTP endpoint that accepts a `RenderRequest` as JSON and
+/// answers with a `RenderedPage` as JSON
+#[cfg(feature = "headless")]
+#[derive(Debug, Clone)]
+pub struct HttpRenderer {
+    endpoint: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "headless")]
+impl HttpRenderer {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        HttpRenderer {
+            endpoint: endpoint.into(),
+            token: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send `Authorization: Bearer <token>` to the endpoint
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+}
+
+#[cfg(feature = "headless")]
+impl Renderer for HttpRenderer {
+    fn render(&self, request: RenderRequest) -> impl Future<Output = Result<RenderedPage>> + Send {
+        use crate::error::CuimpError;
+
+        let mut builder = self.client.post(&self.endpoint).json(&request);
+        if let Some(token) = &self.token {
+            builder = builder.bearer_auth(token);
+        }
+        async move {
+            let response = builder.send().await.map_err(|e| {
+                CuimpError::RequestFailed(format!("Renderer request failed: {}", e))
+            })?;
+            if !response.status().is_success() {
+                return Err(CuimpError::HttpError(format!(
+                    "Renderer returned {}",
+                    response.status()
+                )));
+            }
+            response.json::<RenderedPage>().await.map_err(|e| {
+                CuimpError::InvalidResponse(format!("Invalid renderer response: {}", e))
+            })
+        }
+    }
+}
+
+impl CuimpHttp {
+    /// Render a page with `renderer` instead of fetching it with curl.
+    ///
+    /// The request is prepared like any other (headers, locale, cookies, proxy), so the
+    /// binary is resolved as well. `wait_for` is a CSS selector passed to the renderer.
+    /// The response body is the rendered HTML, and the renderer's cookies are stored
+    /// in the cookie jar when `CuimpOptions::cookie_store` is on.
+    pub async fn render<R: Renderer>(
+        &mut self,
+        renderer: &R,
+        config: CuimpRequestConfig,
+        wait_for: Option<&str>,
+    ) -> Result<CuimpResponse<Value>> {
+        let prepared = self.prepare(&config, &[]).await?;
+
+        let mut headers = prepared.headers.clone();
+        let cookie_key = headers
+            .keys()
+            .find(|k| k.eq_ignore_ascii_case("cookie"))
+            .cloned();
+        let cookie_header = cookie_key.and_then(|key| headers.remove(&key));
+        let cookies = cookie_header
+            .iter()
+            .flat_map(|header| header.split(';'))
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| {
+                let mut cookie = Cookie::new(name.trim(), value.trim());
+                cookie.domain = prepared.url.host_str().map(str::to_string);
+                cookie.path = Some("/".to_string());
+                cookie
+            })
+            .collect();
+
+        let page = renderer
+            .render(RenderRequest {
+                url: prepared.url.to_string(),
+                headers,
+                cookies,
+                proxy: prepared.proxy.clone(),
+                wait_for: wait_for.map(str::to_string),
+                timeout_ms: prepared.timeout_ms,
+            })
+            .await?;
+
+        let final_url = url::Url::parse(&page.url).unwrap_or_else(|_| prepared.url.clone());
+        if prepared.store_cookies {
+            if let Some(jar) = self.cookie_jar_mut() {
+                for cookie in &page.cookies {
+                    jar.insert_for_url(cookie.clone(), &final_url);
+                }
+            }
+        }
+
+        let raw_body = page.html.into_bytes();
+        Ok(CuimpResponse {
+            status: page.status.unwrap_or(200),
+            status_text: String::new(),
+            http_version: None,
+            headers: page.headers,
+            cookies: page.cookies,
+            data: Value::String(String::from_utf8_lossy(&raw_body).into_owned()),
+            raw_body,
+            request: RequestInfo {
+                unicode_host: unicode_host(&final_url),
+                url: final_url,
+                method: prepared.method.to_string(),
+                headers: prepared.headers,
+                command: format!("render {}", prepared.url),
+            },
+            attempts: Vec::new(),
+            diagnostics: None,
+            debug_info: None,
+            connection: None,
+            sha256: None,
+            truncated: false,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::types::CuimpOptions;
+
+    struct EchoRenderer;
+
+    impl Renderer for EchoRenderer {
+        async fn render(&self, request: RenderRequest) -> Result<RenderedPage> {
+            let sent: Vec<String> = request
+                .cookies
+                .iter()
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect();
+            Ok(RenderedPage {
+                url: request.url,
+                status: None,
+                headers: HashMap::new(),
+                html: format!("<p>{}</p>", sent.join(",")),
+                cookies: vec![Cookie::new("rendered", "1")],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_shares_cookies() {
+        let mut client = CuimpHttp::new(CuimpOptions {
+            path: Some("/bin/sh".to_string()),
+            cookie_store: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let url = url::Url::parse("https://example.com/app").unwrap();
+        client
+            .cookie_jar_mut()
+            .unwrap()
+            .insert_for_url(Cookie::new("session", "abc"), &url);
+
+        let config = CuimpRequestConfig {
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+        let response = client
+            .render(&EchoRenderer, config, Some("#root"))
+            .await
+            .unwrap();
+        assert_eq!(response.raw_body, b"<p>session=abc</p>");
+        assert!(client
+            .cookie_jar()
+            .unwrap()
+            .cookie_header(&url)
+            .unwrap()
+            .contains("rendered=1"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Minimal HTML tag scanning for token and form helpers.
+//!
+//! This is not a full HTML parser: it finds start tags and their attributes,
+//! which is enough for `<meta>`, `<input>` and `<form>` elements.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// A start tag with lower-cased attribute names and entity-decoded values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Tag {
+    pub name: String,
+    pub attrs: HashMap<String, String>,
+    /// Byte range of the tag in the source
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Tag {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(String::as_str)
+    }
+}
+
+fn tag_regex() -> &'static Regex {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    TAG.get_or_init(|| {
+        Regex::new(r#"(?is)<([a-z][a-z0-9-]*)\b((?:[^>"']|"[^"]*"|'[^']*')*)>"#)
+            .expect("static regex is valid")
+    })
+}
+
+fn attr_regex() -> &'static Regex {
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    ATTR.get_or_init(|| {
+        Regex::new(r#"(?s)([^\s"'>/=]+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#)
+            .expect("static regex is valid")
+    })
+}
+
+/// All start tags named one of `names` (case-insensitive), in document order
+pub(crate) fn find_tags(html: &str, names: &[&str]) -> Vec<Tag> {
+    tag_regex()
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let whole = captures.get(0)?;
+            let name = captures.get(1)?.as_str().to_ascii_lowercase();
+            if !names.contains(&name.as_str()) {
+                return None;
+            }
+            let attrs = captures
+                .get(2)
+                .map_or_else(HashMap::new, |m| parse_attrs(m.as_str()));
+            Some(Tag {
+                name,
+                attrs,
+                start: whole.start(),
+                end: whole.end(),
+            })
+        })
+        .collect()
+}
+
+fn parse_attrs(text: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    for captures in attr_regex().captures_iter(text) {
+        let Some(name) = captures.get(1) else {
+            continue;
+        };
+        let value = captures
+            .get(2)
+            .or_else(|| captures.get(3))
+            .or_else(|| captures.get(4))
+            .map_or("", |m| m.as_str());
+        // The first occurrence of an attribute wins, as in browsers
+        attrs
+            .entry(name.as_str().to_ascii_lowercase())
+            .or_insert_with(|| decode_entities(value));
+    }
+    attrs
+}
+
+/// Decode the character references that commonly appear in attribute values
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        let (before, from_amp) = rest.split_at(amp);
+        out.push_str(before);
+
+        let decoded = from_amp.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = from_amp.get(1..end)?;
+            let ch = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => {
+                    let code = match entity.strip_prefix('#') {
+                        Some(hex) if hex.starts_with(['x', 'X']) => {
+                            u32::from_str_radix(hex.get(1..)?, 16).ok()?
+                        }
+                        Some(dec) => dec.parse().ok()?,
+                        None => return None,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((ch, end + 1))
+        });
+
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = from_amp.get(len..).unwrap_or_default();
+            }
+            None => {
+                out.push('&');
+                rest = from_amp.get(1..).unwrap_or_default();
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_tags() {
+        let html = r#"<META name="csrf-token" content="a&amp;b"><input type=hidden name='x' value="1 > 0" disabled><div>"#;
+        let tags = find_tags(html, &["meta", "input"]);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].attr("content"), Some("a&b"));
+        assert_eq!(tags[1].attr("type"), Some("hidden"));
+        assert_eq!(tags[1].attr("value"), Some("1 > 0"));
+        assert_eq!(tags[1].attr("disabled"), Some(""));
+        assert_eq!(decode_entities("&#x41;&#66;&bogus; &"), "AB&bogus; &");
+    }
+}
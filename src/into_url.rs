@@ -0,0 +1,97 @@
+use crate::error::{CuimpError, Result};
+use url::Url;
+
+/// Types accepted wherever a request URL is expected
+///
+/// Implemented for [`Url`] and string types. Strings are parsed up front so
+/// an invalid URL fails before any binary is resolved or process spawned.
+pub trait IntoUrl {
+    fn into_url(self) -> Result<Url>;
+
+    /// The URL as text to request: strings are checked with `into_url` and kept as
+    /// given, so hosts, dot segments and escapes reach curl unchanged
+    fn into_url_text(self) -> Result<String>
+    where
+        Self: Sized,
+    {
+        Ok(self.into_url()?.to_string())
+    }
+}
+
+impl IntoUrl for Url {
+    fn into_url(self) -> Result<Url> {
+        Ok(self)
+    }
+}
+
+impl IntoUrl for &Url {
+    fn into_url(self) -> Result<Url> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoUrl for &str {
+    fn into_url(self) -> Result<Url> {
+        check_url_characters(self)?;
+        Url::parse(self).map_err(|e| CuimpError::InvalidUrl(format!("{}: {}", self, e)))
+    }
+
+    fn into_url_text(self) -> Result<String> {
+        self.into_url()?;
+        Ok(self.to_string())
+    }
+}
+
+impl IntoUrl for String {
+    fn into_url(self) -> Result<Url> {
+        self.as_str().into_url()
+    }
+
+    fn into_url_text(self) -> Result<String> {
+        self.as_str().into_url()?;
+        Ok(self)
+    }
+}
+
+impl IntoUrl for &String {
+    fn into_url(self) -> Result<Url> {
+        self.as_str().into_url()
+    }
+
+    fn into_url_text(self) -> Result<String> {
+        self.as_str().into_url_text()
+    }
+}
+
+/// Unicode form of an internationalized domain name, which `Url` keeps in punycode;
+/// `None` for other hosts
+pub(crate) fn unicode_host(url: &Url) -> Option<String> {
+    let host = url.domain()?;
+    if !host.split('.').any(|label| label.starts_with("xn--")) {
+        return None;
+    }
+    let (unicode, result) = idna::domain_to_unicode(host);
+    result.ok().map(|()| unicode)
+}
+
+/// Reject whitespace and control characters, which `Url::parse` would silently
+/// strip or percent-encode instead of reporting
+pub(crate) fn check_url_characters(text: &str) -> Result<()> {
+    match text
+        .char_indices()
+        .find(|(_, c)| c.is_whitespace() || c.is_control())
+    {
+        Some((index, c)) => Err(CuimpError::InvalidUrl(format!(
+            "{:?}: {} U+{:04X} at byte {}",
+            text,
+            if c.is_control() {
+                "control character"
+            } else {
+                "whitespace"
+            },
+            u32::from(c),
+            index
+        ))),
+        None => Ok(()),
+    }
+}
@@ -11,10 +11,33 @@
 //! - Automatic binary management and downloading
 //! - Cross-platform support (Linux, macOS, Windows)
 //!
+//! # Cargo features
+//!
+//! - `download` (default): fetch curl-impersonate releases on demand. Without it,
+//!   the binary must already be installed or configured via [`CuimpOptions::path`].
+//! - `embed-binary`: build the release archive named by the `CUIMP_EMBED_ARCHIVE`
+//!   environment variable into the crate and unpack it instead of downloading.
+//! - `vendored`: build curl-impersonate from source during `cargo build` and use
+//!   those binaries.
+//! - `json` (default): the module-level `get`/`post`/... convenience functions.
+//! - `config` (default): [`CuimpOptions::from_file`] for TOML configuration files.
+//! - `stream`: streaming response bodies via [`CuimpHttp::request_stream`] and
+//!   paginated GETs via [`CuimpHttp::get_paginated`] and NDJSON via [`CuimpHttp::get_ndjson`].
+//! - `scheduler`: [`RequestQueue`], a priority scheduler with per-host concurrency caps.
+//! - `cli`: the `cuimp` command-line binary.
+//! - `ffi`: in-process requests through libcurl-impersonate ([`Backend::Ffi`]).
+//! - `tower`: [`CuimpService`], a `tower::Service` for middleware stacks.
+//! - `otel`: OpenTelemetry client spans for every request.
+//! - `oauth1`: OAuth 1.0a request signing ([`OAuth1`]).
+//! - `test-helpers`: assertions such as [`CuimpResponse::assert_json_matches`] for
+//!   test suites.
+//! - `public-suffix`: refuse cookies for any domain on the Public Suffix List, not
+//!   only top-level domains.
+//!
 //! # Examples
 //!
 //! ```no_run
-//! use cuimp::{get, post, CuimpHttp, Cuimp, CuimpDescriptor, CuimpResponse};
+//! use cuimp::{get, post, Browser, CuimpHttp, Cuimp, CuimpDescriptor, CuimpResponse};
 //! use serde_json::Value;
 //!
 //! #[tokio::main]
@@ -32,7 +55,7 @@
 //!
 //!     // Using HTTP client with custom browser
 //!     let descriptor = CuimpDescriptor {
-//!         browser: Some("chrome".to_string()),
+//!         browser: Some(Browser::Chrome),
 //!         version: Some("123".to_string()),
 //!         ..Default::default()
 //!     };
@@ -44,23 +67,138 @@
 //! ```
 
 mod types;
+pub mod compat;
+mod archive;
+mod auth;
+mod batch;
+mod cache;
+mod cassette;
+mod challenge;
+mod checksum;
+mod chunked;
+mod coalesce;
+mod connection;
+mod cookie;
+#[cfg(feature = "crawler")]
+mod crawler;
+mod csrf;
 mod cuimp;
 mod client;
 mod runner;
 mod parser;
+#[cfg(feature = "download")]
 mod connector;
+mod config;
+mod curl_command;
 mod constants;
 mod validation;
+mod envelope;
 mod error;
+mod events;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "feed")]
+mod feed;
+mod fingerprint;
+mod flow;
+mod form;
+mod headless;
+mod html;
+mod into_url;
+mod locale;
+mod metrics;
+#[cfg(feature = "oauth1")]
+mod oauth;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "stream")]
+mod ndjson;
+#[cfg(feature = "stream")]
+mod paginate;
+mod parallel;
+mod presets;
+mod profile;
+mod redact;
+mod redirect;
+mod query;
+mod queue;
+mod retry;
+mod rotation;
+#[cfg(feature = "scheduler")]
+mod scheduler;
+#[cfg(feature = "tower")]
+mod service;
+#[cfg(feature = "verify-signatures")]
+mod signature;
+mod signer;
+mod transform;
+mod upload;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "test-helpers")]
+mod test_helpers;
+#[cfg(all(test, unix))]
+mod fake_curl;
 
+pub use archive::RESPONSE_SCHEMA_VERSION;
+pub use auth::AuthRefresh;
+pub use cache::{CacheStore, CachedResponse, DiskCache};
+pub use cassette::{Cassette, CassetteMode};
+pub use challenge::{BlockSignature, BlockVendor, Blocked, ChallengeDetector};
+pub use client::CuimpHttp;
+pub use connection::{Certificate, ConnectionInfo};
+pub use cookie::{Cookie, CookieJar, CookiePolicy, SameSite};
+#[cfg(feature = "crawler")]
+pub use crawler::{CrawlConfig, CrawlStats, CrawledPage, Crawler};
+pub use csrf::{CsrfConfig, CsrfSource, CsrfToken};
+pub use cuimp::Cuimp;
+pub use envelope::{
+    BodyEncoding, EnvelopeAttempt, EnvelopeTimings, ErrorEnvelope, ResponseEnvelope,
+    ENVELOPE_SCHEMA_VERSION,
+};
+pub use error::{CuimpError, RequestPhase, Result};
+pub use events::{CuimpEvent, EventHandler};
+#[cfg(feature = "feed")]
+pub use feed::{Feed, FeedEntry, FeedKind, FeedPoller, FeedStream};
+pub use fingerprint::{Http2Fingerprint, TlsOptions};
+pub use flow::{Extract, Flow, FlowResult, FlowStep};
+pub use form::Form;
+#[cfg(feature = "headless")]
+pub use headless::HttpRenderer;
+pub use headless::{RenderRequest, RenderedPage, Renderer};
+pub use into_url::IntoUrl;
+pub use locale::Locale;
+pub use metrics::{MetricsSink, RequestMetrics};
+#[cfg(feature = "stream")]
+pub use ndjson::NdjsonStream;
+#[cfg(feature = "oauth1")]
+pub use oauth::OAuth1;
+#[cfg(feature = "stream")]
+pub use paginate::Paginated;
+pub use presets::{browser_headers, HeaderPresets};
+pub use profile::{DomainProfile, DomainProfiles};
+pub use queue::{BulkExecutor, MemoryQueue, QueueBackend, QueueEntry, QueuedJob};
+pub use redact::Redaction;
+pub use redirect::{RedirectAction, RedirectAttempt, RedirectCallback, RedirectPolicy};
+pub use retry::{AttemptInfo, AttemptOutcome, CurlRetry, RetryPolicy};
+pub use rotation::{Rotation, RotationCallback, RotationPolicy};
+pub use runner::{run_binary, RunResult};
+#[cfg(feature = "scheduler")]
+pub use scheduler::{RequestHandle, RequestQueue, RequestQueueOptions};
+#[cfg(feature = "tower")]
+pub use service::CuimpService;
+pub use signer::{RequestSigner, SigningRequest};
+#[cfg(feature = "stream")]
+pub use stream::{BodyStream, CuimpStreamResponse};
+pub use transform::{BodyTransform, BodyTransformFn};
 pub use types::{
-    CuimpDescriptor, BinaryInfo, Method, CuimpRequestConfig, CuimpResponse, CuimpOptions,
+    canonical_reason, Arch, Backend, BinaryInfo, Browser, CuimpDescriptor, CuimpOptions,
+    CuimpRequestConfig, CuimpResponse, Diagnostics, ExtraArgsMerge, HostOverride, HttpVersion,
+    InstalledBinary, LowSpeedLimit, Method, Platform, Proxy, ReasonPhrase, RequestInfo,
 };
-pub use cuimp::Cuimp;
-pub use client::CuimpHttp;
-pub use runner::run_binary;
-pub use error::{CuimpError, Result};
+pub use upload::EntityMetadata;
 
+#[cfg(feature = "json")]
 use serde_json::Value;
 
 /// Create a new HTTP client instance with optional configuration
@@ -69,43 +207,50 @@ pub fn create_cuimp_http(options: Option<CuimpOptions>) -> Result<CuimpHttp> {
 }
 
 /// Make a GET request
-pub async fn get(url: &str) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn get(url: impl IntoUrl) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.get(url).await
 }
 
 /// Make a POST request
-pub async fn post(url: &str, data: Option<Value>) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn post(url: impl IntoUrl, data: Option<Value>) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.post(url, data).await
 }
 
 /// Make a PUT request
-pub async fn put(url: &str, data: Option<Value>) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn put(url: impl IntoUrl, data: Option<Value>) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.put(url, data).await
 }
 
 /// Make a PATCH request
-pub async fn patch(url: &str, data: Option<Value>) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn patch(url: impl IntoUrl, data: Option<Value>) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.patch(url, data).await
 }
 
 /// Make a DELETE request
-pub async fn delete(url: &str) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn delete(url: impl IntoUrl) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.delete(url).await
 }
 
 /// Make a HEAD request
-pub async fn head(url: &str) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn head(url: impl IntoUrl) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.head(url).await
 }
 
 /// Make an OPTIONS request
-pub async fn options(url: &str) -> Result<CuimpResponse<Value>> {
+#[cfg(feature = "json")]
+pub async fn options(url: impl IntoUrl) -> Result<CuimpResponse<Value>> {
     let mut client = create_cuimp_http(None)?;
     client.options(url).await
 }
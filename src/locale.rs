@@ -0,0 +1,137 @@
+/// Locale to present to servers
+///
+/// Sets a browser-consistent `Accept-Language` header and, when
+/// `CuimpOptions::geo_proxies` has entries for the locale's region, routes the
+/// request through one of those proxies. Browsers do not send the time zone in
+/// HTTP headers, so `time_zone` is never sent; it only travels with the locale
+/// for integrations that execute JavaScript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// BCP 47 language tag, e.g. `de-DE`
+    pub language: String,
+    /// Additional languages in order of preference, e.g. `["en-US"]`
+    pub fallbacks: Vec<String>,
+    /// IANA time zone, e.g. `Europe/Berlin`
+    pub time_zone: Option<String>,
+}
+
+impl Locale {
+    /// Locale for a language tag with English as fallback
+    pub fn new(language: impl Into<String>) -> Self {
+        let language = language.into();
+        let fallbacks = if language.to_ascii_lowercase().starts_with("en") {
+            Vec::new()
+        } else {
+            vec!["en-US".to_string()]
+        };
+        Locale {
+            language,
+            fallbacks,
+            time_zone: None,
+        }
+    }
+
+    /// Set the time zone
+    pub fn with_time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.time_zone = Some(time_zone.into());
+        self
+    }
+
+    /// Upper-case region subtag (`DE` for `de-DE`), if any
+    pub fn region(&self) -> Option<String> {
+        self.language
+            .split(['-', '_'])
+            .skip(1)
+            .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(str::to_ascii_uppercase)
+    }
+
+    /// `Accept-Language` value in the format Chromium sends:
+    /// each tag followed by its bare language, with q-values decreasing by 0.1
+    /// (`de-DE,de;q=0.9,en-US;q=0.8,en;q=0.7`)
+    pub fn accept_language(&self) -> String {
+        self.accept_language_for(None)
+    }
+
+    /// `Accept-Language` value in the format the impersonated browser sends.
+    ///
+    /// Firefox (and Tor Browser) spread q-values evenly over the list
+    /// (`de-DE,de;q=0.8,en-US;q=0.5,en;q=0.3`); Chromium-based browsers, Safari and
+    /// unknown targets use the format of `accept_language`.
+    pub fn accept_language_for(&self, browser: Option<&str>) -> String {
+        let tags = self.tags();
+        let firefox = browser
+            .is_some_and(|b| b.eq_ignore_ascii_case("firefox") || b.eq_ignore_ascii_case("tor"));
+        let count = tags.len() as u32;
+
+        tags.iter()
+            .enumerate()
+            .map(|(i, tag)| {
+                let i = i as u32;
+                let q = if firefox {
+                    // Round 10 * (1 - i / count) half up, as Firefox does
+                    ((count - i) * 20 + count) / (2 * count)
+                } else {
+                    10u32.saturating_sub(i).max(1)
+                };
+                if i == 0 {
+                    tag.clone()
+                } else {
+                    format!("{};q=0.{}", tag, q.clamp(1, 9))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Language tags in order, each followed by its bare language
+    fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in std::iter::once(&self.language).chain(&self.fallbacks) {
+            let tag = tag.replace('_', "-");
+            let primary = tag.split('-').next().unwrap_or_default().to_string();
+            for candidate in [tag, primary] {
+                if !candidate.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(&candidate))
+                {
+                    tags.push(candidate);
+                }
+            }
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_language() {
+        assert_eq!(
+            Locale::new("de-DE").accept_language(),
+            "de-DE,de;q=0.9,en-US;q=0.8,en;q=0.7"
+        );
+        assert_eq!(Locale::new("en-GB").accept_language(), "en-GB,en;q=0.9");
+        assert_eq!(
+            Locale::new("fr").accept_language(),
+            "fr,en-US;q=0.9,en;q=0.8"
+        );
+        assert_eq!(Locale::new("pt_BR").region().as_deref(), Some("BR"));
+        assert_eq!(Locale::new("fr").region(), None);
+
+        let de = Locale::new("de-DE");
+        assert_eq!(
+            de.accept_language_for(Some("firefox")),
+            "de-DE,de;q=0.8,en-US;q=0.5,en;q=0.3"
+        );
+        assert_eq!(
+            Locale::new("en-US").accept_language_for(Some("firefox")),
+            "en-US,en;q=0.5"
+        );
+        assert_eq!(
+            Locale::new("fr").accept_language_for(Some("tor")),
+            "fr,en-US;q=0.7,en;q=0.3"
+        );
+        assert_eq!(de.accept_language_for(Some("safari")), de.accept_language());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::types::Method;
+use std::fmt;
+use std::time::Duration;
+
+/// Measurements of one finished request, including all of its retries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestMetrics {
+    pub method: Method,
+    /// Host of the request URL
+    pub host: String,
+    /// Status of the final response; `None` when the request failed
+    pub status: Option<u16>,
+    /// Time from the start of the request to its response, including retries and backoff
+    pub duration: Duration,
+    /// Bytes of the request body written to curl
+    pub request_bytes: u64,
+    /// Bytes of the response body; `None` for failed and streaming requests
+    pub response_bytes: Option<u64>,
+    /// Exit code of curl for the final attempt (`CURLcode` with `Backend::Ffi`);
+    /// `None` when curl did not run to completion
+    pub exit_code: Option<i32>,
+    /// Number of attempts made
+    pub attempts: usize,
+}
+
+/// Receives the metrics of every request made by `CuimpHttp`, e.g. to update
+/// Prometheus or statsd instruments.
+///
+/// Called on the task making the request, so implementations should not block.
+/// Closures taking `&RequestMetrics` implement this trait.
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+impl<F> MetricsSink for F
+where
+    F: Fn(&RequestMetrics) + Send + Sync,
+{
+    fn record(&self, metrics: &RequestMetrics) {
+        self(metrics)
+    }
+}
+
+impl fmt::Debug for dyn MetricsSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetricsSink(..)")
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_metrics_sink() {
+        use crate::fake_curl::FakeCurl;
+        use crate::types::{CuimpOptions, CuimpRequestConfig};
+        use std::sync::{Arc, Mutex};
+
+        let curl = FakeCurl::new(
+            "metrics",
+            "#!/bin/sh\nprintf 'HTTP/1.1 201 Created\\r\\n\\r\\ncreated'\n",
+        );
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&recorded);
+        let mut client = curl.client(CuimpOptions {
+            metrics: Some(Arc::new(move |metrics: &RequestMetrics| {
+                sink.lock().unwrap().push(metrics.clone())
+            })),
+            ..Default::default()
+        });
+        let response = client
+            .request_raw(CuimpRequestConfig {
+                url: Some("https://api.example.com/items".to_string()),
+                method: Some(Method::POST),
+                body: Some(b"{\"a\":1}".to_vec()),
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(response.unwrap().status, 201);
+
+        let recorded = recorded.lock().unwrap();
+        let [metrics] = recorded.as_slice() else {
+            panic!("expected one record: {:?}", recorded)
+        };
+        assert_eq!(metrics.method, Method::POST);
+        assert_eq!(metrics.host, "api.example.com");
+        assert_eq!(metrics.status, Some(201));
+        assert_eq!(metrics.request_bytes, 7);
+        assert_eq!(metrics.response_bytes, Some(7));
+        assert_eq!(metrics.exit_code, Some(0));
+        assert_eq!(metrics.attempts, 1);
+    }
+}
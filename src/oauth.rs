@@ -0,0 +1,413 @@
+// Signs untrusted URLs and bodies: never index or unwrap.
+#![deny(clippy::indexing_slicing, clippy::unwrap_used, clippy::expect_used)]
+
+use crate::error::Result;
+use crate::signer::{RequestSigner, SigningRequest};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// OAuth 1.0a credentials for signing requests with HMAC-SHA1 (RFC 5849).
+///
+/// Set as `CuimpRequestConfig::oauth1` or `CuimpOptions::oauth1`, it adds an
+/// `Authorization: OAuth ...` header to every attempt with a fresh nonce and timestamp,
+/// signed over the method, the URL with its query parameters and, for
+/// `application/x-www-form-urlencoded` bodies, the form fields. An `Authorization`
+/// header set explicitly is left alone. It also implements `RequestSigner`, to be called
+/// from a custom signer.
+#[derive(Clone, PartialEq, Eq)]
+pub struct OAuth1 {
+    pub consumer_key: String,
+    pub consumer_secret: String,
+    /// Access (or request) token; two-legged requests have none
+    pub token: Option<String>,
+    pub token_secret: Option<String>,
+    /// `realm` parameter of the header, not signed
+    pub realm: Option<String>,
+}
+
+impl OAuth1 {
+    /// Two-legged credentials, without a token
+    pub fn new(consumer_key: impl Into<String>, consumer_secret: impl Into<String>) -> Self {
+        OAuth1 {
+            consumer_key: consumer_key.into(),
+            consumer_secret: consumer_secret.into(),
+            token: None,
+            token_secret: None,
+            realm: None,
+        }
+    }
+
+    /// Sign with a token and its secret
+    pub fn token(mut self, token: impl Into<String>, token_secret: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self.token_secret = Some(token_secret.into());
+        self
+    }
+
+    /// Send `realm` in the header
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+
+    /// `Authorization` header value for a request, with a new nonce and the current time
+    pub(crate) fn authorization(&self, method: &str, url: &Url, form_body: Option<&str>) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.authorization_with(method, url, form_body, &nonce(), timestamp)
+    }
+
+    fn authorization_with(
+        &self,
+        method: &str,
+        url: &Url,
+        form_body: Option<&str>,
+        nonce: &str,
+        timestamp: u64,
+    ) -> String {
+        let mut oauth_params = vec![
+            ("oauth_consumer_key", self.consumer_key.clone()),
+            ("oauth_nonce", nonce.to_string()),
+            ("oauth_signature_method", "HMAC-SHA1".to_string()),
+            ("oauth_timestamp", timestamp.to_string()),
+        ];
+        if let Some(token) = &self.token {
+            oauth_params.push(("oauth_token", token.clone()));
+        }
+        oauth_params.push(("oauth_version", "1.0".to_string()));
+
+        // Parameters are signed percent-encoded and sorted by name, then value
+        let mut params: Vec<(String, String)> = url
+            .query_pairs()
+            .chain(
+                form_body
+                    .into_iter()
+                    .flat_map(|body| url::form_urlencoded::parse(body.as_bytes())),
+            )
+            .map(|(name, value)| (encode(&name), encode(&value)))
+            .chain(
+                oauth_params
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), encode(value))),
+            )
+            .collect();
+        params.sort();
+        let normalized = params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let base_string = format!(
+            "{}&{}&{}",
+            method.to_ascii_uppercase(),
+            encode(&base_url(url)),
+            encode(&normalized)
+        );
+        let key = format!(
+            "{}&{}",
+            encode(&self.consumer_secret),
+            encode(self.token_secret.as_deref().unwrap_or_default())
+        );
+        let signature = STANDARD.encode(hmac_sha1(key.as_bytes(), base_string.as_bytes()));
+
+        let mut fields = Vec::new();
+        if let Some(realm) = &self.realm {
+            fields.push(format!("realm=\"{}\"", encode(realm)));
+        }
+        oauth_params.push(("oauth_signature", signature));
+        oauth_params.sort();
+        fields.extend(
+            oauth_params
+                .iter()
+                .map(|(name, value)| format!("{}=\"{}\"", name, encode(value))),
+        );
+        format!("OAuth {}", fields.join(", "))
+    }
+}
+
+impl RequestSigner for OAuth1 {
+    fn sign(&self, request: &mut SigningRequest<'_>) -> Result<()> {
+        if request.header("Authorization").is_some() {
+            return Ok(());
+        }
+        let is_form = request.header("Content-Type").is_some_and(|value| {
+            value
+                .to_ascii_lowercase()
+                .starts_with("application/x-www-form-urlencoded")
+        });
+        let form_body = request
+            .body
+            .filter(|_| is_form)
+            .map(String::from_utf8_lossy);
+        let authorization = self.authorization(
+            &request.method.to_string(),
+            request.url,
+            form_body.as_deref(),
+        );
+        request
+            .headers
+            .insert("Authorization".to_string(), authorization);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for OAuth1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth1")
+            .field("consumer_key", &self.consumer_key)
+            .field("consumer_secret", &"[REDACTED]")
+            .field("token", &self.token)
+            .field(
+                "token_secret",
+                &self.token_secret.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("realm", &self.realm)
+            .finish()
+    }
+}
+
+/// Scheme, host, non-default port and path, lowercased where case-insensitive (RFC 5849 3.4.1.2)
+fn base_url(url: &Url) -> String {
+    let port = url
+        .port()
+        .map(|port| format!(":{}", port))
+        .unwrap_or_default();
+    format!(
+        "{}://{}{}{}",
+        url.scheme(),
+        url.host_str().unwrap_or_default(),
+        port,
+        url.path()
+    )
+}
+
+/// Percent-encode everything but unreserved characters (RFC 5849 3.6)
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(char::from(byte))
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// 128 unpredictable bits as hex, from the hasher keys std seeds from the OS
+fn nonce() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let state = RandomState::new();
+    format!(
+        "{:016x}{:016x}",
+        state.hash_one((count, nanos)),
+        state.hash_one((nanos, count))
+    )
+}
+
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block.iter_mut().zip(sha1(key)).for_each(|(b, k)| *b = k);
+    } else {
+        block.iter_mut().zip(key).for_each(|(b, k)| *b = *k);
+    }
+    let inner: Vec<u8> = block
+        .iter()
+        .map(|b| b ^ 0x36)
+        .chain(message.iter().copied())
+        .collect();
+    let outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).chain(sha1(&inner)).collect();
+    sha1(&outer)
+}
+
+/// SHA-1, needed only as HMAC-SHA1's hash for OAuth signatures
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let bit_len = u64::try_from(data.len())
+        .unwrap_or(u64::MAX)
+        .wrapping_mul(8);
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend(bit_len.to_be_bytes());
+
+    for chunk in padded.chunks_exact(64) {
+        let mut w: Vec<u32> = chunk
+            .chunks_exact(4)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .fold(0, |word, byte| word << 8 | u32::from(*byte))
+            })
+            .collect();
+        while w.len() < 80 {
+            let n = w.len();
+            let word = match (w.get(n - 3), w.get(n - 8), w.get(n - 14), w.get(n - 16)) {
+                (Some(a), Some(b), Some(c), Some(d)) => (a ^ b ^ c ^ d).rotate_left(1),
+                _ => 0,
+            };
+            w.push(word);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_hmac_sha1() {
+        assert_eq!(
+            hex(&sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(&sha1(&[b'a'; 1000])),
+            "291e9a6c66994949b57ba5e650361e98fc36b1ba"
+        );
+        // RFC 2202 test cases 2 and 6 (key longer than a block)
+        assert_eq!(
+            hex(&hmac_sha1(b"Jefe", b"what do ya want for nothing?")),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            hex(&hmac_sha1(
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        // Twitter's "Creating a signature" walkthrough
+        let oauth = OAuth1::new(
+            "xvz1evFS4wEEPTGEFPHBog",
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+        )
+        .token(
+            "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+        )
+        .realm("Example");
+        let url =
+            Url::parse("https://api.twitter.com/1.1/statuses/update.json?include_entities=true")
+                .unwrap();
+        let header = oauth.authorization_with(
+            "post",
+            &url,
+            Some("status=Hello%20Ladies%20%2B%20Gentlemen%2C%20a%20signed%20OAuth%20request%21"),
+            "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg",
+            1318622958,
+        );
+
+        assert!(header.starts_with(
+            "OAuth realm=\"Example\", oauth_consumer_key=\"xvz1evFS4wEEPTGEFPHBog\", "
+        ));
+        assert!(header.contains("oauth_signature=\"hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D\""));
+        assert!(header.ends_with("oauth_version=\"1.0\""));
+        assert!(!format!("{:?}", oauth).contains("LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"));
+        assert_ne!(nonce(), nonce());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_oauth1_header_sent() {
+        use crate::fake_curl::FakeCurl;
+        use crate::types::{CuimpOptions, CuimpRequestConfig, Method};
+        use std::collections::HashMap;
+
+        let curl = FakeCurl::new(
+            "oauth",
+            "#!/bin/sh\nprintf 'HTTP/1.1 200 OK\\r\\n\\r\\nok'\n",
+        );
+        let mut client = curl.client(CuimpOptions {
+            oauth1: Some(OAuth1::new("key", "secret").token("token", "token-secret")),
+            ..Default::default()
+        });
+
+        let signed = client
+            .request_raw(CuimpRequestConfig {
+                url: Some("https://api.example.com/statuses?count=2".to_string()),
+                method: Some(Method::POST),
+                headers: Some(HashMap::from([(
+                    "Content-Type".to_string(),
+                    "application/x-www-form-urlencoded".to_string(),
+                )])),
+                body: Some(b"status=hello".to_vec()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let explicit = client
+            .request_raw(CuimpRequestConfig {
+                url: Some("https://api.example.com/".to_string()),
+                headers: Some(HashMap::from([(
+                    "authorization".to_string(),
+                    "Bearer other".to_string(),
+                )])),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let authorization = &signed.request.headers["Authorization"];
+        assert!(authorization.starts_with("OAuth oauth_consumer_key=\"key\", oauth_nonce=\""));
+        assert!(authorization.contains("oauth_token=\"token\""));
+        assert!(signed.request.command.contains("Authorization: [REDACTED]"));
+        assert_eq!(explicit.request.headers.len(), 1);
+        assert_eq!(explicit.request.headers["authorization"], "Bearer other");
+    }
+}
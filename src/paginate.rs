@@ -0,0 +1,300 @@
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::into_url::IntoUrl;
+use crate::types::{CuimpRequestConfig, CuimpResponse};
+use futures_core::Stream;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use url::Url;
+
+type PageFuture<'a, T> =
+    Pin<Box<dyn Future<Output = (&'a mut CuimpHttp, Result<CuimpResponse<T>>)> + Send + 'a>>;
+type CursorFn<'a, T> = Box<dyn Fn(&CuimpResponse<T>) -> Option<String> + Send + 'a>;
+
+impl CuimpHttp {
+    /// GET `url` and every page after it, following `Link: <...>; rel="next"` headers
+    /// (RFC 8288). See `Paginated` for cursor-based APIs.
+    pub fn get_paginated<T>(&mut self, url: impl IntoUrl) -> Paginated<'_, T>
+    where
+        T: serde::de::DeserializeOwned + Send,
+    {
+        let url = url.into_url_text();
+        let mut pages = self.paginate(CuimpRequestConfig::default());
+        pages.next = Some(url.map(|url| CuimpRequestConfig {
+            url: Some(url),
+            ..Default::default()
+        }));
+        pages
+    }
+
+    /// `get_paginated` starting from a full request; every page is requested with
+    /// `config` and the next page's URL
+    pub fn paginate<T>(&mut self, config: CuimpRequestConfig) -> Paginated<'_, T>
+    where
+        T: serde::de::DeserializeOwned + Send,
+    {
+        Paginated {
+            client: Some(self),
+            next: Some(Ok(config)),
+            fetching: None,
+            current: None,
+            cursor: None,
+            visited: HashSet::new(),
+            max_pages: None,
+            pages: 0,
+        }
+    }
+}
+
+/// Stream of pages returned by `CuimpHttp::get_paginated`.
+///
+/// Pages are fetched one at a time as the stream is polled. It ends when a page has no
+/// next link (or cursor), links back to a page already fetched, or `max_pages` is
+/// reached. A failed request or a non-2xx status is yielded as an error and ends it.
+pub struct Paginated<'a, T> {
+    client: Option<&'a mut CuimpHttp>,
+    /// Request for the next page
+    next: Option<Result<CuimpRequestConfig>>,
+    fetching: Option<PageFuture<'a, T>>,
+    /// Request of the page being fetched
+    current: Option<CuimpRequestConfig>,
+    /// Query parameter and extractor for cursor pagination
+    cursor: Option<(String, CursorFn<'a, T>)>,
+    visited: HashSet<String>,
+    max_pages: Option<usize>,
+    pages: usize,
+}
+
+impl<'a, T> Paginated<'a, T> {
+    /// Page with a cursor instead of `Link` headers: `extract` reads the next cursor from
+    /// a page (`None` on the last one), and it is sent as the query parameter `param`
+    /// of the next request
+    pub fn cursor<F>(mut self, param: impl Into<String>, extract: F) -> Self
+    where
+        F: Fn(&CuimpResponse<T>) -> Option<String> + Send + 'a,
+    {
+        self.cursor = Some((param.into(), Box::new(extract)));
+        self
+    }
+
+    /// Stop after `max` pages
+    pub fn max_pages(mut self, max: usize) -> Self {
+        self.max_pages = Some(max);
+        self
+    }
+
+    /// Request for the page after `response`, if any
+    fn next_request(
+        &self,
+        config: &CuimpRequestConfig,
+        response: &CuimpResponse<T>,
+    ) -> Option<CuimpRequestConfig> {
+        let current = &response.request.url;
+        let next = match &self.cursor {
+            Some((param, extract)) => {
+                let cursor = extract(response)?;
+                let mut next = current.clone();
+                let pairs: Vec<(String, String)> = current
+                    .query_pairs()
+                    .filter(|(name, _)| name != param.as_str())
+                    .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                    .collect();
+                next.query_pairs_mut()
+                    .clear()
+                    .extend_pairs(pairs)
+                    .append_pair(param, &cursor);
+                next
+            }
+            None => next_link(response.header("link")?, current)?,
+        };
+        Some(CuimpRequestConfig {
+            url: Some(next.to_string()),
+            // The next URL is absolute and already carries the query
+            base_url: None,
+            params: None,
+            ..config.clone()
+        })
+    }
+}
+
+impl<T> std::fmt::Debug for Paginated<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Paginated")
+            .field("pages", &self.pages)
+            .field("max_pages", &self.max_pages)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> Stream for Paginated<'a, T>
+where
+    T: serde::de::DeserializeOwned + Send + 'a,
+{
+    type Item = Result<CuimpResponse<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(fetching) = self.fetching.as_mut() {
+                let (client, result) = match fetching.as_mut().poll(cx) {
+                    Poll::Ready(output) => output,
+                    Poll::Pending => return Poll::Pending,
+                };
+                self.fetching = None;
+                self.client = Some(client);
+                let config = self.current.take().unwrap_or_default();
+                return Poll::Ready(Some(match result {
+                    Ok(response) if !(200..300).contains(&response.status) => {
+                        Err(CuimpError::HttpError(format!(
+                            "Page {} returned {} {}",
+                            response.request.url, response.status, response.status_text
+                        )))
+                    }
+                    Ok(response) => {
+                        self.next = self.next_request(&config, &response).map(Ok);
+                        Ok(response)
+                    }
+                    Err(e) => Err(e),
+                }));
+            }
+
+            let config = match self.next.take() {
+                Some(Ok(config)) => config,
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            };
+            if self.max_pages.is_some_and(|max| self.pages >= max) {
+                return Poll::Ready(None);
+            }
+            if !self.visited.insert(config.url.clone().unwrap_or_default()) {
+                return Poll::Ready(None);
+            }
+            let Some(client) = self.client.take() else {
+                return Poll::Ready(None);
+            };
+            self.pages += 1;
+            self.current = Some(config.clone());
+            self.fetching = Some(Box::pin(async move {
+                let result = client.request::<T>(config).await;
+                (client, result)
+            }));
+        }
+    }
+}
+
+/// Target of the `rel="next"` link in a `Link` header, resolved against `base`
+fn next_link(header: &str, base: &Url) -> Option<Url> {
+    let mut rest = header;
+    while let Some(start) = rest.find('<') {
+        let after = rest.get(start + 1..)?;
+        let end = after.find('>')?;
+        let target = after.get(..end)?;
+        let params = after.get(end + 1..)?;
+        // Parameters run up to the next link; quoted values may contain commas
+        let mut in_quotes = false;
+        let params_end = params
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                c == ',' && !in_quotes
+            })
+            .map_or(params.len(), |(i, _)| i);
+        let is_next = params.get(..params_end)?.split(';').any(|param| {
+            param.split_once('=').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches('"')
+                        .split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("next"))
+            })
+        });
+        if is_next {
+            return base.join(target).ok();
+        }
+        rest = params.get(params_end..)?;
+    }
+    None
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_next_link() {
+        let base = Url::parse("https://api.example.com/items?page=1").unwrap();
+        let header = r#"<https://api.example.com/items?page=1>; rel="prev first", </items?page=3&a=1,2>; title="a, b"; rel="next", <?page=9>; rel=last"#;
+        assert_eq!(
+            next_link(header, &base).unwrap().as_str(),
+            "https://api.example.com/items?page=3&a=1,2"
+        );
+        assert_eq!(
+            next_link(
+                r#"<https://api.example.com/items?page=9>; rel="last""#,
+                &base
+            ),
+            None
+        );
+        assert_eq!(next_link("garbage", &base), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_get_paginated() {
+        use crate::fake_curl::fake_client;
+
+        // Fake curl: pages 1 and 2 link to the next one; `?cursor=` pages carry a cursor in the body
+        let (mut client, _curl) = fake_client(
+            "paginate",
+            "#!/bin/sh\nfor url; do :; done\ncase \"$url\" in\n\
+             *page=1) printf 'HTTP/1.1 200 OK\\r\\nLink: </items?page=2>; rel=\"next\"\\r\\n\\r\\n[1]' ;;\n\
+             *page=2) printf 'HTTP/1.1 200 OK\\r\\nLink: </items?page=1>; rel=\"prev\"\\r\\n\\r\\n[2]' ;;\n\
+             *cursor=b) printf 'HTTP/1.1 200 OK\\r\\n\\r\\n{\"next\":null}' ;;\n\
+             *) printf 'HTTP/1.1 200 OK\\r\\n\\r\\n{\"next\":\"b\"}' ;;\nesac\n",
+        );
+
+        let mut urls = Vec::new();
+        let mut pages = client.get_paginated::<Value>("https://api.example.com/items?page=1");
+        while let Some(page) = std::future::poll_fn(|cx| Pin::new(&mut pages).poll_next(cx)).await {
+            let page = page.unwrap();
+            urls.push((page.request.url.to_string(), page.data));
+        }
+        drop(pages);
+        assert_eq!(
+            urls,
+            [
+                (
+                    "https://api.example.com/items?page=1".to_string(),
+                    serde_json::json!([1])
+                ),
+                (
+                    "https://api.example.com/items?page=2".to_string(),
+                    serde_json::json!([2])
+                ),
+            ]
+        );
+
+        let mut urls = Vec::new();
+        let mut pages = client
+            .get_paginated::<Value>("https://api.example.com/feed?limit=10")
+            .cursor("cursor", |page| {
+                page.data["next"].as_str().map(str::to_string)
+            });
+        while let Some(page) = std::future::poll_fn(|cx| Pin::new(&mut pages).poll_next(cx)).await {
+            urls.push(page.unwrap().request.url.to_string());
+        }
+        assert_eq!(
+            urls,
+            [
+                "https://api.example.com/feed?limit=10",
+                "https://api.example.com/feed?limit=10&cursor=b"
+            ]
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use crate::client::{
+    raw_response, split_response, with_attempts, within, Chain, CuimpHttp, Deadline, Invocation,
+    PreparedRequest,
+};
+use crate::coalesce::{run_flight, Flight};
+use crate::error::{CuimpError, RequestPhase, Result};
+use crate::into_url::IntoUrl;
+use crate::profile::DomainPermit;
+use crate::retry::{AttemptInfo, AttemptOutcome, RetryPolicy};
+use crate::types::{CuimpRequestConfig, CuimpResponse, Method};
+use serde::de::IgnoredAny;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+
+impl CuimpHttp {
+    /// GET every URL with at most `concurrency` requests in flight.
+    ///
+    /// Results are returned in the order of `urls`; see `run_all`.
+    pub async fn get_many<T, U>(
+        &mut self,
+        urls: impl IntoIterator<Item = U>,
+        concurrency: usize,
+    ) -> Vec<Result<CuimpResponse<T>>>
+    where
+        T: serde::de::DeserializeOwned,
+        U: IntoUrl,
+    {
+        let urls: Vec<Result<String>> = urls.into_iter().map(IntoUrl::into_url_text).collect();
+        let configs = urls
+            .iter()
+            .filter_map(|url| url.as_ref().ok())
+            .map(|url| CuimpRequestConfig {
+                url: Some(url.clone()),
+                method: Some(Method::GET),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut responses = self.run_all(configs, concurrency).await.into_iter();
+        urls.into_iter()
+            .map(|url| match url {
+                Ok(_) => responses
+                    .next()
+                    .unwrap_or_else(|| Err(CuimpError::Other("Missing response".to_string()))),
+                Err(e) => Err(e),
+            })
+            .collect()
+    }
+
+    /// Make every request with at most `limit` in flight.
+    ///
+    /// Results are returned in the order of `configs`, one per request, so a failed
+    /// request does not stop the others. Requests are prepared on this client in order
+    /// (headers, cookies, proxy rotation, domain profile throttling) and run
+    /// concurrently; cookies and body transforms are applied as responses arrive.
+    /// Retries follow the request's retry policy but reuse the prepared request, so a
+    /// retried attempt keeps its proxy. A redirect a custom redirect policy follows, or a
+    /// request sent again after `on_unauthorized`, is made like `request` does once the
+    /// response arrives, holding up the next requests meanwhile. When replaying a cassette, requests run one by one.
+    pub async fn run_all<T>(
+        &mut self,
+        configs: Vec<CuimpRequestConfig>,
+        limit: usize,
+    ) -> Vec<Result<CuimpResponse<T>>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if self.is_replaying() {
+            let mut results = Vec::with_capacity(configs.len());
+            for config in configs {
+                results.push(self.request(config).await);
+            }
+            return results;
+        }
+
+        let mut results: Vec<Option<Result<CuimpResponse<T>>>> =
+            configs.iter().map(|_| None).collect();
+        let mut pending = configs.into_iter().enumerate();
+        let mut in_flight = JoinSet::new();
+
+        loop {
+            while in_flight.len() < limit.max(1) {
+                let Some((index, config)) = pending.next() else {
+                    break;
+                };
+                let chain = self.chain(&config);
+                let (prepared, invocation, flight, permit) = match self.start(&config, &chain).await
+                {
+                    Ok(started) => started,
+                    Err(e) => {
+                        if let Some(slot) = results.get_mut(index) {
+                            *slot = Some(Err(e));
+                        }
+                        continue;
+                    }
+                };
+                let policy = self.retry_policy(&config);
+                let fingerprint = self.fingerprint();
+                let proxy = self.attempt_proxy(&prepared);
+
+                let shared = flight.as_ref().is_some_and(Flight::is_follower);
+                in_flight.spawn(async move {
+                    let _permit = permit;
+                    let (output, attempts) = run_within(
+                        flight,
+                        &invocation,
+                        policy.as_ref(),
+                        proxy.as_ref(),
+                        fingerprint.as_ref(),
+                        chain.deadline.as_ref(),
+                    )
+                    .await;
+                    (index, config, chain, prepared, output, attempts, shared)
+                });
+            }
+
+            let Some(joined) = in_flight.join_next().await else {
+                break;
+            };
+            let Ok((index, config, mut chain, prepared, output, attempts, shared)) = joined else {
+                continue;
+            };
+            let mut result = output.and_then(|stdout| {
+                if !shared {
+                    self.record(&prepared, &stdout)?;
+                }
+                self.finish::<T>(&prepared, &stdout)
+            });
+            chain.attempts.extend(attempts);
+            let result = match self
+                .next_request(&config, &prepared.url, &mut result, &mut chain)
+                .await
+            {
+                Ok(Some(next)) => self.request_chained(next, true, None, chain).await,
+                Ok(None) => {
+                    self.observe(&prepared, chain.started, &chain.attempts, &result);
+                    result.map(|response| with_attempts(response, chain.attempts))
+                }
+                Err(e) => Err(e),
+            };
+            if let Some(slot) = results.get_mut(index) {
+                *slot = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(CuimpError::Other("Request task failed".to_string())))
+            })
+            .collect()
+    }
+
+    /// Prepare `config` to run outside the `request` loop and join its flight, waiting
+    /// for the domain profile's limits unless it follows another request, all within the
+    /// deadline of `chain`, which also caps curl's timeout
+    async fn start(
+        &mut self,
+        config: &CuimpRequestConfig,
+        chain: &Chain,
+    ) -> Result<(
+        PreparedRequest,
+        Invocation,
+        Option<Flight>,
+        Option<DomainPermit>,
+    )> {
+        let deadline = chain.deadline.as_ref();
+        let mut prepared = within(
+            deadline,
+            RequestPhase::ResolveBinary,
+            self.prepare(config, &["-i"]),
+        )
+        .await??;
+        let flight = self.join_flight(&prepared);
+        let permit = match &flight {
+            Some(flight) if flight.is_follower() => None,
+            _ => {
+                within(
+                    deadline,
+                    RequestPhase::Throttle,
+                    self.throttle(&prepared.url),
+                )
+                .await?
+            }
+        };
+        if let Some(deadline) = deadline {
+            if deadline.passed() {
+                return Err(deadline.exceeded(RequestPhase::Spawn));
+            }
+            prepared.timeout_ms = Some(deadline.cap(prepared.timeout_ms));
+        }
+        let invocation = self.invocation(&prepared)?;
+        Ok((prepared, invocation, flight, permit))
+    }
+}
+
+/// `request_raw` on a client shared between tasks, holding it only to prepare the
+/// request and to finish the response so calls run concurrently
+pub(crate) async fn send_shared(
+    client: &Mutex<CuimpHttp>,
+    config: CuimpRequestConfig,
+) -> Result<CuimpResponse<Vec<u8>>> {
+    let (mut chain, prepared, invocation, flight, permit, policy, fingerprint, proxy) = {
+        let mut client = client.lock().await;
+        if client.is_replaying() {
+            return client.request_raw(config).await;
+        }
+        let chain = client.chain(&config);
+        let (prepared, invocation, flight, permit) = client.start(&config, &chain).await?;
+        let proxy = client.attempt_proxy(&prepared);
+        (
+            chain,
+            prepared,
+            invocation,
+            flight,
+            permit,
+            client.retry_policy(&config),
+            client.fingerprint(),
+            proxy,
+        )
+    };
+
+    let shared = flight.as_ref().is_some_and(Flight::is_follower);
+    let (output, attempts) = run_within(
+        flight,
+        &invocation,
+        policy.as_ref(),
+        proxy.as_ref(),
+        fingerprint.as_ref(),
+        chain.deadline.as_ref(),
+    )
+    .await;
+    drop(permit);
+
+    let mut client = client.lock().await;
+    let mut result = output.and_then(|stdout| {
+        if !shared {
+            client.record(&prepared, &stdout)?;
+        }
+        client.finish_raw(&prepared, &stdout)
+    });
+    chain.attempts.extend(attempts);
+    // The rest of a redirected or reauthorized request keeps the client to itself
+    if let Some(next) = client
+        .next_request(&config, &prepared.url, &mut result, &mut chain)
+        .await?
+    {
+        return client
+            .request_chained::<IgnoredAny>(next, false, None, chain)
+            .await
+            .map(raw_response);
+    }
+    client.observe(&prepared, chain.started, &chain.attempts, &result);
+    result.map(|response| with_attempts(response, chain.attempts))
+}
+
+/// `run_flight` cut off at `deadline`, with a curl timeout at the deadline reported as
+/// exceeding it
+async fn run_within(
+    flight: Option<Flight>,
+    invocation: &Invocation,
+    policy: Option<&RetryPolicy>,
+    proxy: Option<&String>,
+    fingerprint: Option<&String>,
+    deadline: Option<&Deadline>,
+) -> (Result<Vec<u8>>, Vec<AttemptInfo>) {
+    let run = run_flight(flight, invocation, policy, proxy, fingerprint, deadline);
+    let (output, attempts) = match within(deadline, RequestPhase::Transfer, run).await {
+        Ok(ran) => ran,
+        Err(e) => return (Err(e), Vec::new()),
+    };
+    match (output, deadline) {
+        (Err(CuimpError::Timeout(_)), Some(deadline)) if deadline.passed() => {
+            (Err(deadline.exceeded(RequestPhase::Transfer)), attempts)
+        }
+        (output, _) => (output, attempts),
+    }
+}
+
+/// Run an invocation, retrying it as `policy` allows unless the backoff would end after
+/// `deadline`, and record every attempt
+pub(crate) async fn run_attempts(
+    invocation: &Invocation,
+    policy: Option<&RetryPolicy>,
+    proxy: Option<&String>,
+    fingerprint: Option<&String>,
+    deadline: Option<&Deadline>,
+) -> (Result<Vec<u8>>, Vec<AttemptInfo>) {
+    let mut attempts = Vec::new();
+    let mut retry = 0;
+    loop {
+        let started = Instant::now();
+        let (output, report) = invocation.run().await;
+        // Delay before the next attempt, `None` when this one is final
+        let (outcome, delay) = match &output {
+            Ok(stdout) => match split_response(stdout) {
+                Ok((head, _)) => (
+                    AttemptOutcome::Status(head.status),
+                    policy
+                        .filter(|p| p.should_retry_status(head.status))
+                        .and_then(|p| p.response_delay(retry, head.status, &head.headers)),
+                ),
+                Err(e) => (
+                    AttemptOutcome::Error(e.to_string()),
+                    policy
+                        .filter(|p| p.should_retry_error(&e))
+                        .map(|p| p.delay(retry)),
+                ),
+            },
+            Err(e) => (
+                AttemptOutcome::Error(e.to_string()),
+                policy
+                    .filter(|p| p.should_retry_error(e))
+                    .map(|p| p.delay(retry)),
+            ),
+        };
+        attempts.push(report.attempt(
+            outcome,
+            started.elapsed(),
+            proxy.cloned(),
+            fingerprint.cloned(),
+        ));
+        let delay = delay.filter(|delay| deadline.is_none_or(|deadline| deadline.allows(*delay)));
+
+        match (policy, delay) {
+            (Some(policy), Some(delay)) if retry < policy.max_retries => {
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            _ => return (output, attempts),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::fake_curl::fake_client;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_run_all_keeps_order() {
+        // Fake curl: the last argument is the URL; `/slow` answers after the others
+        let (mut client, _curl) = fake_client(
+            "parallel",
+            "#!/bin/sh\nfor url; do :; done\ncase \"$url\" in *slow) sleep 0.3 ;; esac\n\
+             printf 'HTTP/1.1 200 OK\\r\\n\\r\\n%s' \"$url\"\n",
+        );
+        let results = client
+            .get_many::<Value, _>(
+                [
+                    "https://example.com/slow",
+                    "not a url",
+                    "https://example.com/fast",
+                ],
+                2,
+            )
+            .await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap().raw_body,
+            b"https://example.com/slow"
+        );
+        assert!(matches!(results[1], Err(CuimpError::InvalidUrl(_))));
+        assert_eq!(results[2].as_ref().unwrap().attempts.len(), 1);
+    }
+}
@@ -1,39 +1,39 @@
-use crate::connector::get_latest_release;
-use crate::constants::{ARCHITECTURE_LIST, BINARY_PATTERNS, BINARY_SEARCH_PATHS, BROWSER_LIST, PLATFORM_LIST};
+// Filesystem scanning and archive handling must not panic on unexpected layouts.
+#![deny(clippy::indexing_slicing, clippy::unwrap_used, clippy::expect_used)]
+
+#[cfg(feature = "download")]
+use crate::connector::get_latest_release_cached;
+#[cfg(feature = "download")]
+use crate::constants::RELEASE_DIGESTS;
+use crate::constants::{
+    BINARY_PATTERNS, BINARY_SEARCH_PATHS, VERSION_PROBE_TIMEOUT_MS, WRAPPER_PREFIX,
+};
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+use crate::constants::{DOWNLOAD_LOCK_FILE, DOWNLOAD_LOCK_POLL_MS};
 use crate::error::{CuimpError, Result};
-use crate::types::{BinaryInfo, CuimpDescriptor};
+use crate::events::{CuimpEvent, Events};
+use crate::runner::run_binary;
+use crate::types::{
+    Arch, BinaryInfo, Browser, CuimpDescriptor, CuimpOptions, InstalledBinary, Platform,
+};
 use crate::validation::validate_descriptor;
+#[cfg(any(feature = "download", feature = "embed-binary"))]
 use flate2::read::GzDecoder;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+#[cfg(any(feature = "download", feature = "embed-binary"))]
 use tar::Archive;
+use tokio::sync::OnceCell;
 
 /// Get system architecture and platform
-pub fn get_system_info() -> Result<(String, String)> {
-    let arch = match std::env::consts::ARCH {
-        "x86_64" => "x64",
-        "aarch64" => "arm64",
-        other => {
-            return Err(CuimpError::UnsupportedArchitecture(format!(
-                "Unsupported architecture: {}",
-                other
-            )))
-        }
-    };
-
-    let platform = match std::env::consts::OS {
-        "linux" => "linux",
-        "windows" => "windows",
-        "macos" => "macos",
-        other => {
-            return Err(CuimpError::UnsupportedPlatform(format!(
-                "Unsupported platform: {}",
-                other
-            )))
-        }
-    };
-
-    Ok((arch.to_string(), platform.to_string()))
+pub fn get_system_info() -> Result<(Arch, Platform)> {
+    Ok((
+        std::env::consts::ARCH.parse()?,
+        std::env::consts::OS.parse()?,
+    ))
 }
 
 /// Extract version number from filename
@@ -46,15 +46,112 @@ fn extract_version_number(filename: &str) -> u32 {
         .unwrap_or(0)
 }
 
-/// Get binaries directory path
-fn get_binaries_dir() -> PathBuf {
-    if let Some(home_dir) = dirs::home_dir() {
-        home_dir.join(".cuimp").join("binaries")
+/// Run `<binary> --version` to make sure a found or downloaded binary actually runs (a
+/// truncated file or one built for another architecture passes the executable check)
+/// and return the version it reports
+async fn probe_binary(binary_path: &str) -> Result<Option<String>> {
+    let not_runnable =
+        |reason: String| CuimpError::BinaryNotExecutable(format!("{}: {}", binary_path, reason));
+    let result = run_binary(
+        binary_path,
+        &["--version".to_string()],
+        Some(VERSION_PROBE_TIMEOUT_MS),
+    )
+    .await
+    .map_err(|e| not_runnable(e.to_string()))?;
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    if result.exit_code != Some(0) || !stdout.starts_with("curl ") {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let output = stderr
+            .lines()
+            .chain(stdout.lines())
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default();
+        return Err(not_runnable(format!(
+            "`--version` exited with {} ({})",
+            result
+                .exit_code
+                .map_or("a signal".to_string(), |code| code.to_string()),
+            output.trim()
+        )));
+    }
+    Ok(parse_version_output(&stdout))
+}
+
+/// Version in `--version` output: curl-impersonate's own (`curl-impersonate/1.0.0`) when
+/// listed, otherwise curl's from the first line (`curl 8.13.0 (x86_64-pc-linux-gnu) ...`)
+fn parse_version_output(output: &str) -> Option<String> {
+    let words = || output.split_whitespace();
+    let own = words().find_map(|word| word.strip_prefix("curl-impersonate/"));
+    own.or_else(|| words().nth(1).filter(|_| output.starts_with("curl ")))
+        .map(|version| version.trim_start_matches('v').to_string())
+}
+
+/// Default time a resolved latest-release tag is reused
+pub(crate) const DEFAULT_RELEASE_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How binaries are resolved and downloaded
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "download"), allow(dead_code))]
+pub(crate) struct ResolveOptions {
+    /// Where binaries are downloaded to and looked up first
+    pub binaries_dir: PathBuf,
+    pub release_cache_ttl: Duration,
+    pub refresh_release: bool,
+    /// Download even when a binary is installed
+    pub force: bool,
+    /// Download when no binary is installed
+    pub auto_download: bool,
+    pub events: Events,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        ResolveOptions {
+            binaries_dir: default_binaries_dir(),
+            release_cache_ttl: DEFAULT_RELEASE_CACHE_TTL,
+            refresh_release: false,
+            force: false,
+            auto_download: true,
+            events: Events::default(),
+        }
+    }
+}
+
+impl From<&CuimpOptions> for ResolveOptions {
+    fn from(options: &CuimpOptions) -> Self {
+        ResolveOptions {
+            binaries_dir: options
+                .binaries_dir
+                .as_ref()
+                .map_or_else(default_binaries_dir, PathBuf::from),
+            release_cache_ttl: options
+                .release_cache_ttl_secs
+                .map_or(DEFAULT_RELEASE_CACHE_TTL, Duration::from_secs),
+            refresh_release: options.refresh_release_cache,
+            force: options.force,
+            auto_download: options.auto_download,
+            events: Events(options.on_event.clone()),
+        }
+    }
+}
+
+/// cuimp's data directory: `CUIMP_HOME`, or `~/.cuimp`
+pub(crate) fn cuimp_home() -> PathBuf {
+    if let Some(home) = std::env::var_os("CUIMP_HOME").filter(|home| !home.is_empty()) {
+        PathBuf::from(home)
+    } else if let Some(home_dir) = dirs::home_dir() {
+        home_dir.join(".cuimp")
     } else {
-        PathBuf::from("./binaries")
+        PathBuf::from(".")
     }
 }
 
+/// Binaries directory used when `CuimpOptions::binaries_dir` is unset
+fn default_binaries_dir() -> PathBuf {
+    cuimp_home().join("binaries")
+}
+
 /// Check if a binary is executable
 fn is_binary_executable(path: &Path) -> bool {
     if !path.exists() || !path.is_file() {
@@ -78,6 +175,7 @@ fn is_binary_executable(path: &Path) -> bool {
 }
 
 /// Make binary executable
+#[cfg(any(feature = "download", feature = "embed-binary"))]
 fn make_executable(path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
@@ -89,32 +187,27 @@ fn make_executable(path: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Find existing binary in search paths
-pub fn find_existing_binary(browser: Option<&str>) -> Option<PathBuf> {
-    let binaries_dir = get_binaries_dir();
-    let mut search_paths: Vec<PathBuf> = vec![binaries_dir];
+/// Directories searched for binaries, in order: `binaries_dir`, the binaries built by
+/// the `vendored` feature, then the system paths
+fn search_paths(binaries_dir: &Path) -> Vec<PathBuf> {
+    let mut search_paths = vec![binaries_dir.to_path_buf()];
+    #[cfg(feature = "vendored")]
+    search_paths.push(PathBuf::from(env!("CUIMP_VENDORED_BIN_DIR")));
+    search_paths.extend(BINARY_SEARCH_PATHS.iter().map(PathBuf::from));
+    search_paths
+}
 
-    // Add system paths
-    for path_str in BINARY_SEARCH_PATHS {
-        search_paths.push(PathBuf::from(path_str));
-    }
+/// Find existing binary in search paths
+pub fn find_existing_binary(browser: Option<&str>, binaries_dir: &Path) -> Option<PathBuf> {
+    let search_paths = search_paths(binaries_dir);
 
     // Filter patterns based on browser
     let patterns: Vec<&str> = if let Some(browser) = browser {
         BINARY_PATTERNS
             .iter()
             .filter(|&&pattern| {
-                if browser == "chrome" {
-                    pattern.contains("chrome") || pattern == "curl-impersonate"
-                } else if browser == "firefox" {
-                    pattern.contains("firefox") || pattern == "curl-impersonate"
-                } else if browser == "edge" {
-                    pattern.contains("edge") || pattern == "curl-impersonate"
-                } else if browser == "safari" {
-                    pattern.contains("safari") || pattern == "curl-impersonate"
-                } else {
-                    pattern == "curl-impersonate"
-                }
+                pattern.starts_with("curl-impersonate")
+                    || (browser != "curl" && pattern == format!("{}{}", WRAPPER_PREFIX, browser))
             })
             .copied()
             .collect()
@@ -152,14 +245,14 @@ pub fn find_existing_binary(browser: Option<&str>) -> Option<PathBuf> {
                         .filter(|path| is_binary_executable(path))
                         .collect();
 
-                    if !matches.is_empty() {
-                        // Sort by version number (highest first)
-                        matches.sort_by(|a, b| {
-                            let ver_a = extract_version_number(&a.to_string_lossy());
-                            let ver_b = extract_version_number(&b.to_string_lossy());
-                            ver_b.cmp(&ver_a)
-                        });
-                        return Some(matches[0].clone());
+                    // Sort by version number (highest first)
+                    matches.sort_by(|a, b| {
+                        let ver_a = extract_version_number(&a.to_string_lossy());
+                        let ver_b = extract_version_number(&b.to_string_lossy());
+                        ver_b.cmp(&ver_a)
+                    });
+                    if let Some(best) = matches.into_iter().next() {
+                        return Some(best);
                     }
                 }
             }
@@ -169,65 +262,476 @@ pub fn find_existing_binary(browser: Option<&str>) -> Option<PathBuf> {
     None
 }
 
-/// Download and extract binary
-pub async fn download_and_extract_binary(
-    browser: &str,
-    architecture: &str,
-    platform: &str,
-    version: &str,
-) -> Result<BinaryInfo> {
-    // Validate parameters
-    if !BROWSER_LIST.contains(&browser) {
-        return Err(CuimpError::UnsupportedBrowser(browser.to_string()));
+/// Find the wrapper script (`curl_chrome124`) for a browser target, whose flags make the
+/// fingerprint match that browser; `binaries_dir` is searched before the system paths.
+///
+/// With a `version`, only its wrapper matches (compared without separators, so `18_0`
+/// finds `curl_safari180`); without one, the newest desktop wrapper of the browser wins.
+pub(crate) fn find_wrapper(
+    browser: Browser,
+    version: Option<&str>,
+    binaries_dir: &Path,
+) -> Option<PathBuf> {
+    if browser == Browser::Curl {
+        return None;
     }
-    if !ARCHITECTURE_LIST.contains(&architecture) {
-        return Err(CuimpError::UnsupportedArchitecture(architecture.to_string()));
+    let wanted = version.map(normalize_version);
+    let search_paths = search_paths(binaries_dir);
+
+    search_paths.iter().find_map(|dir| {
+        fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_binary_executable(path))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_string_lossy().into_owned();
+                let name = name.strip_suffix(".bat").unwrap_or(&name);
+                let target_version = name
+                    .strip_prefix(WRAPPER_PREFIX)?
+                    .strip_prefix(browser.as_str())?;
+                if !target_version.starts_with(|c: char| c.is_ascii_digit()) {
+                    return None;
+                }
+                let matches = wanted
+                    .as_ref()
+                    .is_none_or(|wanted| normalize_version(target_version) == *wanted);
+                // Newest first, desktop (`chrome131`) before mobile (`chrome131_android`)
+                let rank = (
+                    extract_version_number(target_version),
+                    !target_version.contains('_'),
+                );
+                matches.then_some((rank, path))
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, path)| path)
+    })
+}
+
+/// Detect the impersonation targets provided by installed releases.
+///
+/// Returns the names of the wrapper scripts without their `curl_` prefix
+/// (e.g. `chrome124`, `tor145`), sorted and de-duplicated.
+pub fn detect_available_targets(binaries_dir: &Path) -> Vec<String> {
+    let search_paths = search_paths(binaries_dir);
+
+    let mut targets: Vec<String> = search_paths
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .filter(|entry| is_binary_executable(&entry.path()))
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix(WRAPPER_PREFIX)
+                .map(str::to_string)
+        })
+        .collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+/// Every curl-impersonate binary and wrapper script in `binaries_dir` and the system search paths
+pub fn list_installed_binaries(binaries_dir: &Path) -> Vec<InstalledBinary> {
+    let search_paths = search_paths(binaries_dir);
+
+    let mut seen = std::collections::HashSet::new();
+    search_paths
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| is_binary_executable(path))
+        .filter(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+        .filter_map(|path| {
+            let file_name = path.file_name()?.to_string_lossy().into_owned();
+            let (browser, version) = if file_name.starts_with("curl-impersonate") {
+                (None, None)
+            } else {
+                let target = file_name.strip_prefix(WRAPPER_PREFIX)?;
+                let split = target
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(target.len());
+                let (browser, version) = target.split_at(split);
+                browser.parse::<Browser>().ok()?;
+                (
+                    Some(browser.to_string()),
+                    Some(version.to_string()).filter(|v| !v.is_empty()),
+                )
+            };
+
+            let metadata = fs::metadata(&path).ok()?;
+            Some(InstalledBinary {
+                architecture: executable_architecture(&path),
+                path: path.to_string_lossy().into_owned(),
+                browser,
+                version,
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Delete installs of `target` from `binaries_dir`: one wrapper (`chrome124`), every
+/// wrapper of a browser (`chrome`), or the main binary (`curl`)
+pub(crate) fn remove_installed(binaries_dir: &Path, target: &str) -> Result<Vec<PathBuf>> {
+    let target = target.strip_prefix(WRAPPER_PREFIX).unwrap_or(target);
+    let matches: Vec<PathBuf> = list_installed_binaries(binaries_dir)
+        .into_iter()
+        .map(|binary| (PathBuf::from(&binary.path), binary))
+        .filter(|(path, _)| path.parent() == Some(binaries_dir))
+        .filter(|(path, binary)| match &binary.browser {
+            None => target == "curl" || target == "curl-impersonate",
+            Some(browser) => {
+                browser == target
+                    || path.file_name().is_some_and(|name| {
+                        name.to_string_lossy() == format!("{}{}", WRAPPER_PREFIX, target)
+                    })
+            }
+        })
+        .map(|(path, _)| path)
+        .collect();
+
+    if matches.is_empty() {
+        return Err(CuimpError::BinaryNotFound(format!(
+            "{} is not installed in {}",
+            target,
+            binaries_dir.display()
+        )));
     }
-    if !PLATFORM_LIST.contains(&platform) {
-        return Err(CuimpError::UnsupportedPlatform(platform.to_string()));
+    for path in &matches {
+        fs::remove_file(path)?;
     }
+    Ok(matches)
+}
+
+/// Delete outdated files from `binaries_dir`: wrappers older than the newest one of each
+/// browser and leftover archives, or with `keep_latest` false, everything
+pub(crate) fn prune_installed(binaries_dir: &Path, keep_latest: bool) -> Result<Vec<PathBuf>> {
+    let Ok(entries) = fs::read_dir(binaries_dir) else {
+        return Ok(Vec::new());
+    };
+    let files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
 
-    // Get latest version
-    let latest_version = get_latest_release().await?;
-    let actual_version = if version == "latest" {
-        latest_version.trim_start_matches('v').to_string()
+    let outdated: Vec<PathBuf> = if keep_latest {
+        let installed = list_installed_binaries(binaries_dir);
+        let wrappers: Vec<&InstalledBinary> = installed
+            .iter()
+            .filter(|binary| Path::new(&binary.path).parent() == Some(binaries_dir))
+            .filter(|binary| binary.browser.is_some())
+            .collect();
+        let version = |binary: &InstalledBinary| {
+            extract_version_number(binary.version.as_deref().unwrap_or_default())
+        };
+        let newest = |browser: &Option<String>| {
+            wrappers
+                .iter()
+                .filter(|b| b.browser == *browser)
+                .map(|b| version(b))
+                .max()
+                .unwrap_or(0)
+        };
+
+        let old_wrappers = wrappers
+            .iter()
+            .filter(|binary| version(binary) < newest(&binary.browser))
+            .map(|binary| PathBuf::from(&binary.path));
+        let archives = files
+            .iter()
+            .filter(|path| {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                name.ends_with(".tar.gz") || name.ends_with(".tmp")
+            })
+            .cloned();
+        old_wrappers.chain(archives).collect()
     } else {
-        version.trim_start_matches('v').to_string()
+        files
     };
 
-    // Construct download URL
-    let asset_name = if platform == "linux" {
-        let linux_arch = if architecture == "x64" {
-            "x86_64"
+    for path in &outdated {
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
         } else {
-            "aarch64"
-        };
-        format!(
-            "curl-impersonate-{}.{}-linux-gnu.tar.gz",
-            latest_version, linux_arch
-        )
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(outdated)
+}
+
+/// CPU architecture of an ELF, Mach-O or PE executable
+fn executable_architecture(path: &Path) -> Option<String> {
+    use std::io::Read;
+
+    let mut header = [0u8; 512];
+    let read = fs::File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .ok()?;
+    let header = header.get(..read)?;
+    let u16_at = |at: usize| Some(u16::from_le_bytes([*header.get(at)?, *header.get(at + 1)?]));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(header.get(at..at + 4)?.try_into().ok()?));
+
+    let machine = if header.starts_with(b"\x7fELF") {
+        match u16_at(18)? {
+            0x3e => "x64",
+            0xb7 => "arm64",
+            _ => return None,
+        }
+    } else if header.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        match u32_at(4)? {
+            0x0100_0007 => "x64",
+            0x0100_000c => "arm64",
+            _ => return None,
+        }
+    } else if header.starts_with(b"MZ") {
+        let pe = u32_at(0x3c)? as usize;
+        match u16_at(pe + 4)? {
+            0x8664 => "x64",
+            0xaa64 => "arm64",
+            _ => return None,
+        }
     } else {
-        format!(
+        return None;
+    };
+    Some(machine.to_string())
+}
+
+/// Check that a non-browser target is provided by the installed release.
+///
+/// Passes when nothing is installed yet, since the target may still be downloaded.
+fn check_target_available(browser: Browser, binaries_dir: &Path) -> Result<()> {
+    if !matches!(browser, Browser::Tor | Browser::OkHttp) {
+        return Ok(());
+    }
+
+    let targets = detect_available_targets(binaries_dir);
+    if targets.is_empty()
+        || targets
+            .iter()
+            .any(|target| target.starts_with(browser.as_str()))
+    {
+        return Ok(());
+    }
+
+    Err(CuimpError::UnsupportedBrowser(format!(
+        "{} is not provided by the installed curl-impersonate release. Available targets: {}",
+        browser,
+        targets.join(", ")
+    )))
+}
+
+/// Archive formats used by curl-impersonate releases
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    TarGz,
+    TarXz,
+    Zip,
+}
+
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+impl ArchiveFormat {
+    fn from_name(name: &str) -> ArchiveFormat {
+        if name.ends_with(".zip") {
+            ArchiveFormat::Zip
+        } else if name.ends_with(".tar.xz") {
+            ArchiveFormat::TarXz
+        } else {
+            ArchiveFormat::TarGz
+        }
+    }
+}
+
+/// Browser version without separators and in lowercase, so `18_0` equals `180`
+fn normalize_version(version: &str) -> String {
+    version
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Check that the descriptor's browser version exists, in the bundled table or as an
+/// installed wrapper script. Versions compare without separators, so `18_0` matches `180`.
+fn check_version_available(
+    descriptor: &CuimpDescriptor,
+    browser: Browser,
+    binaries_dir: &Path,
+) -> Result<()> {
+    let Some(version) = descriptor
+        .version
+        .as_deref()
+        .filter(|version| *version != "latest" && pinned_release(descriptor).is_none())
+    else {
+        return Ok(());
+    };
+    let mut versions: Vec<String> = browser
+        .known_versions()
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    versions.extend(
+        detect_available_targets(binaries_dir)
+            .iter()
+            .filter_map(|target| target.strip_prefix(browser.as_str()))
+            .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+            .map(str::to_string),
+    );
+    let wanted = normalize_version(version);
+    if versions.is_empty() || versions.iter().any(|v| normalize_version(v) == wanted) {
+        return Ok(());
+    }
+
+    versions.sort_by_key(|v| (extract_version_number(v), v.clone()));
+    versions.dedup();
+    Err(CuimpError::UnsupportedBrowser(format!(
+        "{} {} is not available in curl-impersonate. Available versions: {}",
+        browser,
+        version,
+        versions.join(", ")
+    )))
+}
+
+/// Release asset for a platform: `.tar.gz` for Linux and macOS, `.zip` for Windows
+#[cfg(feature = "download")]
+fn release_asset_name(tag: &str, architecture: Arch, platform: Platform) -> String {
+    let cpu = match architecture {
+        Arch::X64 => "x86_64",
+        Arch::Arm64 => "aarch64",
+    };
+    match platform {
+        Platform::Linux => format!("curl-impersonate-{}.{}-linux-gnu.tar.gz", tag, cpu),
+        Platform::Windows => format!("curl-impersonate-{}.{}-win32.zip", tag, cpu),
+        Platform::Macos => format!(
             "curl-impersonate-{}.{}-{}.tar.gz",
-            latest_version, architecture, platform
+            tag, architecture, platform
+        ),
+    }
+}
+
+/// Unpack an archive into `dest`; entries escaping `dest` are rejected
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+fn extract_archive(path: &Path, format: ArchiveFormat, dest: &Path) -> Result<()> {
+    let extraction_failed = |e: &dyn std::fmt::Display| CuimpError::ExtractionFailed(e.to_string());
+    let file = fs::File::open(path)?;
+    match format {
+        ArchiveFormat::TarGz => Archive::new(GzDecoder::new(file))
+            .unpack(dest)
+            .map_err(|e| extraction_failed(&e)),
+        ArchiveFormat::TarXz => {
+            let mut tar = Vec::new();
+            lzma_rs::xz_decompress(&mut std::io::BufReader::new(file), &mut tar)
+                .map_err(|e| extraction_failed(&e))?;
+            Archive::new(tar.as_slice())
+                .unpack(dest)
+                .map_err(|e| extraction_failed(&e))
+        }
+        ArchiveFormat::Zip => zip::ZipArchive::new(file)
+            .and_then(|mut archive| archive.extract(dest))
+            .map_err(|e| extraction_failed(&e)),
+    }
+}
+
+/// Exclusive lock on a binaries directory, held while a release is downloaded and
+/// extracted into it so concurrent processes and tasks don't unpack over each other.
+/// Released on drop, or by the OS when the process dies.
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+struct DownloadLock {
+    _file: fs::File,
+    /// Whether another holder had to finish first
+    waited: bool,
+}
+
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+impl DownloadLock {
+    async fn acquire(binaries_dir: &Path, events: &Events) -> Result<Self> {
+        fs::create_dir_all(binaries_dir)?;
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(binaries_dir.join(DOWNLOAD_LOCK_FILE))?;
+        let mut waited = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => {
+                    return Ok(DownloadLock {
+                        _file: file,
+                        waited,
+                    })
+                }
+                Err(fs::TryLockError::WouldBlock) => {
+                    if !waited {
+                        events.emit(CuimpEvent::DownloadWaiting {
+                            binaries_dir: binaries_dir.to_path_buf(),
+                        });
+                    }
+                    waited = true;
+                    tokio::time::sleep(Duration::from_millis(DOWNLOAD_LOCK_POLL_MS)).await;
+                }
+                Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Download and extract binary, verified as `descriptor` asks
+#[cfg(feature = "download")]
+pub async fn download_and_extract_binary(
+    browser: Browser,
+    architecture: Arch,
+    platform: Platform,
+    release: &str,
+    descriptor: &CuimpDescriptor,
+    resolve: &ResolveOptions,
+) -> Result<BinaryInfo> {
+    // Resolve the release tag
+    let tag = if release == "latest" {
+        get_latest_release_cached(
+            resolve.release_cache_ttl,
+            resolve.refresh_release,
+            &resolve.events,
         )
+        .await?
+    } else {
+        format!("v{}", release.trim_start_matches('v'))
     };
+    let actual_version = tag.trim_start_matches('v').to_string();
+
+    // Construct download URL
+    let asset_name = release_asset_name(&tag, architecture, platform);
 
     let download_url = format!(
         "https://github.com/lexiforest/curl-impersonate/releases/download/{}/{}",
-        latest_version, asset_name
+        tag, asset_name
     );
 
-    println!("Downloading {}...", download_url);
+    resolve.events.emit(CuimpEvent::DownloadStarted {
+        url: download_url.clone(),
+    });
 
     // Download the file
     let client = reqwest::Client::new();
-    let response = client
+    let mut response = client
         .get(&download_url)
         .send()
         .await
         .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?;
 
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(CuimpError::DownloadFailed(format!(
+            "curl-impersonate release {} has no asset {} for {}-{}",
+            tag, asset_name, platform, architecture
+        )));
+    }
     if !response.status().is_success() {
         return Err(CuimpError::DownloadFailed(format!(
             "HTTP {}: {}",
@@ -236,39 +740,98 @@ pub async fn download_and_extract_binary(
         )));
     }
 
-    let bytes = response
-        .bytes()
+    let total = response.content_length();
+    let mut bytes = Vec::with_capacity(
+        total
+            .and_then(|total| usize::try_from(total).ok())
+            .unwrap_or_default(),
+    );
+    while let Some(chunk) = response
+        .chunk()
         .await
-        .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?;
+        .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?
+    {
+        bytes.extend_from_slice(&chunk);
+        resolve.events.emit(CuimpEvent::DownloadProgress {
+            downloaded: bytes.len() as u64,
+            total,
+        });
+    }
+
+    // Verify before anything touches the filesystem
+    let expected = match descriptor
+        .sha256
+        .as_deref()
+        .or_else(|| pinned_digest(&asset_name))
+    {
+        Some(sha256) => Some(sha256.to_string()),
+        None => fetch_published_checksum(&client, &download_url, &asset_name).await?,
+    };
+    verify_archive(
+        &bytes,
+        expected.as_deref(),
+        &asset_name,
+        descriptor.allow_unverified,
+        &resolve.events,
+    )?;
+    if let Some(signing_key) = descriptor.signing_key.as_deref() {
+        verify_signature(&client, &download_url, &asset_name, &bytes, signing_key).await?;
+    }
+
+    let binary_path = install_archive(
+        &bytes,
+        &asset_name,
+        browser,
+        platform,
+        &resolve.binaries_dir,
+    )?;
+    Ok(BinaryInfo {
+        binary_path: binary_path.to_string_lossy().to_string(),
+        is_downloaded: true,
+        version: Some(actual_version),
+    })
+}
 
+/// Unpack a release archive into `binaries_dir` and return the binary to use: the main
+/// curl-impersonate binary, or the newest wrapper of `browser` for releases without one
+#[cfg(any(feature = "download", feature = "embed-binary"))]
+fn install_archive(
+    archive: &[u8],
+    asset_name: &str,
+    browser: Browser,
+    platform: Platform,
+    binaries_dir: &Path,
+) -> Result<PathBuf> {
     // Create binaries directory
-    let binaries_dir = get_binaries_dir();
-    fs::create_dir_all(&binaries_dir)?;
+    fs::create_dir_all(binaries_dir)?;
 
     // Save to temporary file
-    let temp_file_path = binaries_dir.join(format!("{}-{}-{}.tar.gz", browser, architecture, platform));
-    fs::write(&temp_file_path, bytes)?;
+    let temp_file_path = binaries_dir.join(asset_name);
+    fs::write(&temp_file_path, archive)?;
 
     // Extract the archive
-    println!("Extracting to {:?}...", binaries_dir);
-    let tar_gz = fs::File::open(&temp_file_path)?;
-    let tar = GzDecoder::new(tar_gz);
-    let mut archive = Archive::new(tar);
-    archive
-        .unpack(&binaries_dir)
-        .map_err(|e| CuimpError::ExtractionFailed(e.to_string()))?;
+    let extracted = extract_archive(
+        &temp_file_path,
+        ArchiveFormat::from_name(asset_name),
+        binaries_dir,
+    );
 
     // Clean up temp file
     fs::remove_file(&temp_file_path)?;
+    extracted?;
 
     // Find the extracted binary
-    let main_binary_name = "curl-impersonate";
+    let main_binary_name = if platform == Platform::Windows {
+        "curl-impersonate.exe"
+    } else {
+        "curl-impersonate"
+    };
     let binary_path = binaries_dir.join(main_binary_name);
 
     if !binary_path.exists() {
         // Look for browser-specific binary
         let browser_pattern = format!("curl_{}", browser);
-        if let Ok(entries) = fs::read_dir(&binaries_dir) {
+        if let Ok(entries) = fs::read_dir(binaries_dir) {
             let mut matches: Vec<PathBuf> = entries
                 .filter_map(|entry| entry.ok())
                 .map(|entry| entry.path())
@@ -281,20 +844,14 @@ pub async fn download_and_extract_binary(
                 })
                 .collect();
 
-            if !matches.is_empty() {
-                matches.sort_by(|a, b| {
-                    let ver_a = extract_version_number(&a.to_string_lossy());
-                    let ver_b = extract_version_number(&b.to_string_lossy());
-                    ver_b.cmp(&ver_a)
-                });
-                let browser_binary_path = matches[0].clone();
+            matches.sort_by(|a, b| {
+                let ver_a = extract_version_number(&a.to_string_lossy());
+                let ver_b = extract_version_number(&b.to_string_lossy());
+                ver_b.cmp(&ver_a)
+            });
+            if let Some(browser_binary_path) = matches.into_iter().next() {
                 make_executable(&browser_binary_path)?;
-
-                return Ok(BinaryInfo {
-                    binary_path: browser_binary_path.to_string_lossy().to_string(),
-                    is_downloaded: true,
-                    version: Some(actual_version),
-                });
+                return Ok(browser_binary_path);
             }
         }
 
@@ -306,44 +863,824 @@ pub async fn download_and_extract_binary(
 
     // Make executable
     make_executable(&binary_path)?;
+    Ok(binary_path)
+}
+
+/// Digest of `asset_name` from `RELEASE_DIGESTS`, if the release is pinned there
+#[cfg(feature = "download")]
+fn pinned_digest(asset_name: &str) -> Option<&'static str> {
+    RELEASE_DIGESTS
+        .iter()
+        .find(|(asset, _)| *asset == asset_name)
+        .map(|&(_, sha256)| sha256)
+}
+
+/// Fetch `<asset>.sha256` from the release, if published
+#[cfg(feature = "download")]
+async fn fetch_published_checksum(
+    client: &reqwest::Client,
+    download_url: &str,
+    asset_name: &str,
+) -> Result<Option<String>> {
+    let response = client
+        .get(format!("{}.sha256", download_url))
+        .send()
+        .await
+        .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?;
 
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(CuimpError::DownloadFailed(format!(
+            "HTTP {} fetching checksum for {}",
+            response.status(),
+            asset_name
+        )));
+    }
+
+    let text = response
+        .text()
+        .await
+        .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?;
+    parse_checksum_file(&text, asset_name)
+        .map(Some)
+        .ok_or_else(|| {
+            CuimpError::DownloadFailed(format!("Unreadable checksum file for {}", asset_name))
+        })
+}
+
+/// Fetch `<asset>.minisig` and verify the archive against `signing_key`
+#[cfg(feature = "verify-signatures")]
+async fn verify_signature(
+    client: &reqwest::Client,
+    download_url: &str,
+    asset_name: &str,
+    bytes: &[u8],
+    signing_key: &str,
+) -> Result<()> {
+    let response = client
+        .get(format!("{}.minisig", download_url))
+        .send()
+        .await
+        .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(CuimpError::SignatureInvalid(format!(
+            "{}: no signature available (HTTP {})",
+            asset_name,
+            response.status()
+        )));
+    }
+
+    let signature_file = response
+        .text()
+        .await
+        .map_err(|e| CuimpError::DownloadFailed(e.to_string()))?;
+    crate::signature::verify_minisign(bytes, &signature_file, signing_key, asset_name)
+}
+
+/// Without `verify-signatures` a configured key cannot be honored, so refuse to install
+#[cfg(all(feature = "download", not(feature = "verify-signatures")))]
+async fn verify_signature(
+    _client: &reqwest::Client,
+    _download_url: &str,
+    asset_name: &str,
+    _bytes: &[u8],
+    _signing_key: &str,
+) -> Result<()> {
+    Err(CuimpError::SignatureInvalid(format!(
+        "{}: signing_key is set but the `verify-signatures` feature is disabled",
+        asset_name
+    )))
+}
+
+/// Find the digest for `asset_name` in `sha256sum` output (or a bare digest)
+#[cfg(feature = "download")]
+fn parse_checksum_file(text: &str, asset_name: &str) -> Option<String> {
+    let is_digest = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    let entries: Vec<(&str, Option<&str>)> = text
+        .lines()
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let digest = parts.next().unwrap_or_default();
+            let name = parts.next().map(|n| n.trim_start_matches('*'));
+            (digest, name)
+        })
+        .filter(|(digest, _)| is_digest(digest))
+        .collect();
+
+    entries
+        .iter()
+        .find(|(_, name)| *name == Some(asset_name))
+        .or_else(|| entries.iter().find(|(_, name)| name.is_none()))
+        .or(if entries.len() == 1 {
+            entries.first()
+        } else {
+            None
+        })
+        .map(|(digest, _)| digest.to_ascii_lowercase())
+}
+
+/// Check a downloaded archive against its expected digest; one without a digest is only
+/// installed when `allow_unverified` is set
+#[cfg(feature = "download")]
+fn verify_archive(
+    bytes: &[u8],
+    expected: Option<&str>,
+    asset_name: &str,
+    allow_unverified: bool,
+    events: &Events,
+) -> Result<()> {
+    match expected {
+        Some(expected) => verify_sha256(bytes, expected, asset_name),
+        None if allow_unverified => {
+            events.emit(CuimpEvent::ChecksumUnavailable {
+                asset: asset_name.to_string(),
+            });
+            Ok(())
+        }
+        None => Err(CuimpError::ChecksumUnknown(format!(
+            "no SHA-256 is known for {}; set CuimpDescriptor::sha256, or allow_unverified to install it anyway",
+            asset_name
+        ))),
+    }
+}
+
+/// Compare the SHA-256 of `bytes` against a hex digest
+#[cfg(feature = "download")]
+fn verify_sha256(bytes: &[u8], expected: &str, asset_name: &str) -> Result<()> {
+    crate::checksum::verify(&crate::checksum::sha256_hex(bytes), expected, asset_name)
+}
+
+/// The curl-impersonate release a descriptor pins: `release`, or a `version` written
+/// as a release number (`1.0.0`, `v1.0.0`) rather than a browser version (`124`)
+pub(crate) fn pinned_release(descriptor: &CuimpDescriptor) -> Option<&str> {
+    descriptor
+        .release
+        .as_deref()
+        .filter(|r| *r != "latest")
+        .or_else(|| {
+            descriptor
+                .version
+                .as_deref()
+                .filter(|v| v.trim_start_matches('v').contains('.'))
+        })
+}
+
+async fn existing_binary_info(binary_path: &Path, events: &Events) -> Result<BinaryInfo> {
+    let binary_path = binary_path.to_string_lossy().to_string();
+    let version = probe_binary(&binary_path).await?;
+    events.emit(CuimpEvent::BinaryFound {
+        path: binary_path.clone(),
+        version: version.clone(),
+    });
     Ok(BinaryInfo {
-        binary_path: binary_path.to_string_lossy().to_string(),
-        is_downloaded: true,
-        version: Some(actual_version),
+        binary_path,
+        is_downloaded: false,
+        version,
     })
 }
 
+/// Release archive built in with the `embed-binary` feature: its file name and contents
+fn embedded_archive() -> Option<(&'static str, &'static [u8])> {
+    #[cfg(feature = "embed-binary")]
+    return include!(concat!(env!("OUT_DIR"), "/embedded_archive.rs"));
+    #[cfg(not(feature = "embed-binary"))]
+    None
+}
+
 /// Parse descriptor and get binary information
-pub async fn parse_descriptor(descriptor: &CuimpDescriptor) -> Result<BinaryInfo> {
+pub(crate) async fn parse_descriptor(
+    descriptor: &CuimpDescriptor,
+    resolve: &ResolveOptions,
+) -> Result<BinaryInfo> {
     // Validate descriptor
     validate_descriptor(descriptor)?;
 
     // Get system info
     let (architecture, platform) = get_system_info()?;
-    let browser = descriptor.browser.as_deref().unwrap_or("chrome");
-    let version = descriptor.version.as_deref().unwrap_or("latest");
-
-    // First, try to find existing binary
-    if let Some(existing_binary) = find_existing_binary(Some(browser)) {
-        println!("Found existing binary: {:?}", existing_binary);
-        let version_str = extract_version_number(&existing_binary.to_string_lossy()).to_string();
-        return Ok(BinaryInfo {
-            binary_path: existing_binary.to_string_lossy().to_string(),
-            is_downloaded: false,
-            version: if version_str != "0" {
-                Some(version_str)
-            } else {
-                Some("unknown".to_string())
+    let browser = descriptor.browser.unwrap_or(Browser::Chrome);
+    let release = pinned_release(descriptor).unwrap_or("latest");
+
+    check_target_available(browser, &resolve.binaries_dir)?;
+    check_version_available(descriptor, browser, &resolve.binaries_dir)?;
+
+    // First, try to find existing binary, after any download in progress has finished
+    #[cfg(any(feature = "download", feature = "embed-binary"))]
+    if resolve.binaries_dir.join(DOWNLOAD_LOCK_FILE).exists() {
+        drop(DownloadLock::acquire(&resolve.binaries_dir, &resolve.events).await?);
+    }
+    let version = descriptor
+        .version
+        .as_deref()
+        .filter(|v| *v != "latest" && pinned_release(descriptor).is_none());
+    let find_existing = || {
+        find_wrapper(browser, version, &resolve.binaries_dir)
+            .or_else(|| find_existing_binary(Some(browser.as_str()), &resolve.binaries_dir))
+    };
+    let embedded = embedded_archive();
+    let can_install = embedded.is_some() || (cfg!(feature = "download") && resolve.auto_download);
+    if let Some(existing_binary) = find_existing().filter(|_| !resolve.force || !can_install) {
+        return existing_binary_info(&existing_binary, &resolve.events).await;
+    }
+    let not_found = || {
+        let hint = if cfg!(feature = "download") {
+            "enable CuimpOptions::auto_download"
+        } else {
+            "enable the `download` feature"
+        };
+        CuimpError::BinaryNotFound(format!(
+            "No curl-impersonate binary found for {} on {}-{}. Set CuimpOptions::path or {}.",
+            browser, platform, architecture, hint
+        ))
+    };
+    if !can_install {
+        return Err(not_found());
+    }
+
+    // If not found, unpack the embedded release or download one
+    #[cfg(any(feature = "download", feature = "embed-binary"))]
+    {
+        // Whoever holds the lock is installing the same release; reuse what it installed
+        let lock = DownloadLock::acquire(&resolve.binaries_dir, &resolve.events).await?;
+        if let Some(existing_binary) = find_existing().filter(|_| lock.waited || !resolve.force) {
+            return existing_binary_info(&existing_binary, &resolve.events).await;
+        }
+
+        let mut info = match embedded {
+            Some((asset_name, archive)) => BinaryInfo {
+                binary_path: install_archive(
+                    archive,
+                    asset_name,
+                    browser,
+                    platform,
+                    &resolve.binaries_dir,
+                )?
+                .to_string_lossy()
+                .into_owned(),
+                is_downloaded: false,
+                version: None,
             },
+            #[cfg(feature = "download")]
+            None => {
+                download_and_extract_binary(
+                    browser,
+                    architecture,
+                    platform,
+                    release,
+                    descriptor,
+                    resolve,
+                )
+                .await?
+            }
+            #[cfg(not(feature = "download"))]
+            None => {
+                let _ = release;
+                return Err(not_found());
+            }
+        };
+        if let Some(wrapper) = find_wrapper(browser, version, &resolve.binaries_dir) {
+            info.binary_path = wrapper.to_string_lossy().into_owned();
+        }
+        drop(lock);
+        resolve.events.emit(CuimpEvent::Extracted {
+            binaries_dir: resolve.binaries_dir.clone(),
+            path: info.binary_path.clone(),
         });
+        if let Some(version) = probe_binary(&info.binary_path).await? {
+            info.version = Some(version);
+        }
+        Ok(info)
     }
 
-    // If not found, download it
-    println!(
-        "No existing binary found. Downloading curl-impersonate for {} on {}-{}...",
-        browser, platform, architecture
-    );
+    #[cfg(not(any(feature = "download", feature = "embed-binary")))]
+    {
+        let _ = release;
+        Err(not_found())
+    }
+}
+
+type ResolvedBinaries = Mutex<HashMap<(CuimpDescriptor, PathBuf), Arc<OnceCell<BinaryInfo>>>>;
+
+/// Binaries resolved so far in this process, by descriptor and binaries directory
+fn resolved_binaries() -> &'static ResolvedBinaries {
+    static RESOLVED: OnceLock<ResolvedBinaries> = OnceLock::new();
+    RESOLVED.get_or_init(Default::default)
+}
+
+/// `parse_descriptor`, remembered for the rest of the process so clients created per
+/// call (like the module-level `get`) don't scan the filesystem or ask GitHub each time.
+///
+/// Concurrent callers with the same descriptor share one resolution. Failures are not
+/// remembered, a binary that has disappeared since is resolved again, and `force` and
+/// `refresh_release` always resolve anew.
+pub(crate) async fn parse_descriptor_cached(
+    descriptor: &CuimpDescriptor,
+    resolve: &ResolveOptions,
+) -> Result<BinaryInfo> {
+    if resolve.force || resolve.refresh_release {
+        return parse_descriptor(descriptor, resolve).await;
+    }
+    let cell = {
+        let mut resolved = resolved_binaries()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let cell = resolved
+            .entry((descriptor.clone(), resolve.binaries_dir.clone()))
+            .or_default();
+        if cell
+            .get()
+            .is_some_and(|info| !is_binary_executable(Path::new(&info.binary_path)))
+        {
+            *cell = Arc::default();
+        }
+        Arc::clone(cell)
+    };
+    cell.get_or_try_init(|| parse_descriptor(descriptor, resolve))
+        .await
+        .cloned()
+}
 
-    download_and_extract_binary(browser, &architecture, &platform, version).await
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_installed_binaries() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cuimp-installed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut elf = b"\x7fELF".to_vec();
+        elf.resize(18, 0);
+        elf.extend_from_slice(&[0xb7, 0x00]);
+        for (name, contents) in [
+            ("curl-impersonate", elf.as_slice()),
+            ("curl_chrome124", b"#!/bin/sh\n".as_slice()),
+            ("curl_unknown1", b"#!/bin/sh\n".as_slice()),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, contents).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let mut found: Vec<InstalledBinary> = list_installed_binaries(&dir)
+            .into_iter()
+            .filter(|b| b.path.starts_with(&*dir.to_string_lossy()))
+            .collect();
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            (
+                found[0].browser.as_deref(),
+                found[0].architecture.as_deref()
+            ),
+            (None, Some("arm64"))
+        );
+        assert_eq!(found[0].size, 20);
+        assert_eq!(found[1].browser.as_deref(), Some("chrome"));
+        assert_eq!(
+            (
+                found[1].version.as_deref(),
+                found[1].architecture.as_deref()
+            ),
+            (Some("124"), None)
+        );
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        let lexiforest = "curl 8.13.0 (x86_64-pc-linux-gnu) libcurl/8.13.0 BoringSSL curl-impersonate/v1.0.0\nRelease-Date: 2025-04-02\n";
+        assert_eq!(parse_version_output(lexiforest).as_deref(), Some("1.0.0"));
+        assert_eq!(
+            parse_version_output("curl 8.1.1 (x86_64-pc-linux-gnu) libcurl/8.1.1 BoringSSL\n")
+                .as_deref(),
+            Some("8.1.1")
+        );
+        assert_eq!(parse_version_output("garbage"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_existing_binary_is_probed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cuimp-probe-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("curl-impersonate");
+        let resolve = ResolveOptions {
+            binaries_dir: dir.clone(),
+            ..Default::default()
+        };
+        fs::write(&binary, "#!/bin/sh\necho 'curl 8.13.0 (aarch64-apple-darwin) libcurl/8.13.0 curl-impersonate/1.0.0'\n").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        let info = parse_descriptor(&CuimpDescriptor::default(), &resolve)
+            .await
+            .unwrap();
+        assert_eq!(
+            (info.binary_path, info.version),
+            (
+                binary.to_string_lossy().into_owned(),
+                Some("1.0.0".to_string())
+            )
+        );
+
+        // A truncated download still has its executable bit
+        fs::write(&binary, b"\x7fELF\x02\x01").unwrap();
+        let error = parse_descriptor(&CuimpDescriptor::default(), &resolve)
+            .await
+            .unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            matches!(error, CuimpError::BinaryNotExecutable(message) if message.starts_with(&*binary.to_string_lossy()))
+        );
+    }
+
+    #[cfg(feature = "download")]
+    #[tokio::test]
+    async fn test_download_lock() {
+        let dir = std::env::temp_dir().join(format!("cuimp-lock-{}", std::process::id()));
+        let first = DownloadLock::acquire(&dir, &Events::default())
+            .await
+            .unwrap();
+        assert!(!first.waited);
+
+        let waiter = tokio::spawn({
+            let dir = dir.clone();
+            async move {
+                DownloadLock::acquire(&dir, &Events::default())
+                    .await
+                    .map(|lock| lock.waited)
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(3 * DOWNLOAD_LOCK_POLL_MS)).await;
+        assert!(!waiter.is_finished());
+        drop(first);
+        let waited = waiter.await.unwrap().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(waited);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_resolved_binary_cache() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cuimp-resolved-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let install = |name: &str| {
+            let path = dir.join(name);
+            fs::write(
+                &path,
+                "#!/bin/sh\necho 'curl 8.13.0 (x86_64-pc-linux-gnu)'\n",
+            )
+            .unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let old = install("curl_chrome124");
+        let descriptor = CuimpDescriptor {
+            browser: Some(Browser::Chrome),
+            ..Default::default()
+        };
+        let resolve = ResolveOptions {
+            binaries_dir: dir.clone(),
+            ..Default::default()
+        };
+        let resolved = || async {
+            parse_descriptor_cached(&descriptor, &resolve)
+                .await
+                .unwrap()
+                .binary_path
+        };
+
+        let first = resolved().await;
+        let newer = install("curl_chrome131");
+        // Remembered without scanning again, until the binary goes away
+        assert_eq!(resolved().await, first);
+        fs::remove_file(&old).unwrap();
+        let after_removal = resolved().await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first, old.to_string_lossy());
+        assert_eq!(after_removal, newer.to_string_lossy());
+    }
+
+    #[cfg(all(unix, any(feature = "download", feature = "embed-binary")))]
+    #[test]
+    fn test_install_archive() {
+        let dir = std::env::temp_dir().join(format!("cuimp-install-{}", std::process::id()));
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::fast(),
+        ));
+        for name in ["curl-impersonate", "curl_chrome124"] {
+            let script = b"#!/bin/sh\necho 'curl 8.13.0'\n";
+            let mut header = tar::Header::new_gnu();
+            header.set_size(script.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, &script[..]).unwrap();
+        }
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+
+        let binary = install_archive(
+            &archive,
+            "release.tar.gz",
+            Browser::Chrome,
+            Platform::Linux,
+            &dir,
+        )
+        .unwrap();
+        let executable = is_binary_executable(&binary);
+        let leftover = dir.join("release.tar.gz").exists();
+        let wrapper = dir.join("curl_chrome124").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(binary, dir.join("curl-impersonate"));
+        assert!(executable && wrapper && !leftover);
+    }
+
+    #[cfg(feature = "download")]
+    #[tokio::test]
+    async fn test_auto_download_disabled() {
+        let dir = std::env::temp_dir().join(format!("cuimp-no-download-{}", std::process::id()));
+        let resolve = ResolveOptions {
+            binaries_dir: dir.clone(),
+            auto_download: false,
+            ..Default::default()
+        };
+        let descriptor = CuimpDescriptor {
+            browser: Some(Browser::Tor),
+            ..Default::default()
+        };
+        let result = parse_descriptor(&descriptor, &resolve).await;
+
+        assert!(
+            matches!(result, Err(CuimpError::BinaryNotFound(message)) if message.contains("auto_download"))
+        );
+        assert!(!dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wrapper_resolution() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cuimp-wrappers-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "curl-impersonate",
+            "curl_chrome124",
+            "curl_chrome131",
+            "curl_chrome131_android",
+            "curl_safari180",
+        ] {
+            let path = dir.join(name);
+            fs::write(
+                &path,
+                "#!/bin/sh\necho 'curl 8.13.0 (x86_64-pc-linux-gnu) libcurl/8.13.0'\n",
+            )
+            .unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let resolve = ResolveOptions {
+            binaries_dir: dir.clone(),
+            ..Default::default()
+        };
+        let mut resolved = Vec::new();
+        for (browser, version) in [
+            (Browser::Chrome, Some("124")),
+            (Browser::Chrome, None),
+            (Browser::Safari, Some("18_0")),
+            (Browser::Firefox, None),
+            (Browser::Curl, None),
+        ] {
+            let descriptor = CuimpDescriptor {
+                browser: Some(browser),
+                version: version.map(str::to_string),
+                ..Default::default()
+            };
+            let info = parse_descriptor(&descriptor, &resolve).await.unwrap();
+            resolved.push(
+                Path::new(&info.binary_path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Firefox has no wrapper installed and falls back to the main binary
+        assert_eq!(
+            resolved,
+            [
+                "curl_chrome124",
+                "curl_chrome131",
+                "curl_safari180",
+                "curl-impersonate",
+                "curl-impersonate"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_version_available() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cuimp-versions-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let wrapper = dir.join("curl_safari999");
+        fs::write(&wrapper, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755)).unwrap();
+        let check = |browser, version: &str| {
+            let descriptor = CuimpDescriptor {
+                browser: Some(browser),
+                version: Some(version.to_string()),
+                ..Default::default()
+            };
+            check_version_available(&descriptor, browser, &dir)
+        };
+
+        assert!(check(Browser::Safari, "18_0").is_ok());
+        assert!(check(Browser::Safari, "999").is_ok());
+        assert!(check(Browser::Chrome, "1.0.0").is_ok());
+        assert!(check(Browser::OkHttp, "4").is_ok() && check(Browser::Edge, "latest").is_ok());
+        let error = check(Browser::Safari, "121").unwrap_err().to_string();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(error.contains("safari 121 is not available"));
+        assert!(error.ends_with("Available versions: 153, 155, 170, 172_ios, 180, 180_ios, 184, 184_ios, 260, 260_ios, 999"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_prune_and_remove() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cuimp-prune-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "curl-impersonate",
+            "curl_chrome116",
+            "curl_chrome124",
+            "curl_firefox133",
+            "old.tar.gz",
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let names = |paths: Vec<PathBuf>| {
+            let mut names: Vec<String> = paths
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            names(prune_installed(&dir, true).unwrap()),
+            ["curl_chrome116", "old.tar.gz"]
+        );
+        assert_eq!(
+            names(remove_installed(&dir, "firefox").unwrap()),
+            ["curl_firefox133"]
+        );
+        assert!(matches!(
+            remove_installed(&dir, "safari"),
+            Err(CuimpError::BinaryNotFound(_))
+        ));
+        assert_eq!(
+            names(prune_installed(&dir, false).unwrap()),
+            ["curl-impersonate", "curl_chrome124"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "download")]
+    #[test]
+    fn test_archive_formats() {
+        use std::io::Write;
+
+        assert_eq!(
+            release_asset_name("v1.0.0", Arch::X64, Platform::Windows),
+            "curl-impersonate-v1.0.0.x86_64-win32.zip"
+        );
+        assert_eq!(
+            release_asset_name("v1.0.0", Arch::Arm64, Platform::Linux),
+            "curl-impersonate-v1.0.0.aarch64-linux-gnu.tar.gz"
+        );
+
+        let dir = std::env::temp_dir().join(format!("cuimp-archives-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let zip_path = dir.join("release.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        writer
+            .start_file(
+                "curl-impersonate.exe",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(b"zip binary").unwrap();
+        writer.finish().unwrap();
+        extract_archive(
+            &zip_path,
+            ArchiveFormat::from_name("x.zip"),
+            &dir.join("zip"),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join("zip/curl-impersonate.exe")).unwrap(),
+            b"zip binary"
+        );
+
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(10);
+        header.set_cksum();
+        tar.append_data(&mut header, "curl-impersonate", &b"xz binary!"[..])
+            .unwrap();
+        let tar = tar.into_inner().unwrap();
+        let mut xz = Vec::new();
+        lzma_rs::xz_compress(&mut tar.as_slice(), &mut xz).unwrap();
+        let xz_path = dir.join("release.tar.xz");
+        fs::write(&xz_path, xz).unwrap();
+        extract_archive(
+            &xz_path,
+            ArchiveFormat::from_name("x.tar.xz"),
+            &dir.join("xz"),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join("xz/curl-impersonate")).unwrap(),
+            b"xz binary!"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "download")]
+    #[test]
+    fn test_checksum_verification() {
+        let archive = b"archive bytes";
+        let digest = "cc9c340301ad4ba5e54aa24b442ff938d1ed84f7f32c4c5a73773c58af37bd1b";
+        // SHA-256 of no bytes at all
+        let other = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let listing = format!(
+            "{}  other.tar.gz\n{} *curl.tar.gz\n",
+            "0".repeat(64),
+            digest.to_uppercase()
+        );
+        assert_eq!(
+            parse_checksum_file(&listing, "curl.tar.gz").as_deref(),
+            Some(digest)
+        );
+        assert_eq!(
+            parse_checksum_file(digest, "curl.tar.gz").as_deref(),
+            Some(digest)
+        );
+        assert_eq!(parse_checksum_file("not a checksum", "curl.tar.gz"), None);
+
+        assert_eq!(crate::checksum::sha256_hex(archive), digest);
+        assert!(verify_sha256(archive, &digest.to_uppercase(), "curl.tar.gz").is_ok());
+        assert!(matches!(
+            verify_sha256(archive, other, "curl.tar.gz"),
+            Err(CuimpError::ChecksumMismatch(_))
+        ));
+    }
+
+    #[cfg(feature = "download")]
+    #[test]
+    fn test_unknown_checksum() {
+        let events = Events::default();
+        assert!(verify_archive(b"archive", None, "curl.tar.gz", true, &events).is_ok());
+        assert!(matches!(
+            verify_archive(b"archive", None, "curl.tar.gz", false, &events),
+            Err(CuimpError::ChecksumUnknown(_))
+        ));
+        assert!(matches!(
+            verify_archive(
+                b"archive",
+                Some(&"0".repeat(64)),
+                "curl.tar.gz",
+                true,
+                &events
+            ),
+            Err(CuimpError::ChecksumMismatch(_))
+        ));
+
+        // Pinned digests are well-formed and keyed by release asset name
+        for (asset, sha256) in RELEASE_DIGESTS {
+            assert!(asset.starts_with("curl-impersonate-v"), "{}", asset);
+            assert!(sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit()));
+        }
+    }
 }
@@ -0,0 +1,228 @@
+use crate::types::{Browser, CuimpDescriptor, Platform};
+
+/// When `CuimpHttp` sends the impersonated browser's default headers
+///
+/// The per-target wrapper scripts (`curl_chrome124`, ...) and libcurl-impersonate
+/// already send them, so `Auto` only adds them when the plain `curl-impersonate`
+/// binary is used. Request, profile and default headers override preset values in place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderPresets {
+    /// Add presets when the resolved binary is not a per-target wrapper
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+const CHROME_VERSION: &str = "136";
+const EDGE_VERSION: &str = "101";
+const FIREFOX_VERSION: &str = "135";
+const TOR_FIREFOX_VERSION: &str = "128";
+const SAFARI_VERSION: &str = "18_0";
+
+const NAVIGATE_ACCEPT_CHROMIUM: &str =
+    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,\
+     image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+const NAVIGATE_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// Headers the descriptor's browser sends on a top-level navigation, in the browser's order.
+///
+/// Versions default to the newest supported target and the platform to Windows (macOS
+/// for Safari). Targets without a browser profile (`okhttp`, `curl`) have no preset.
+pub fn browser_headers(descriptor: &CuimpDescriptor) -> Vec<(String, String)> {
+    let browser = descriptor.browser.unwrap_or(Browser::Chrome);
+    let platform = descriptor.platform.unwrap_or(match browser {
+        Browser::Safari => Platform::Macos,
+        _ => Platform::Windows,
+    });
+
+    let headers: Vec<(&str, String)> = match browser {
+        Browser::Chrome | Browser::Edge => {
+            let edge = browser == Browser::Edge;
+            let version = major(descriptor.version.as_deref().unwrap_or(if edge {
+                EDGE_VERSION
+            } else {
+                CHROME_VERSION
+            }));
+            let brand = if edge {
+                "Microsoft Edge"
+            } else {
+                "Google Chrome"
+            };
+            let mut user_agent = format!(
+                "Mozilla/5.0 ({}) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+                chromium_os(platform),
+                version
+            );
+            if edge {
+                user_agent.push_str(&format!(" Edg/{}.0.0.0", version));
+            }
+            vec![
+                (
+                    "sec-ch-ua",
+                    format!(
+                        "\"Chromium\";v=\"{0}\", \"{1}\";v=\"{0}\", \"Not-A.Brand\";v=\"99\"",
+                        version, brand
+                    ),
+                ),
+                ("sec-ch-ua-mobile", "?0".to_string()),
+                (
+                    "sec-ch-ua-platform",
+                    format!("\"{}\"", client_hint_platform(platform)),
+                ),
+                ("Upgrade-Insecure-Requests", "1".to_string()),
+                ("User-Agent", user_agent),
+                ("Accept", NAVIGATE_ACCEPT_CHROMIUM.to_string()),
+                ("Sec-Fetch-Site", "none".to_string()),
+                ("Sec-Fetch-Mode", "navigate".to_string()),
+                ("Sec-Fetch-User", "?1".to_string()),
+                ("Sec-Fetch-Dest", "document".to_string()),
+                ("Accept-Encoding", "gzip, deflate, br, zstd".to_string()),
+                ("Accept-Language", "en-US,en;q=0.9".to_string()),
+                ("Priority", "u=0, i".to_string()),
+            ]
+        }
+        Browser::Firefox | Browser::Tor => {
+            let tor = browser == Browser::Tor;
+            let version = major(descriptor.version.as_deref().unwrap_or(if tor {
+                TOR_FIREFOX_VERSION
+            } else {
+                FIREFOX_VERSION
+            }));
+            // Tor Browser reports Windows without the architecture, whatever the platform
+            let os = if tor {
+                "Windows NT 10.0".to_string()
+            } else {
+                gecko_os(platform)
+            };
+            vec![
+                (
+                    "User-Agent",
+                    format!(
+                        "Mozilla/5.0 ({}; rv:{}.0) Gecko/20100101 Firefox/{}.0",
+                        os, version, version
+                    ),
+                ),
+                ("Accept", NAVIGATE_ACCEPT.to_string()),
+                ("Accept-Language", "en-US,en;q=0.5".to_string()),
+                ("Accept-Encoding", "gzip, deflate, br, zstd".to_string()),
+                ("Upgrade-Insecure-Requests", "1".to_string()),
+                ("Sec-Fetch-Dest", "document".to_string()),
+                ("Sec-Fetch-Mode", "navigate".to_string()),
+                ("Sec-Fetch-Site", "none".to_string()),
+                ("Sec-Fetch-User", "?1".to_string()),
+                ("Priority", "u=0, i".to_string()),
+                ("TE", "trailers".to_string()),
+            ]
+        }
+        Browser::Safari => {
+            let version = descriptor
+                .version
+                .as_deref()
+                .unwrap_or(SAFARI_VERSION)
+                .replace('_', ".");
+            let version = if version.contains('.') {
+                version
+            } else {
+                format!("{}.0", version)
+            };
+            vec![
+                ("Accept", NAVIGATE_ACCEPT.to_string()),
+                ("Sec-Fetch-Site", "none".to_string()),
+                ("Sec-Fetch-Mode", "navigate".to_string()),
+                (
+                    "User-Agent",
+                    format!(
+                        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 \
+                         (KHTML, like Gecko) Version/{} Safari/605.1.15",
+                        version
+                    ),
+                ),
+                ("Accept-Language", "en-US,en;q=0.9".to_string()),
+                ("Sec-Fetch-Dest", "document".to_string()),
+                ("Accept-Encoding", "gzip, deflate, br".to_string()),
+            ]
+        }
+        Browser::OkHttp | Browser::Curl => Vec::new(),
+    };
+
+    headers
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+/// Major version of a target version (`133` for `133a`, `99` for `99.0`)
+fn major(version: &str) -> String {
+    version.chars().take_while(char::is_ascii_digit).collect()
+}
+
+fn chromium_os(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Macos => "Macintosh; Intel Mac OS X 10_15_7",
+        Platform::Linux => "X11; Linux x86_64",
+        Platform::Windows => "Windows NT 10.0; Win64; x64",
+    }
+}
+
+fn gecko_os(platform: Platform) -> String {
+    match platform {
+        Platform::Macos => "Macintosh; Intel Mac OS X 10.15".to_string(),
+        Platform::Linux => "X11; Linux x86_64".to_string(),
+        Platform::Windows => "Windows NT 10.0; Win64; x64".to_string(),
+    }
+}
+
+fn client_hint_platform(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Macos => "macOS",
+        Platform::Linux => "Linux",
+        Platform::Windows => "Windows",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(headers: &[(String, String)]) -> Vec<&str> {
+        headers.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_browser_headers() {
+        let chrome = browser_headers(&CuimpDescriptor {
+            browser: Some(Browser::Chrome),
+            version: Some("124".to_string()),
+            platform: Some(Platform::Macos),
+            ..Default::default()
+        });
+        assert_eq!(
+            names(&chrome)[..3],
+            ["sec-ch-ua", "sec-ch-ua-mobile", "sec-ch-ua-platform"]
+        );
+        assert_eq!(chrome[2].1, "\"macOS\"");
+        assert!(chrome[4].1.contains("Chrome/124.0.0.0") && chrome[4].1.contains("Mac OS X"));
+
+        let firefox = browser_headers(&CuimpDescriptor {
+            browser: Some(Browser::Firefox),
+            ..Default::default()
+        });
+        assert_eq!(names(&firefox)[0], "User-Agent");
+        assert!(firefox[0].1.ends_with("Firefox/135.0"));
+        assert_eq!(names(&firefox).last(), Some(&"TE"));
+
+        let safari = browser_headers(&CuimpDescriptor {
+            browser: Some(Browser::Safari),
+            version: Some("17_0".to_string()),
+            ..Default::default()
+        });
+        assert!(safari[3].1.contains("Version/17.0 Safari"));
+
+        let okhttp = CuimpDescriptor {
+            browser: Some(Browser::OkHttp),
+            ..Default::default()
+        };
+        assert!(browser_headers(&okhttp).is_empty());
+    }
+}
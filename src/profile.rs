@@ -0,0 +1,198 @@
+use crate::types::CuimpDescriptor;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Settings for requests to a domain and its subdomains
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DomainProfile {
+    /// Maximum requests per second
+    pub rate: Option<f64>,
+    /// Maximum requests in flight, across all clients sharing the `DomainProfiles`
+    pub concurrency: Option<usize>,
+    /// Headers added to requests; request headers take precedence
+    pub headers: HashMap<String, String>,
+    /// Browser to impersonate instead of the client's descriptor
+    pub fingerprint: Option<CuimpDescriptor>,
+    /// Proxy pools to rotate through when the request sets no proxy
+    pub proxy_labels: Vec<String>,
+}
+
+/// Registry of `DomainProfile`s consulted for every request URL
+///
+/// Clones share rate and concurrency state, so one registry can be handed to
+/// several clients (for example through `CuimpOptions`, which `Crawler` clones per slot).
+#[derive(Debug, Clone, Default)]
+pub struct DomainProfiles {
+    profiles: HashMap<String, DomainProfile>,
+    proxy_pools: HashMap<String, Vec<String>>,
+    state: Arc<Mutex<HashMap<String, DomainState>>>,
+}
+
+#[derive(Debug)]
+struct DomainState {
+    next_allowed: Instant,
+    permits: Option<Arc<Semaphore>>,
+    proxy_cursor: usize,
+}
+
+/// Held while a request to a throttled domain is in flight
+#[derive(Debug)]
+pub(crate) struct DomainPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl DomainProfiles {
+    pub fn new() -> Self {
+        DomainProfiles::default()
+    }
+
+    /// Add a profile for `domain` (`example.com` also covers `www.example.com`)
+    pub fn domain(mut self, domain: impl Into<String>, profile: DomainProfile) -> Self {
+        let domain = domain.into().trim_start_matches('.').to_ascii_lowercase();
+        self.profiles.insert(domain, profile);
+        self
+    }
+
+    /// Add a named proxy pool that profiles refer to in `proxy_labels`
+    pub fn proxy_pool(mut self, label: impl Into<String>, proxies: Vec<String>) -> Self {
+        self.proxy_pools.insert(label.into(), proxies);
+        self
+    }
+
+    /// The most specific profile for `host` and the domain it is registered under
+    pub fn profile_for(&self, host: &str) -> Option<(&str, &DomainProfile)> {
+        let host = host.to_ascii_lowercase();
+        self.profiles
+            .iter()
+            .filter(|(domain, _)| {
+                host == **domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            })
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(domain, profile)| (domain.as_str(), profile))
+    }
+
+    /// Next proxy from the profile's pools, round-robin per domain
+    pub(crate) fn next_proxy(&self, domain: &str, profile: &DomainProfile) -> Option<String> {
+        let proxies: Vec<&String> = profile
+            .proxy_labels
+            .iter()
+            .filter_map(|label| self.proxy_pools.get(label))
+            .flatten()
+            .collect();
+        if proxies.is_empty() {
+            return None;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = state
+            .entry(domain.to_string())
+            .or_insert_with(|| DomainState::new(profile));
+        let proxy = proxies
+            .get(entry.proxy_cursor % proxies.len())
+            .map(|p| p.to_string());
+        entry.proxy_cursor = entry.proxy_cursor.wrapping_add(1);
+        proxy
+    }
+
+    /// Wait for the domain's rate limit and a concurrency slot
+    pub(crate) async fn throttle(&self, host: &str) -> DomainPermit {
+        let Some((domain, profile)) = self.profile_for(host) else {
+            return DomainPermit { _permit: None };
+        };
+
+        let (start_at, permits) = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = state
+                .entry(domain.to_string())
+                .or_insert_with(|| DomainState::new(profile));
+            let now = Instant::now();
+            let start_at = entry.next_allowed.max(now);
+            if let Some(rate) = profile.rate.filter(|rate| *rate > 0.0) {
+                entry.next_allowed = start_at + Duration::from_secs_f64(1.0 / rate);
+            }
+            (start_at, entry.permits.clone())
+        };
+
+        let permit = match permits {
+            Some(permits) => permits.acquire_owned().await.ok(),
+            None => None,
+        };
+        tokio::time::sleep_until(start_at).await;
+        DomainPermit { _permit: permit }
+    }
+}
+
+impl DomainState {
+    fn new(profile: &DomainProfile) -> Self {
+        DomainState {
+            next_allowed: Instant::now(),
+            permits: profile
+                .concurrency
+                .map(|n| Arc::new(Semaphore::new(n.max(1)))),
+            proxy_cursor: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_profile_lookup_and_rate() {
+        let profiles = DomainProfiles::new()
+            .domain(
+                "example.com",
+                DomainProfile {
+                    rate: Some(20.0),
+                    ..Default::default()
+                },
+            )
+            .domain(
+                "api.example.com",
+                DomainProfile {
+                    proxy_labels: vec!["pool".to_string()],
+                    ..Default::default()
+                },
+            )
+            .proxy_pool(
+                "pool",
+                vec!["http://a:1".to_string(), "http://b:1".to_string()],
+            );
+
+        assert_eq!(
+            profiles.profile_for("www.EXAMPLE.com").unwrap().0,
+            "example.com"
+        );
+        assert_eq!(
+            profiles.profile_for("api.example.com").unwrap().0,
+            "api.example.com"
+        );
+        assert!(profiles.profile_for("notexample.com").is_none());
+
+        let (domain, profile) = profiles.profile_for("api.example.com").unwrap();
+        assert_eq!(
+            profiles.next_proxy(domain, profile).as_deref(),
+            Some("http://a:1")
+        );
+        assert_eq!(
+            profiles.next_proxy(domain, profile).as_deref(),
+            Some("http://b:1")
+        );
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            profiles.throttle("example.com").await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::error::{CuimpError, Result};
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use std::fmt;
+use url::form_urlencoded::byte_serialize;
+
+/// A serialized value in field order, which `serde_json::Value` does not keep
+enum Node {
+    Null,
+    Scalar(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a JSON value")
+            }
+
+            fn visit_unit<E>(self) -> std::result::Result<Node, E> {
+                Ok(Node::Null)
+            }
+
+            fn visit_bool<E>(self, value: bool) -> std::result::Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> std::result::Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> std::result::Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_f64<E>(self, value: f64) -> std::result::Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Node, E> {
+                Ok(Node::Scalar(value.to_string()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> std::result::Result<Node, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Node::Seq(items))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Node, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(Node::Map(fields))
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// Encode `value` as a query string the way serde_qs does: `name=Acme&address[city]=Paris&ids[0]=1`.
+///
+/// `value` is a struct or map, or a sequence of `(name, value)` pairs. Fields keep their
+/// order, nested maps and structs become `outer[inner]`, sequences `name[0]`, `name[1]`;
+/// `None` and `null` fields are left out. Names and values are form-encoded, brackets are not.
+pub(crate) fn to_query_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let node: Node = serde_json::from_str(&serde_json::to_string(value)?)?;
+    let fields = match node {
+        Node::Map(fields) => fields,
+        Node::Seq(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Node::Seq(pair) => match <[Node; 2]>::try_from(pair) {
+                    Ok([Node::Scalar(name), value]) => Ok((name, value)),
+                    _ => Err(not_a_query()),
+                },
+                _ => Err(not_a_query()),
+            })
+            .collect::<Result<_>>()?,
+        Node::Null => Vec::new(),
+        Node::Scalar(_) => return Err(not_a_query()),
+    };
+    let mut pairs = Vec::new();
+    for (name, value) in fields {
+        flatten(encode(&name), value, &mut pairs);
+    }
+    Ok(pairs.join("&"))
+}
+
+fn flatten(key: String, node: Node, pairs: &mut Vec<String>) {
+    match node {
+        Node::Null => {}
+        Node::Scalar(value) => pairs.push(format!("{}={}", key, encode(&value))),
+        Node::Seq(items) => {
+            for (index, item) in items.into_iter().enumerate() {
+                flatten(format!("{}[{}]", key, index), item, pairs);
+            }
+        }
+        Node::Map(fields) => {
+            for (name, value) in fields {
+                flatten(format!("{}[{}]", key, encode(&name)), value, pairs);
+            }
+        }
+    }
+}
+
+fn encode(text: &str) -> String {
+    byte_serialize(text.as_bytes()).collect()
+}
+
+fn not_a_query() -> CuimpError {
+    CuimpError::InvalidConfig(
+        "Query parameters must serialize to a map, a struct or a sequence of (name, value) pairs"
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Address {
+        city: String,
+        postcode: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Search {
+        name: String,
+        address: Address,
+        user_ids: Vec<u8>,
+        tags: BTreeMap<String, Vec<String>>,
+        archived: bool,
+        page: Option<u32>,
+    }
+
+    #[test]
+    fn test_to_query_string() {
+        let search = Search {
+            name: "Acme & Co".to_string(),
+            address: Address {
+                city: "Carrot City".to_string(),
+                postcode: 12345,
+            },
+            user_ids: vec![1, 2],
+            tags: BTreeMap::from([("kind[x]".to_string(), vec!["a=b".to_string()])]),
+            archived: false,
+            page: None,
+        };
+        assert_eq!(
+            to_query_string(&search).unwrap(),
+            "name=Acme+%26+Co&address[city]=Carrot+City&address[postcode]=12345&user_ids[0]=1&user_ids[1]=2\
+             &tags[kind%5Bx%5D][0]=a%3Db&archived=false"
+        );
+        assert_eq!(
+            to_query_string(&[("q", "rust lang"), ("page", "2")]).unwrap(),
+            "q=rust+lang&page=2"
+        );
+        assert_eq!(to_query_string(&None::<Search>).unwrap(), "");
+        assert!(matches!(
+            to_query_string("flat"),
+            Err(CuimpError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            to_query_string(&[1, 2]),
+            Err(CuimpError::InvalidConfig(_))
+        ));
+    }
+}
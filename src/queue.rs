@@ -0,0 +1,206 @@
+use crate::client::CuimpHttp;
+use crate::error::Result;
+use crate::types::{CuimpRequestConfig, CuimpResponse, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+
+/// A request submitted to a [`BulkExecutor`], serializable so backends can persist it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub method: Method,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub data: Option<Value>,
+    /// Failed attempts so far
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl QueuedJob {
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        QueuedJob {
+            method,
+            url: url.into(),
+            headers: HashMap::new(),
+            data: None,
+            attempts: 0,
+        }
+    }
+
+    /// Request configuration for this job
+    pub fn to_config(&self) -> CuimpRequestConfig {
+        CuimpRequestConfig {
+            url: Some(self.url.clone()),
+            method: Some(self.method),
+            headers: (!self.headers.is_empty()).then(|| self.headers.clone()),
+            data: self.data.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A job taken from a queue, identified by the backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueEntry {
+    pub id: String,
+    pub job: QueuedJob,
+}
+
+/// Storage for pending jobs.
+///
+/// Implement this over sled, Redis, SQS, ... to share a crawl between processes;
+/// [`MemoryQueue`] is the in-process default. A popped entry stays in flight until
+/// it is acknowledged with `ack` or handed back with `retry`.
+pub trait QueueBackend {
+    /// Enqueue a job and return its id
+    fn push(&mut self, job: QueuedJob) -> impl Future<Output = Result<String>> + Send;
+
+    /// Take the next job, or `None` when the queue is empty
+    fn pop(&mut self) -> impl Future<Output = Result<Option<QueueEntry>>> + Send;
+
+    /// Mark a job as finished
+    fn ack(&mut self, id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Return a failed job to the queue
+    fn retry(&mut self, entry: QueueEntry) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// In-memory FIFO queue
+#[derive(Debug, Default)]
+pub struct MemoryQueue {
+    pending: VecDeque<QueueEntry>,
+    in_flight: HashMap<String, QueuedJob>,
+    next_id: u64,
+}
+
+impl MemoryQueue {
+    pub fn new() -> Self {
+        MemoryQueue::default()
+    }
+
+    /// Number of jobs waiting to be popped
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl QueueBackend for MemoryQueue {
+    async fn push(&mut self, job: QueuedJob) -> Result<String> {
+        self.next_id += 1;
+        let id = self.next_id.to_string();
+        self.pending.push_back(QueueEntry {
+            id: id.clone(),
+            job,
+        });
+        Ok(id)
+    }
+
+    async fn pop(&mut self) -> Result<Option<QueueEntry>> {
+        let entry = self.pending.pop_front();
+        if let Some(entry) = &entry {
+            self.in_flight.insert(entry.id.clone(), entry.job.clone());
+        }
+        Ok(entry)
+    }
+
+    async fn ack(&mut self, id: &str) -> Result<()> {
+        self.in_flight.remove(id);
+        Ok(())
+    }
+
+    async fn retry(&mut self, entry: QueueEntry) -> Result<()> {
+        self.in_flight.remove(&entry.id);
+        self.pending.push_back(entry);
+        Ok(())
+    }
+}
+
+/// Runs queued jobs through a client until the backend is drained
+#[derive(Debug)]
+pub struct BulkExecutor<B: QueueBackend> {
+    backend: B,
+    /// Attempts per job before its error is reported (default: 3)
+    pub max_attempts: u32,
+}
+
+impl<B: QueueBackend> BulkExecutor<B> {
+    pub fn new(backend: B) -> Self {
+        BulkExecutor {
+            backend,
+            max_attempts: 3,
+        }
+    }
+
+    /// Submit a job and return its id
+    pub async fn submit(&mut self, job: QueuedJob) -> Result<String> {
+        self.backend.push(job).await
+    }
+
+    /// Process jobs until `pop` returns `None`, calling `handler` with each finished job.
+    ///
+    /// Failed jobs are re-queued until they reach `max_attempts`; only the final
+    /// error is passed to `handler`. Backend errors stop the run.
+    pub async fn run<F>(&mut self, client: &mut CuimpHttp, mut handler: F) -> Result<()>
+    where
+        F: FnMut(&QueueEntry, Result<CuimpResponse<Value>>),
+    {
+        while let Some(mut entry) = self.backend.pop().await? {
+            let result = client.request::<Value>(entry.job.to_config()).await;
+
+            if result.is_err() && entry.job.attempts + 1 < self.max_attempts {
+                entry.job.attempts += 1;
+                self.backend.retry(entry).await?;
+                continue;
+            }
+
+            handler(&entry, result);
+            self.backend.ack(&entry.id).await?;
+        }
+        Ok(())
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_queue_order_and_retry() {
+        let mut queue = MemoryQueue::new();
+        let first = queue
+            .push(QueuedJob::new(Method::GET, "https://a.example/"))
+            .await
+            .unwrap();
+        queue
+            .push(QueuedJob::new(Method::GET, "https://b.example/"))
+            .await
+            .unwrap();
+
+        let entry = queue.pop().await.unwrap().unwrap();
+        assert_eq!(entry.id, first);
+        queue.retry(entry).await.unwrap();
+
+        let next = queue.pop().await.unwrap().unwrap();
+        assert_eq!(next.job.url, "https://b.example/");
+        queue.ack(&next.id).await.unwrap();
+        assert_eq!(queue.pop().await.unwrap().unwrap().id, first);
+        assert!(queue.pop().await.unwrap().is_none());
+    }
+}
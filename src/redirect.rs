@@ -0,0 +1,346 @@
+use crate::constants::MAX_CUSTOM_REDIRECTS;
+use crate::cookie::CookiePolicy;
+use crate::error::{CuimpError, Result};
+use crate::types::{CuimpRequestConfig, CuimpResponse, Method};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+/// Decides whether to follow a redirect, for `RedirectPolicy::Custom`
+pub type RedirectCallback = Arc<dyn Fn(&RedirectAttempt) -> RedirectAction + Send + Sync>;
+
+/// How 3xx responses with a `Location` are followed
+#[derive(Clone)]
+pub enum RedirectPolicy {
+    /// Return redirect responses as they are
+    None,
+    /// Let curl follow up to this many redirects; the last redirect response is returned
+    /// beyond that. `Limited(0)` is the same as `None`.
+    Limited(u32),
+    /// Ask the callback for every redirect; each one followed is a request of its own.
+    /// The request fails with `CuimpError::RedirectRejected` after 20 redirects.
+    Custom(RedirectCallback),
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Limited(10)
+    }
+}
+
+impl RedirectPolicy {
+    /// `Custom` policy from a closure
+    pub fn custom<F>(decide: F) -> Self
+    where
+        F: Fn(&RedirectAttempt) -> RedirectAction + Send + Sync + 'static,
+    {
+        RedirectPolicy::Custom(Arc::new(decide))
+    }
+}
+
+impl fmt::Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectPolicy::None => f.write_str("None"),
+            RedirectPolicy::Limited(max) => f.debug_tuple("Limited").field(max).finish(),
+            RedirectPolicy::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// A redirect about to be followed, passed to a `RedirectPolicy::Custom` callback
+#[derive(Debug, Clone)]
+pub struct RedirectAttempt {
+    /// Status of the redirect response
+    pub status: u16,
+    /// Where it points, resolved against the request URL
+    pub url: Url,
+    /// URLs requested so far, the one that got this response last
+    pub previous: Vec<Url>,
+}
+
+/// What a `RedirectPolicy::Custom` callback decided
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedirectAction {
+    Follow,
+    /// Return the redirect response
+    Stop,
+    /// Fail the request with `CuimpError::RedirectRejected` and this reason
+    Reject(String),
+}
+
+/// Redirect settings of a request, with the client defaults filled in
+#[derive(Debug, Clone)]
+pub(crate) struct Redirects {
+    pub policy: RedirectPolicy,
+    pub strip_credentials: bool,
+    pub forbid_https_downgrade: bool,
+}
+
+impl Redirects {
+    /// Whether curl follows redirects itself, with `Limited`
+    pub(crate) fn curl_follows(&self) -> bool {
+        matches!(self.policy, RedirectPolicy::Limited(max) if max > 0)
+    }
+
+    /// curl arguments for a `Limited` policy
+    pub(crate) fn to_args(&self, url: &Url) -> Vec<String> {
+        let max = match self.policy {
+            RedirectPolicy::Limited(max) if max > 0 => max,
+            _ => return Vec::new(),
+        };
+        let mut args = vec![
+            "--location".to_string(),
+            "--max-redirs".to_string(),
+            max.to_string(),
+        ];
+        // curl drops `Authorization` and `Cookie` on redirects to another host unless trusted
+        if !self.strip_credentials {
+            args.push("--location-trusted".to_string());
+        }
+        if self.forbid_https_downgrade && url.scheme() == "https" {
+            args.push("--proto-redir".to_string());
+            args.push("=https".to_string());
+        }
+        args
+    }
+
+    /// The request following `response` to `url`, `None` to return the response.
+    ///
+    /// `Limited` redirects are followed by curl, so a redirect response left over is only
+    /// checked for a downgrade, which curl refused to follow.
+    pub(crate) fn follow<T>(
+        &self,
+        config: &CuimpRequestConfig,
+        url: &Url,
+        response: &CuimpResponse<T>,
+        previous: &[Url],
+    ) -> Result<Option<CuimpRequestConfig>> {
+        let next = match location(url, response) {
+            Some(next)
+                if self.curl_follows() || matches!(self.policy, RedirectPolicy::Custom(_)) =>
+            {
+                next
+            }
+            _ => return Ok(None),
+        };
+        if self.forbid_https_downgrade && url.scheme() == "https" && next.scheme() == "http" {
+            return Err(CuimpError::RedirectRejected(format!(
+                "{} redirects to {}: https downgraded to http",
+                url, next
+            )));
+        }
+        let decide = match &self.policy {
+            RedirectPolicy::Custom(decide) => decide,
+            _ => return Ok(None),
+        };
+        if previous.len() >= MAX_CUSTOM_REDIRECTS {
+            return Err(CuimpError::RedirectRejected(format!(
+                "{}: more than {} redirects",
+                next, MAX_CUSTOM_REDIRECTS
+            )));
+        }
+        let attempt = RedirectAttempt {
+            status: response.status,
+            url: next,
+            previous: previous.iter().chain([url]).cloned().collect(),
+        };
+        match decide(&attempt) {
+            RedirectAction::Follow => Ok(Some(self.redirected(
+                config,
+                url,
+                attempt.url,
+                response.status,
+            ))),
+            RedirectAction::Stop => Ok(None),
+            RedirectAction::Reject(reason) => Err(CuimpError::RedirectRejected(format!(
+                "{}: {}",
+                attempt.url, reason
+            ))),
+        }
+    }
+
+    /// `config` sent to `next` as a browser would after a `status` redirect from `url`
+    fn redirected(
+        &self,
+        config: &CuimpRequestConfig,
+        url: &Url,
+        next: Url,
+        status: u16,
+    ) -> CuimpRequestConfig {
+        let mut config = config.clone();
+        let cross_origin = url.origin() != next.origin();
+        config.url = Some(next.to_string());
+        config.base_url = None;
+        // The query is in the URL already
+        config.params = Some(HashMap::new());
+        config.raw_query = None;
+        if cross_origin {
+            config.host_override = None;
+        }
+
+        // 303 turns everything but HEAD into a GET, and so do 301 and 302 a POST
+        let method = config.method.unwrap_or(Method::GET);
+        if (status == 303 && method != Method::HEAD)
+            || (matches!(status, 301 | 302) && method == Method::POST)
+        {
+            config.method = Some(Method::GET);
+            config.data = None;
+            config.body = None;
+            drop_headers(&mut config, &["content-type"]);
+        }
+
+        if cross_origin && self.strip_credentials {
+            drop_headers(&mut config, &["authorization", "cookie"]);
+            let removed = config.remove_headers.get_or_insert_with(Vec::new);
+            removed.extend(["Authorization".to_string(), "Cookie".to_string()]);
+            // The jar matches cookies to the new URL by itself
+            if matches!(config.cookies, Some(CookiePolicy::Only(_))) {
+                config.cookies = Some(CookiePolicy::None);
+            }
+        }
+        config
+    }
+}
+
+/// Target of a redirect response, resolved against `url`
+fn location<T>(url: &Url, response: &CuimpResponse<T>) -> Option<Url> {
+    if !matches!(response.status, 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+    response
+        .header("location")
+        .and_then(|location| url.join(location).ok())
+}
+
+fn drop_headers(config: &mut CuimpRequestConfig, names: &[&str]) {
+    if let Some(headers) = config.headers.as_mut() {
+        headers.retain(|name, _| !names.iter().any(|n| name.eq_ignore_ascii_case(n)));
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::fake_curl::FakeCurl;
+    use crate::types::CuimpOptions;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_redirect_policy() {
+        let curl = FakeCurl::new(
+            "redirect",
+            "#!/bin/sh\ncase \"$*\" in\n\
+             *portal.example.com/login*) printf 'HTTP/1.1 302 Found\\r\\nLocation: https://sso.example.com/next?step=2\\r\\n\\r\\n' ;;\n\
+             *portal.example.com/old*) printf 'HTTP/1.1 301 Moved Permanently\\r\\nLocation: http://portal.example.com/new\\r\\n\\r\\n' ;;\n\
+             *) printf 'HTTP/1.1 200 OK\\r\\n\\r\\n%s' \"$*\" ;;\nesac\n",
+        );
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        let mut client = curl.client(CuimpOptions {
+            redirect: Some(RedirectPolicy::custom(move |attempt| {
+                log.lock().unwrap().push(attempt.clone());
+                match attempt.url.host_str() {
+                    Some("sso.example.com") => RedirectAction::Follow,
+                    _ => RedirectAction::Reject("unknown host".to_string()),
+                }
+            })),
+            ..Default::default()
+        });
+        let login = CuimpRequestConfig {
+            url: Some("https://portal.example.com/login".to_string()),
+            method: Some(Method::POST),
+            headers: Some(HashMap::from([(
+                "Authorization".to_string(),
+                "Bearer secret".to_string(),
+            )])),
+            data: Some(serde_json::json!({"user": "alice"})),
+            ..Default::default()
+        };
+        let followed = client.request_raw(login.clone()).await.unwrap();
+        let batch = client
+            .run_all::<serde_json::Value>(vec![login.clone()], 2)
+            .await;
+        let stopped = client
+            .request_raw(CuimpRequestConfig {
+                redirect: Some(RedirectPolicy::custom(|_| RedirectAction::Stop)),
+                ..login
+            })
+            .await
+            .unwrap();
+        let rejected = client
+            .get::<serde_json::Value>("https://portal.example.com/old")
+            .await;
+
+        let mut client = curl.client(CuimpOptions {
+            forbid_https_downgrade: Some(true),
+            ..Default::default()
+        });
+        let downgraded = client
+            .get::<serde_json::Value>("https://portal.example.com/old")
+            .await;
+        let limited = client
+            .get::<serde_json::Value>("https://portal.example.com/home")
+            .await
+            .unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            (seen[0].status, seen[0].url.as_str()),
+            (302, "https://sso.example.com/next?step=2")
+        );
+        assert_eq!(
+            seen[0].previous,
+            vec![Url::parse("https://portal.example.com/login").unwrap()]
+        );
+        assert_eq!((followed.status, followed.attempts.len()), (200, 2));
+        assert_eq!(followed.request.method, "GET");
+        let sent = String::from_utf8(followed.data).unwrap();
+        assert!(
+            !sent.contains("secret") && !sent.contains("alice") && !sent.contains("--location")
+        );
+        assert!(sent.starts_with("-H Authorization: -H Cookie: -i "));
+        assert_eq!(
+            followed.request.url.as_str(),
+            "https://sso.example.com/next?step=2"
+        );
+        assert_eq!((stopped.status, stopped.attempts.len()), (302, 1));
+        assert!(
+            matches!(rejected, Err(CuimpError::RedirectRejected(reason)) if reason == "http://portal.example.com/new: unknown host")
+        );
+        let batched = batch.into_iter().next().unwrap().unwrap();
+        assert_eq!((batched.status, batched.attempts.len()), (200, 2));
+        assert_eq!(
+            batched.request.url.as_str(),
+            "https://sso.example.com/next?step=2"
+        );
+
+        assert!(matches!(downgraded, Err(CuimpError::RedirectRejected(_))));
+        assert!(limited
+            .request
+            .command
+            .contains(" --location --max-redirs 10 --proto-redir =https "));
+    }
+
+    #[tokio::test]
+    async fn test_custom_redirect_limit() {
+        let curl = FakeCurl::new(
+            "redirect-loop",
+            "#!/bin/sh\nprintf 'HTTP/1.1 302 Found\\r\\nLocation: /loop\\r\\n\\r\\n'\n",
+        );
+        let mut client = curl.client(CuimpOptions {
+            redirect: Some(RedirectPolicy::custom(|_| RedirectAction::Follow)),
+            ..Default::default()
+        });
+
+        let looped = client
+            .get::<serde_json::Value>("https://example.com/loop")
+            .await;
+        assert!(
+            matches!(looped, Err(CuimpError::RedirectRejected(reason)) if reason == "https://example.com/loop: more than 20 redirects")
+        );
+    }
+}
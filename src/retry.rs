@@ -0,0 +1,261 @@
+use crate::connection::ConnectionInfo;
+use crate::error::CuimpError;
+use crate::types::Diagnostics;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Retry policy for failed requests
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Status codes that trigger a retry
+    pub retry_on_status: Vec<u16>,
+    /// Retry on transport failures (timeouts, connection errors, empty output)
+    pub retry_on_error: bool,
+    /// Delay before the first retry; doubled for every subsequent one
+    pub base_delay_ms: u64,
+    /// Upper bound for the backoff delay
+    pub max_delay_ms: u64,
+    /// Wait as long as the `Retry-After` of a 429 or 503 response asks, up to this many
+    /// milliseconds, instead of the backoff delay; a longer wait ends the retries and the
+    /// response is returned. `None` ignores `Retry-After`.
+    pub max_retry_after_ms: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            retry_on_status: vec![429, 500, 502, 503, 504],
+            retry_on_error: true,
+            base_delay_ms: 500,
+            max_delay_ms: 10_000,
+            max_retry_after_ms: Some(60_000),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff delay before retry number `retry` (0-based)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u64.checked_shl(retry).unwrap_or(u64::MAX);
+        Duration::from_millis(
+            self.base_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_delay_ms),
+        )
+    }
+
+    /// Delay before retry number `retry` after a response, from its `Retry-After` header
+    /// on 429 and 503; `None` when the server asks to wait longer than `max_retry_after_ms`
+    pub(crate) fn response_delay(
+        &self,
+        retry: u32,
+        status: u16,
+        headers: &HashMap<String, String>,
+    ) -> Option<Duration> {
+        let retry_after = match self.max_retry_after_ms {
+            Some(max) if matches!(status, 429 | 503) => headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+                .and_then(|(_, value)| parse_retry_after(value, SystemTime::now()))
+                .map(|wait| (wait, Duration::from_millis(max))),
+            _ => None,
+        };
+        match retry_after {
+            Some((wait, max)) if wait > max => None,
+            Some((wait, _)) => Some(wait),
+            None => Some(self.delay(retry)),
+        }
+    }
+
+    pub(crate) fn should_retry_status(&self, status: u16) -> bool {
+        self.retry_on_status.contains(&status)
+    }
+
+    pub(crate) fn should_retry_error(&self, error: &CuimpError) -> bool {
+        self.retry_on_error
+            && matches!(
+                error,
+                CuimpError::Timeout(_)
+                    | CuimpError::RequestFailed(_)
+                    | CuimpError::InvalidResponse(_)
+            )
+    }
+}
+
+/// Parse a `Retry-After` value: delay in seconds or an HTTP date (a past date means now)
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Connection-level retries performed by curl itself (`--retry` and friends)
+///
+/// curl only retries transient failures (timeouts, connection errors and
+/// 408/429/5xx responses) within a single process. Requests that combine it
+/// with a [`RetryPolicy`] allowing retries are rejected; the two layers would multiply attempts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CurlRetry {
+    /// `--retry`: number of retries
+    pub retries: u32,
+    /// `--retry-delay`: fixed delay between retries in seconds (curl backs off exponentially when unset)
+    pub delay_secs: Option<u64>,
+    /// `--retry-max-time`: stop retrying after this many seconds.
+    /// Derived from the request timeout when unset, so retries never outlive it.
+    pub max_time_secs: Option<u64>,
+    /// `--retry-connrefused`: also retry on refused connections
+    pub connrefused: bool,
+    /// `--retry-all-errors`: retry on any error
+    pub all_errors: bool,
+}
+
+impl CurlRetry {
+    /// Build the curl arguments for a request with an optional timeout
+    pub(crate) fn to_args(&self, timeout_ms: Option<u64>) -> Vec<String> {
+        let mut args = vec!["--retry".to_string(), self.retries.to_string()];
+        if let Some(delay) = self.delay_secs {
+            args.push("--retry-delay".to_string());
+            args.push(delay.to_string());
+        }
+        let max_time = self
+            .max_time_secs
+            .or_else(|| timeout_ms.map(|ms| ms.div_ceil(1000).max(1)));
+        if let Some(max_time) = max_time {
+            args.push("--retry-max-time".to_string());
+            args.push(max_time.to_string());
+        }
+        if self.connrefused {
+            args.push("--retry-connrefused".to_string());
+        }
+        if self.all_errors {
+            args.push("--retry-all-errors".to_string());
+        }
+        args
+    }
+}
+
+/// Result of a single attempt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttemptOutcome {
+    Status(u16),
+    Error(String),
+}
+
+/// Information about one attempt of a (possibly retried) request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptInfo {
+    pub outcome: AttemptOutcome,
+    pub duration: Duration,
+    /// Proxy used for this attempt, with its password redacted
+    pub proxy: Option<String>,
+    /// Impersonation target used for this attempt (e.g. `chrome 123`)
+    pub fingerprint: Option<String>,
+    /// Exit code of curl (`CURLcode` with `Backend::Ffi`); `None` when curl did not run
+    /// to completion or the response was replayed
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Errors and warnings curl wrote to stderr, without the progress meter
+    #[serde(default)]
+    pub stderr: String,
+    /// curl's verbose trace of this attempt when `CuimpRequestConfig::debug` is set
+    #[serde(default)]
+    pub debug_info: Option<String>,
+    /// Connection details of this attempt, from curl's `--write-out`
+    #[serde(default)]
+    pub connection: Option<ConnectionInfo>,
+    /// The transfer timed out after the response head and the body was cut short
+    /// (`CuimpRequestConfig::allow_partial`)
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+impl AttemptInfo {
+    /// Exit code and stderr when curl exited with an error or wrote to stderr
+    pub(crate) fn diagnostics(&self) -> Option<Diagnostics> {
+        (self.exit_code.is_some_and(|code| code != 0) || !self.stderr.is_empty()).then(|| {
+            Diagnostics {
+                exit_code: self.exit_code,
+                stderr: self.stderr.clone(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curl_retry_deadline_from_timeout() {
+        let retry = CurlRetry {
+            retries: 2,
+            connrefused: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            retry.to_args(Some(2500)),
+            vec![
+                "--retry",
+                "2",
+                "--retry-max-time",
+                "3",
+                "--retry-connrefused"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_millis(2000));
+        assert_eq!(policy.delay(10), Duration::from_millis(10_000));
+        assert_eq!(policy.delay(200), Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        let policy = RetryPolicy::default();
+        let headers = HashMap::from([("Retry-After".to_string(), "3".to_string())]);
+        assert_eq!(
+            policy.response_delay(0, 429, &headers),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.response_delay(0, 502, &headers),
+            Some(Duration::from_millis(500))
+        );
+        let headers = HashMap::from([("retry-after".to_string(), "3600".to_string())]);
+        assert_eq!(policy.response_delay(0, 503, &headers), None);
+        let ignoring = RetryPolicy {
+            max_retry_after_ms: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            ignoring.response_delay(1, 503, &headers),
+            Some(Duration::from_millis(1000))
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::challenge::Blocked;
+use crate::types::CuimpDescriptor;
+use std::fmt;
+use std::sync::Arc;
+
+/// Observer called before every rotation
+pub type RotationCallback = Arc<dyn Fn(&Rotation) + Send + Sync>;
+
+/// Retry blocked requests with another impersonation target and/or proxy.
+///
+/// When a response is recognized as a block page (see `ChallengeDetector`), the request is
+/// sent again right away with the next entry of `fingerprints` and the next of `proxies`,
+/// cycling through them, up to `max_rotations` times. Empty pools leave the target or proxy
+/// as configured. Rotations are counted separately from `RetryPolicy` retries.
+#[derive(Clone)]
+pub struct RotationPolicy {
+    /// Impersonation targets to switch to; a domain profile's own fingerprint still wins
+    pub fingerprints: Vec<CuimpDescriptor>,
+    /// Proxies to switch to, replacing the request's proxy
+    pub proxies: Vec<String>,
+    /// Rotations per request (default: 3)
+    pub max_rotations: u32,
+    pub on_rotate: Option<RotationCallback>,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        RotationPolicy {
+            fingerprints: Vec::new(),
+            proxies: Vec::new(),
+            max_rotations: 3,
+            on_rotate: None,
+        }
+    }
+}
+
+impl fmt::Debug for RotationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RotationPolicy")
+            .field("fingerprints", &self.fingerprints)
+            .field("proxies", &self.proxies)
+            .field("max_rotations", &self.max_rotations)
+            .field("on_rotate", &self.on_rotate.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// A rotation about to happen, passed to `RotationPolicy::on_rotate`
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    pub url: String,
+    /// The block that caused it
+    pub blocked: Blocked,
+    /// Rotations of this request so far, from 1
+    pub rotation: u32,
+    /// Impersonation target of the next attempt (e.g. `firefox 133`)
+    pub fingerprint: Option<String>,
+    /// Proxy of the next attempt, when rotated
+    pub proxy: Option<String>,
+}
+
+impl RotationPolicy {
+    /// Call `callback` before every rotation
+    pub fn on_rotate<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Rotation) + Send + Sync + 'static,
+    {
+        self.on_rotate = Some(Arc::new(callback));
+        self
+    }
+
+    /// Index into `fingerprints` for rotation number `rotation`; `None` before the first
+    pub(crate) fn fingerprint_index(&self, rotation: u32) -> Option<usize> {
+        pool_index(self.fingerprints.len(), rotation)
+    }
+
+    /// Proxy for rotation number `rotation`; `None` before the first
+    pub(crate) fn proxy(&self, rotation: u32) -> Option<&String> {
+        pool_index(self.proxies.len(), rotation).and_then(|index| self.proxies.get(index))
+    }
+}
+
+fn pool_index(len: usize, rotation: u32) -> Option<usize> {
+    let rotation = usize::try_from(rotation).ok()?.checked_sub(1)?;
+    (len > 0).then(|| rotation % len)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_pools() {
+        let policy = RotationPolicy {
+            proxies: vec!["http://a:8080".to_string(), "http://b:8080".to_string()],
+            ..Default::default()
+        };
+        let proxies: Vec<_> = (0..4)
+            .map(|rotation| policy.proxy(rotation).cloned())
+            .collect();
+        assert_eq!(
+            proxies,
+            [
+                None,
+                Some("http://a:8080".to_string()),
+                Some("http://b:8080".to_string()),
+                Some("http://a:8080".to_string())
+            ]
+        );
+        assert_eq!(policy.fingerprint_index(2), None);
+    }
+}
@@ -1,6 +1,9 @@
 use crate::error::{CuimpError, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::io::AsyncReadExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 
@@ -11,18 +14,133 @@ pub struct RunResult {
     pub stderr: Vec<u8>,
 }
 
+/// Spawn the binary with piped stdout/stderr without waiting for it.
+///
+/// The child and everything it spawned are killed if the returned handles are
+/// dropped before it exits.
+#[cfg(feature = "stream")]
+pub(crate) fn spawn_binary(
+    bin_path: &str,
+    args: &[String],
+    input: Option<Vec<u8>>,
+) -> Result<(tokio::process::Child, ProcessTree)> {
+    let mut child = command(bin_path, args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .spawn()
+        .map_err(|e| CuimpError::RequestFailed(format!("Failed to spawn process: {}", e)))?;
+    let tree = ProcessTree::new(&child);
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        tokio::spawn(async move {
+            let _ = stdin.write_all(&input).await;
+        });
+    }
+    Ok((child, tree))
+}
+
+/// A request body in a temporary file only the current user can read, passed to curl
+/// as `--data-binary @<path>` instead of in argv. The file is removed on drop.
+#[derive(Debug)]
+pub(crate) struct TempBody {
+    path: PathBuf,
+}
+
+impl TempBody {
+    pub(crate) fn create(contents: &[u8]) -> Result<Self> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let path = std::env::temp_dir().join(format!(
+            "cuimp-body-{}-{}-{:08x}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            nanos
+        ));
+
+        // `create_new` refuses existing files and symlinks planted at the path
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        let body = TempBody { path };
+        file.write_all(contents)?;
+        Ok(body)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempBody {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
 pub async fn run_binary(
     bin_path: &str,
     args: &[String],
     timeout_ms: Option<u64>,
 ) -> Result<RunResult> {
-    let mut child = Command::new(bin_path)
-        .args(args)
+    run_binary_with_input(bin_path, args, None, timeout_ms).await
+}
+
+/// Like [`run_binary`], writing `input` to the process's stdin (e.g. for `--data-binary @-`)
+///
+/// On timeout, or when the returned future is dropped, the process and everything it
+/// spawned (e.g. curl started by a wrapper script) are killed.
+pub(crate) async fn run_binary_with_input(
+    bin_path: &str,
+    args: &[String],
+    input: Option<&[u8]>,
+    timeout_ms: Option<u64>,
+) -> Result<RunResult> {
+    match run_binary_until(bin_path, args, input, timeout_ms).await? {
+        (_, true) => Err(CuimpError::Timeout(format!(
+            "Request timed out after {} ms",
+            timeout_ms.unwrap_or_default()
+        ))),
+        (result, false) => Ok(result),
+    }
+}
+
+/// Like [`run_binary_with_input`], returning what the process wrote before `timeout_ms`
+/// passed instead of failing; the flag tells whether it did, and the exit code is then `None`
+pub(crate) async fn run_binary_until(
+    bin_path: &str,
+    args: &[String],
+    input: Option<&[u8]>,
+    timeout_ms: Option<u64>,
+) -> Result<(RunResult, bool)> {
+    let mut child = command(bin_path, args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .stdin(Stdio::null())
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
         .spawn()
         .map_err(|e| CuimpError::RequestFailed(format!("Failed to spawn process: {}", e)))?;
+    let mut tree = ProcessTree::new(&child);
+
+    // Dropping stdin after writing closes it so curl sees EOF
+    let stdin = child.stdin.take();
+    let write_input = async move {
+        if let (Some(input), Some(mut stdin)) = (input, stdin) {
+            // A process that exits without reading its input is reported through its output
+            let _ = stdin.write_all(input).await;
+        }
+    };
 
     let stdout_handle = child.stdout.take().ok_or_else(|| {
         CuimpError::RequestFailed("Failed to capture stdout".to_string())
@@ -32,55 +150,206 @@ pub async fn run_binary(
         CuimpError::RequestFailed("Failed to capture stderr".to_string())
     })?;
 
-    // Read stdout and stderr concurrently
-    let (stdout_result, stderr_result) = tokio::join!(
-        read_stream(stdout_handle),
-        read_stream(stderr_handle)
-    );
-
-    let stdout = stdout_result?;
-    let stderr = stderr_result?;
-
-    // Wait for the child process with optional timeout
-    let status = if let Some(timeout_ms) = timeout_ms {
-        match timeout(Duration::from_millis(timeout_ms), child.wait()).await {
-            Ok(Ok(status)) => status,
-            Ok(Err(e)) => {
-                return Err(CuimpError::RequestFailed(format!(
-                    "Process error: {}",
-                    e
-                )))
-            }
+    // Feed stdin, read stdout and stderr concurrently, then reap the child; the output
+    // lives outside the future so what was read survives a timeout
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let run = async {
+        let ((), stdout, stderr) = tokio::join!(
+            write_input,
+            read_stream(stdout_handle, &mut stdout),
+            read_stream(stderr_handle, &mut stderr)
+        );
+        (stdout, stderr, child.wait().await)
+    };
+
+    // The timeout covers reading the output too: a hung process may never close it
+    let (read_stdout, read_stderr, status) = match timeout_ms {
+        Some(timeout_ms) => match timeout(Duration::from_millis(timeout_ms), run).await {
+            Ok(result) => result,
             Err(_) => {
-                // Timeout occurred, kill the process
+                tree.kill();
                 let _ = child.kill().await;
-                return Err(CuimpError::Timeout(format!(
-                    "Request timed out after {} ms",
-                    timeout_ms
-                )));
+                let result = RunResult {
+                    exit_code: None,
+                    stdout,
+                    stderr,
+                };
+                return Ok((result, true));
             }
-        }
-    } else {
-        child
-            .wait()
-            .await
-            .map_err(|e| CuimpError::RequestFailed(format!("Process wait error: {}", e)))?
+        },
+        None => run.await,
     };
+    // The group id may be reused once the child is reaped
+    tree.disarm();
 
-    Ok(RunResult {
+    let status =
+        status.map_err(|e| CuimpError::RequestFailed(format!("Process wait error: {}", e)))?;
+    read_stdout?;
+    read_stderr?;
+    let result = RunResult {
         exit_code: status.code(),
         stdout,
         stderr,
-    })
+    };
+    Ok((result, false))
+}
+
+/// Command for the binary, started in its own process group on Unix so the whole
+/// tree can be killed
+fn command(bin_path: &str, args: &[String]) -> Command {
+    let mut command = Command::new(bin_path);
+    command.args(args).kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+    command
+}
+
+/// Every process started by a child: its process group on Unix, a job object on Windows.
+///
+/// The tree is killed when dropped unless `disarm` was called after the child exited,
+/// so nested processes of wrapper scripts are not orphaned by timeouts or cancellation.
+#[derive(Debug)]
+pub(crate) struct ProcessTree {
+    #[cfg(unix)]
+    pgid: Option<libc::pid_t>,
+    #[cfg(windows)]
+    job: windows_sys::Win32::Foundation::HANDLE,
+    armed: bool,
+}
+
+// The job handle is only used to terminate and close the job
+#[cfg(windows)]
+unsafe impl Send for ProcessTree {}
+#[cfg(windows)]
+unsafe impl Sync for ProcessTree {}
+
+impl ProcessTree {
+    #[cfg(unix)]
+    fn new(child: &tokio::process::Child) -> Self {
+        ProcessTree {
+            pgid: child.id().and_then(|pid| libc::pid_t::try_from(pid).ok()),
+            armed: true,
+        }
+    }
+
+    /// Processes the child spawns before it is assigned to the job escape it
+    #[cfg(windows)]
+    fn new(child: &tokio::process::Child) -> Self {
+        use windows_sys::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+
+        // SAFETY: an anonymous job object without security attributes, and the child's live process handle
+        let job = unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if !job.is_null() {
+                if let Some(process) = child.raw_handle() {
+                    AssignProcessToJobObject(job, process as _);
+                }
+            }
+            job
+        };
+        ProcessTree { job, armed: true }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn new(_child: &tokio::process::Child) -> Self {
+        ProcessTree { armed: true }
+    }
+
+    /// Kill every process of the tree
+    pub(crate) fn kill(&mut self) {
+        if !std::mem::take(&mut self.armed) {
+            return;
+        }
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            // SAFETY: signals the group created for the child, which is not reaped while armed
+            unsafe {
+                libc::killpg(pgid, libc::SIGKILL);
+            }
+        }
+        #[cfg(windows)]
+        if !self.job.is_null() {
+            // SAFETY: the job handle is owned by this tree and open until drop
+            unsafe {
+                windows_sys::Win32::System::JobObjects::TerminateJobObject(self.job, 1);
+            }
+        }
+    }
+
+    /// Keep the remaining processes running when the tree is dropped
+    pub(crate) fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        self.kill();
+        #[cfg(windows)]
+        if !self.job.is_null() {
+            // SAFETY: the job handle is owned by this tree and closed only here
+            unsafe {
+                windows_sys::Win32::Foundation::CloseHandle(self.job);
+            }
+        }
+    }
 }
 
 async fn read_stream<R: tokio::io::AsyncRead + Unpin>(
     mut stream: R,
-) -> Result<Vec<u8>> {
-    let mut buffer = Vec::new();
+    buffer: &mut Vec<u8>,
+) -> Result<()> {
     stream
-        .read_to_end(&mut buffer)
+        .read_to_end(buffer)
         .await
-        .map_err(|e| CuimpError::IoError(e))?;
-    Ok(buffer)
+        .map_err(CuimpError::IoError)?;
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_binary_with_input() {
+        let input = [0u8, 159, 146, 150, b'\n'];
+        let result = run_binary_with_input("cat", &[], Some(&input), Some(5000))
+            .await
+            .unwrap();
+        assert_eq!(result.stdout, input);
+    }
+
+    #[tokio::test]
+    async fn test_output_kept_on_timeout() {
+        let args = ["-c".to_string(), "printf partial; sleep 5".to_string()];
+        let (result, timed_out) = run_binary_until("sh", &args, None, Some(300))
+            .await
+            .unwrap();
+        assert!(timed_out);
+        assert_eq!(
+            (result.stdout.as_slice(), result.exit_code),
+            (&b"partial"[..], None)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_kills_nested_processes() {
+        let pid_file = std::env::temp_dir().join(format!("cuimp-tree-{}", std::process::id()));
+        // The nested sleep keeps stdout open, like curl started by a wrapper script
+        let script = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+        let args = ["-c".to_string(), script];
+        let result = run_binary_with_input("sh", &args, None, Some(300)).await;
+        assert!(matches!(result, Err(CuimpError::Timeout(_))));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        std::fs::remove_file(&pid_file).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Gone, or a zombie waiting to be reaped by init
+        let stat =
+            std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+        assert!(stat.is_empty() || stat.contains(") Z "), "{}", stat);
+    }
 }
@@ -0,0 +1,264 @@
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::parallel::send_shared;
+use crate::types::{CuimpRequestConfig, CuimpResponse};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::task::{Context, Poll};
+use tokio::sync::{oneshot, Mutex};
+use url::Url;
+
+/// Limits of a `RequestQueue`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestQueueOptions {
+    /// Requests in flight across all hosts (default: 8)
+    pub concurrency: usize,
+    /// Requests in flight per host (default: 2)
+    pub per_host: usize,
+    /// Per-host limits overriding `per_host`, keyed by lowercase host
+    pub host_limits: HashMap<String, usize>,
+}
+
+impl Default for RequestQueueOptions {
+    fn default() -> Self {
+        RequestQueueOptions {
+            concurrency: 8,
+            per_host: 2,
+            host_limits: HashMap::new(),
+        }
+    }
+}
+
+/// Priority scheduler running requests through a shared client.
+///
+/// Submitted requests wait until both the global and their host's concurrency allow
+/// them, then run on background tasks; among waiting requests the highest priority
+/// goes first, and requests of equal priority run in submission order. A request whose
+/// handle is dropped before it starts is skipped. Must be used within a Tokio runtime.
+#[derive(Debug, Clone)]
+pub struct RequestQueue {
+    client: Arc<Mutex<CuimpHttp>>,
+    state: Arc<StdMutex<SchedulerState>>,
+}
+
+/// Future resolving to the response of a queued request
+#[derive(Debug)]
+pub struct RequestHandle {
+    receiver: oneshot::Receiver<Result<CuimpResponse<Vec<u8>>>>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    options: RequestQueueOptions,
+    /// Waiting requests, highest priority first, then by submission order
+    pending: BTreeMap<(Reverse<i32>, u64), Job>,
+    next_seq: u64,
+    running: usize,
+    running_per_host: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+struct Job {
+    host: String,
+    config: CuimpRequestConfig,
+    sender: oneshot::Sender<Result<CuimpResponse<Vec<u8>>>>,
+}
+
+impl RequestQueue {
+    pub fn new(client: CuimpHttp, options: RequestQueueOptions) -> Self {
+        RequestQueue {
+            client: Arc::new(Mutex::new(client)),
+            state: Arc::new(StdMutex::new(SchedulerState {
+                options,
+                pending: BTreeMap::new(),
+                next_seq: 0,
+                running: 0,
+                running_per_host: HashMap::new(),
+            })),
+        }
+    }
+
+    /// The shared client, e.g. to read its cookie jar between requests
+    pub fn client(&self) -> &Arc<Mutex<CuimpHttp>> {
+        &self.client
+    }
+
+    /// Queue a request; higher `priority` runs first
+    pub fn submit(&self, config: CuimpRequestConfig, priority: i32) -> RequestHandle {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut state = self.lock();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            let host = request_host(&config);
+            state.pending.insert(
+                (Reverse(priority), seq),
+                Job {
+                    host,
+                    config,
+                    sender,
+                },
+            );
+        }
+        self.dispatch();
+        RequestHandle { receiver }
+    }
+
+    /// Number of requests waiting to start
+    pub fn pending(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Number of requests running
+    pub fn running(&self) -> usize {
+        self.lock().running
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start every waiting request the limits allow
+    fn dispatch(&self) {
+        let mut state = self.lock();
+        state.pending.retain(|_, job| !job.sender.is_closed());
+        while state.running < state.options.concurrency.max(1) {
+            let Some(key) = state
+                .pending
+                .iter()
+                .find(|(_, job)| state.has_capacity(&job.host))
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            let Some(job) = state.pending.remove(&key) else {
+                break;
+            };
+            state.running += 1;
+            *state.running_per_host.entry(job.host.clone()).or_default() += 1;
+
+            let queue = self.clone();
+            tokio::spawn(async move {
+                let result = send_shared(&queue.client, job.config).await;
+                let _ = job.sender.send(result);
+                queue.finish(&job.host);
+            });
+        }
+    }
+
+    /// Release the slots of a finished request and start the next ones
+    fn finish(&self, host: &str) {
+        {
+            let mut state = self.lock();
+            state.running = state.running.saturating_sub(1);
+            if let Some(running) = state.running_per_host.get_mut(host) {
+                *running = running.saturating_sub(1);
+                if *running == 0 {
+                    state.running_per_host.remove(host);
+                }
+            }
+        }
+        self.dispatch();
+    }
+}
+
+impl SchedulerState {
+    fn has_capacity(&self, host: &str) -> bool {
+        let limit = self
+            .options
+            .host_limits
+            .get(host)
+            .copied()
+            .unwrap_or(self.options.per_host);
+        self.running_per_host.get(host).copied().unwrap_or(0) < limit.max(1)
+    }
+}
+
+impl Future for RequestHandle {
+    type Output = Result<CuimpResponse<Vec<u8>>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver).poll(cx).map(|result| {
+            result.unwrap_or_else(|_| Err(CuimpError::Other("Request task failed".to_string())))
+        })
+    }
+}
+
+/// Lowercase host a request goes to, for per-host limits; empty when the URL is invalid
+/// (the request then fails when it runs)
+fn request_host(config: &CuimpRequestConfig) -> String {
+    let url = config.url.as_deref().unwrap_or_default();
+    let url = match config.base_url.as_deref().map(Url::parse) {
+        Some(Ok(base)) => base.join(url),
+        _ => Url::parse(url),
+    };
+    url.ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::fake_curl::FakeCurl;
+    use crate::types::CuimpOptions;
+
+    #[tokio::test]
+    async fn test_priority_and_host_limits() {
+        // Fake curl: logs each URL when it starts, and `/block` holds its slot for a while
+        let curl = FakeCurl::with_dir("scheduler", |dir| {
+            format!(
+                "#!/bin/sh\nfor url; do :; done\necho \"$url\" >> '{}'\n\
+                 case \"$url\" in *block) sleep 0.3 ;; esac\nprintf 'HTTP/1.1 200 OK\\r\\n\\r\\n%s' \"$url\"\n",
+                dir.join("log").display()
+            )
+        });
+
+        let client = curl.client(CuimpOptions::default());
+        let queue = RequestQueue::new(
+            client,
+            RequestQueueOptions {
+                concurrency: 2,
+                per_host: 1,
+                ..Default::default()
+            },
+        );
+        let config = |url: &str| CuimpRequestConfig {
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+
+        let blocker = queue.submit(config("https://a.example/block"), 0);
+        let low = queue.submit(config("https://a.example/low"), 1);
+        let dropped = queue.submit(config("https://a.example/dropped"), 5);
+        let mid = queue.submit(config("https://a.example/mid"), 3);
+        let other_host = queue.submit(config("https://b.example/other"), 0);
+        assert_eq!((queue.running(), queue.pending()), (2, 3));
+        drop(dropped);
+
+        assert_eq!(low.await.unwrap().raw_body, b"https://a.example/low");
+        mid.await.unwrap();
+        other_host.await.unwrap();
+        blocker.await.unwrap();
+        let log = std::fs::read_to_string(curl.dir().join("log")).unwrap();
+
+        // `block` and `other` start together; `a.example` then runs one request at a time
+        let mut started: Vec<&str> = log.lines().collect();
+        if let Some(first_two) = started.get_mut(..2) {
+            first_two.sort_unstable();
+        }
+        assert_eq!(
+            started,
+            [
+                "https://a.example/block",
+                "https://b.example/other",
+                "https://a.example/mid",
+                "https://a.example/low"
+            ]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::client::CuimpHttp;
+use crate::error::{CuimpError, Result};
+use crate::parallel::send_shared;
+use crate::types::{CuimpRequestConfig, CuimpResponse, HttpVersion, Method};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::Mutex;
+
+/// `tower::Service<http::Request<Bytes>>` over a shared `CuimpHttp`
+///
+/// Clones share the client. Each call is prepared on the client (default headers,
+/// cookies, proxy rotation, domain profile throttling) and then runs without holding
+/// it, so calls from concurrent tasks run in parallel; the client's retry policy still
+/// applies. The response carries the `RequestInfo` in its extensions. When replaying
+/// a cassette, calls run one at a time.
+#[derive(Debug, Clone)]
+pub struct CuimpService {
+    client: Arc<Mutex<CuimpHttp>>,
+}
+
+impl CuimpService {
+    pub fn new(client: CuimpHttp) -> Self {
+        CuimpService {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    /// The shared client, e.g. to read its cookie jar between calls
+    pub fn client(&self) -> &Arc<Mutex<CuimpHttp>> {
+        &self.client
+    }
+}
+
+impl From<CuimpHttp> for CuimpService {
+    fn from(client: CuimpHttp) -> Self {
+        CuimpService::new(client)
+    }
+}
+
+impl tower_service::Service<http::Request<Bytes>> for CuimpService {
+    type Response = http::Response<Bytes>;
+    type Error = CuimpError;
+    type Future = Pin<Box<dyn Future<Output = Result<http::Response<Bytes>>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Bytes>) -> Self::Future {
+        let client = Arc::clone(&self.client);
+        Box::pin(async move {
+            let config = request_config(request)?;
+            let response = send_shared(&client, config).await?;
+            http_response(response)
+        })
+    }
+}
+
+fn request_config(request: http::Request<Bytes>) -> Result<CuimpRequestConfig> {
+    let (parts, body) = request.into_parts();
+    let method: Method = parts.method.as_str().parse()?;
+
+    // Repeated headers are folded into one line, as curl would send them
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in &parts.headers {
+        let value = value
+            .to_str()
+            .map_err(|_| CuimpError::InvalidConfig(format!("Header {} is not valid text", name)))?;
+        let separator = if name == http::header::COOKIE {
+            "; "
+        } else {
+            ", "
+        };
+        headers
+            .entry(name.as_str().to_string())
+            .and_modify(|existing| {
+                existing.push_str(separator);
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+
+    Ok(CuimpRequestConfig {
+        url: Some(parts.uri.to_string()),
+        method: Some(method),
+        headers: Some(headers).filter(|headers| !headers.is_empty()),
+        body: (!body.is_empty()).then(|| body.to_vec()),
+        ..Default::default()
+    })
+}
+
+fn http_response(response: CuimpResponse<Vec<u8>>) -> Result<http::Response<Bytes>> {
+    let version = match response.http_version {
+        Some(HttpVersion::Http10) => http::Version::HTTP_10,
+        Some(HttpVersion::Http2) => http::Version::HTTP_2,
+        Some(HttpVersion::Http3) => http::Version::HTTP_3,
+        Some(HttpVersion::Http11) | None => http::Version::HTTP_11,
+    };
+    let mut builder = http::Response::builder()
+        .status(response.status)
+        .version(version);
+    for (name, value) in &response.headers {
+        // Skip headers the server sent that `http` does not accept rather than failing the response
+        let name = http::HeaderName::from_bytes(name.as_bytes());
+        let value = http::HeaderValue::from_str(value);
+        if let (Ok(name), Ok(value)) = (name, value) {
+            builder = builder.header(name, value);
+        }
+    }
+    builder
+        .extension(response.request)
+        .body(Bytes::from(response.data))
+        .map_err(|e| {
+            CuimpError::InvalidResponse(format!("Invalid response for http::Response: {}", e))
+        })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_config() {
+        let request = http::Request::post("https://example.com/items?page=2")
+            .header("Cookie", "a=1")
+            .header("Cookie", "b=2")
+            .header("Accept", "text/html")
+            .body(Bytes::from_static(b"payload"))
+            .unwrap();
+        let config = request_config(request).unwrap();
+        assert_eq!(
+            config.url.as_deref(),
+            Some("https://example.com/items?page=2")
+        );
+        assert_eq!(config.method, Some(Method::POST));
+        assert_eq!(config.body.as_deref(), Some(&b"payload"[..]));
+        let headers = config.headers.unwrap();
+        assert_eq!(headers.get("cookie").map(String::as_str), Some("a=1; b=2"));
+
+        let trace = http::Request::builder()
+            .method("TRACE")
+            .uri("https://example.com/")
+            .body(Bytes::new())
+            .unwrap();
+        assert!(request_config(trace).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_service_call() {
+        use crate::fake_curl::FakeCurl;
+        use crate::types::{CuimpOptions, RequestInfo};
+        use tower_service::Service;
+
+        let curl = FakeCurl::new(
+            "service",
+            "#!/bin/sh\nprintf 'HTTP/2 201\\r\\ncontent-type: text/plain\\r\\n\\r\\ncreated'\n",
+        );
+
+        let mut service = CuimpService::new(curl.client(CuimpOptions::default()));
+        let request = http::Request::put("https://example.com/a")
+            .body(Bytes::from_static(b"{}"))
+            .unwrap();
+        let response = service.call(request).await;
+
+        let response = response.unwrap();
+        assert_eq!(response.status(), http::StatusCode::CREATED);
+        assert_eq!(response.version(), http::Version::HTTP_2);
+        assert_eq!(response.headers()["content-type"], "text/plain");
+        assert_eq!(
+            response.extensions().get::<RequestInfo>().unwrap().method,
+            "PUT"
+        );
+        assert_eq!(response.body(), &b"created"[..]);
+    }
+}
@@ -0,0 +1,125 @@
+// Signature files come from the network: never index or unwrap.
+#![deny(clippy::indexing_slicing, clippy::unwrap_used, clippy::expect_used)]
+
+use crate::error::{CuimpError, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+/// Verify a minisign signature (`<asset>.minisig`) of `data`.
+///
+/// `public_key` is the base64 key line of a minisign `.pub` file (`RW...`). Both the
+/// signature and the global signature over the trusted comment must be valid.
+pub(crate) fn verify_minisign(
+    data: &[u8],
+    signature_file: &str,
+    public_key: &str,
+    asset_name: &str,
+) -> Result<()> {
+    let invalid =
+        |reason: &str| CuimpError::SignatureInvalid(format!("{}: {}", asset_name, reason));
+
+    let key = decode(public_key.trim()).ok_or_else(|| invalid("malformed public key"))?;
+    let (key_alg, key_rest) = key
+        .split_at_checked(2)
+        .ok_or_else(|| invalid("malformed public key"))?;
+    let (key_id, key_bytes) = key_rest
+        .split_at_checked(8)
+        .ok_or_else(|| invalid("malformed public key"))?;
+    if key_alg != b"Ed" {
+        return Err(invalid("unsupported public key algorithm"));
+    }
+    let verifying_key = key_bytes
+        .try_into()
+        .ok()
+        .and_then(|bytes: [u8; 32]| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| invalid("malformed public key"))?;
+
+    let mut lines = signature_file
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    let _untrusted_comment = lines.next();
+    let signature_line = lines.next().ok_or_else(|| invalid("missing signature"))?;
+    let trusted_comment = lines
+        .next()
+        .and_then(|l| l.strip_prefix("trusted comment: "))
+        .ok_or_else(|| invalid("missing trusted comment"))?;
+    let global_line = lines
+        .next()
+        .ok_or_else(|| invalid("missing global signature"))?;
+
+    let sig = decode(signature_line).ok_or_else(|| invalid("malformed signature"))?;
+    let (sig_alg, sig_rest) = sig
+        .split_at_checked(2)
+        .ok_or_else(|| invalid("malformed signature"))?;
+    let (sig_key_id, sig_bytes) = sig_rest
+        .split_at_checked(8)
+        .ok_or_else(|| invalid("malformed signature"))?;
+    if sig_key_id != key_id {
+        return Err(invalid("signed with a different key"));
+    }
+    let signature = Signature::from_slice(sig_bytes).map_err(|_| invalid("malformed signature"))?;
+
+    let verified = match sig_alg {
+        b"ED" => verifying_key.verify(&Blake2b512::digest(data), &signature),
+        b"Ed" => verifying_key.verify(data, &signature),
+        _ => return Err(invalid("unsupported signature algorithm")),
+    };
+    verified.map_err(|_| invalid("signature does not match archive"))?;
+
+    let global = decode(global_line)
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid("malformed global signature"))?;
+    let signed_comment = [sig_bytes, trusted_comment.as_bytes()].concat();
+    verifying_key
+        .verify(&signed_comment, &global)
+        .map_err(|_| invalid("trusted comment signature does not match"))
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    STANDARD.decode(text).ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[test]
+    fn test_verify_minisign() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let key_id = [1u8; 8];
+        let public_key = STANDARD.encode(
+            [
+                b"Ed".as_slice(),
+                &key_id,
+                signing_key.verifying_key().as_bytes(),
+            ]
+            .concat(),
+        );
+
+        let archive = b"archive bytes";
+        let signature = signing_key.sign(&Blake2b512::digest(archive)).to_bytes();
+        let comment = "timestamp:0\tfile:curl.tar.gz";
+        let global = signing_key
+            .sign(&[signature.as_slice(), comment.as_bytes()].concat())
+            .to_bytes();
+        let file = format!(
+            "untrusted comment: test\n{}\ntrusted comment: {}\n{}\n",
+            STANDARD.encode([b"ED".as_slice(), &key_id, &signature].concat()),
+            comment,
+            STANDARD.encode(global)
+        );
+
+        assert!(verify_minisign(archive, &file, &public_key, "curl.tar.gz").is_ok());
+        assert!(matches!(
+            verify_minisign(b"tampered", &file, &public_key, "curl.tar.gz"),
+            Err(CuimpError::SignatureInvalid(_))
+        ));
+        let forged = file.replace("timestamp:0", "timestamp:1");
+        assert!(verify_minisign(archive, &forged, &public_key, "curl.tar.gz").is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::error::Result;
+use crate::types::Method;
+use std::collections::HashMap;
+use std::fmt;
+use url::Url;
+
+/// A request about to be sent, as passed to `RequestSigner::sign`
+#[derive(Debug)]
+pub struct SigningRequest<'a> {
+    pub method: Method,
+    /// Final URL, query parameters included; a signer may add its own, e.g. a signature
+    pub url: &'a mut Url,
+    /// Headers to send, including cookies and the default `Content-Type` of a body;
+    /// signature headers are added here
+    pub headers: &'a mut HashMap<String, String>,
+    /// Body exactly as sent, `None` without one
+    pub body: Option<&'a [u8]>,
+}
+
+impl SigningRequest<'_> {
+    /// Value of a header, matching its name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Signs requests right before the curl command is built, for HMAC and other schemes
+/// the client does not know.
+///
+/// Called for every attempt, including retries and rotations, after all headers and
+/// the body are final. An error fails the request without running curl; report it as
+/// `CuimpError::SigningFailed`. Closures taking `&mut SigningRequest` implement this trait.
+pub trait RequestSigner: Send + Sync {
+    fn sign(&self, request: &mut SigningRequest<'_>) -> Result<()>;
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&mut SigningRequest<'_>) -> Result<()> + Send + Sync,
+{
+    fn sign(&self, request: &mut SigningRequest<'_>) -> Result<()> {
+        self(request)
+    }
+}
+
+impl fmt::Debug for dyn RequestSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestSigner(..)")
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+    use crate::error::CuimpError;
+    use crate::fake_curl::FakeCurl;
+    use crate::types::CuimpOptions;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_request_signer() {
+        let curl = FakeCurl::new(
+            "signer",
+            "#!/bin/sh\nprintf 'HTTP/1.1 200 OK\\r\\n\\r\\nok'\n",
+        );
+        let signer = |request: &mut SigningRequest<'_>| {
+            if request.url.path() == "/forbidden" {
+                return Err(CuimpError::SigningFailed(
+                    "no key for this path".to_string(),
+                ));
+            }
+            let signed = format!(
+                "{} {} {} {}",
+                request.method,
+                request.url.path(),
+                request.header("content-type").unwrap_or_default(),
+                String::from_utf8_lossy(request.body.unwrap_or_default())
+            );
+            request.headers.insert("X-Signature".to_string(), signed);
+            request
+                .url
+                .query_pairs_mut()
+                .append_pair("ts", "1700000000");
+            Ok(())
+        };
+        let mut client = curl.client(CuimpOptions {
+            signer: Some(Arc::new(signer)),
+            ..Default::default()
+        });
+
+        let signed = client
+            .post::<serde_json::Value>(
+                "https://api.example.com/orders",
+                Some(serde_json::json!({"id": 1})),
+            )
+            .await
+            .unwrap();
+        let failed = client
+            .get::<serde_json::Value>("https://api.example.com/forbidden")
+            .await;
+
+        assert_eq!(
+            signed.request.headers["X-Signature"],
+            "POST /orders application/json {\"id\":1}"
+        );
+        assert_eq!(
+            signed.request.url.as_str(),
+            "https://api.example.com/orders?ts=1700000000"
+        );
+        assert!(signed
+            .request
+            .command
+            .contains("'X-Signature: POST /orders"));
+        assert!(matches!(failed, Err(CuimpError::SigningFailed(_))));
+    }
+}
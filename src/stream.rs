@@ -0,0 +1,378 @@
+use crate::checksum::{hex, verify};
+use crate::client::{CuimpHttp, ResponseHead};
+use crate::cookie::Cookie;
+use crate::error::{CuimpError, Result};
+use crate::into_url::unicode_host;
+use crate::profile::DomainPermit;
+use crate::retry::{AttemptInfo, AttemptOutcome};
+use crate::runner::{spawn_binary, ProcessTree};
+use crate::types::{CuimpRequestConfig, HttpVersion, RequestInfo};
+use bytes::Bytes;
+use futures_core::Stream;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader, ReadBuf};
+use tokio::process::{Child, ChildStdout};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// HTTP response whose body is read incrementally from curl's stdout
+#[derive(Debug)]
+pub struct CuimpStreamResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: Option<HttpVersion>,
+    pub headers: HashMap<String, String>,
+    /// Cookies from the `Set-Cookie` headers of the final response
+    pub cookies: Vec<Cookie>,
+    pub body: BodyStream,
+    pub request: RequestInfo,
+}
+
+/// Stream of response body chunks, also readable as `AsyncRead` and `AsyncBufRead`
+///
+/// The body is read straight from curl's stdout through a `CHUNK_SIZE` buffer, so a
+/// slow consumer fills the pipe and curl stops reading from the socket instead of the
+/// body piling up in memory. The underlying curl process is killed when the stream is dropped.
+#[derive(Debug)]
+pub struct BodyStream {
+    reader: BufReader<ChildStdout>,
+    /// Killed with the stream, together with the child
+    _tree: ProcessTree,
+    _child: Child,
+    /// Domain profile concurrency slot, released with the stream
+    _permit: Option<DomainPermit>,
+    done: bool,
+    /// Hashes the body as it is read, with `CuimpRequestConfig::compute_sha256`
+    digest: Option<Sha256>,
+    sha256: Option<String>,
+}
+
+impl BodyStream {
+    /// Lowercase hex SHA-256 of the body once it has been read to the end, when the
+    /// request set `CuimpRequestConfig::compute_sha256`
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// Check the body read to the end against a hex SHA-256 digest, ignoring case
+    pub fn verify_sha256(&self, expected: &str) -> Result<()> {
+        match &self.sha256 {
+            Some(actual) => verify(actual, expected, "response body"),
+            None => Err(CuimpError::ChecksumMismatch(
+                "response body: no digest; set `compute_sha256` and read the body to the end"
+                    .to_string(),
+            )),
+        }
+    }
+
+    fn hash(&mut self, bytes: &[u8]) {
+        if let Some(digest) = self.digest.as_mut() {
+            digest.update(bytes);
+        }
+    }
+
+    fn finish_digest(&mut self) {
+        if let Some(digest) = self.digest.take() {
+            self.sha256 = Some(hex(&digest.finalize()));
+        }
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        let mut buf = ReadBuf::new(&mut chunk);
+        match Pin::new(&mut self.reader).poll_read(cx, &mut buf) {
+            Poll::Ready(Ok(())) => {
+                let filled = buf.filled().len();
+                if filled == 0 {
+                    self.done = true;
+                    self.finish_digest();
+                    return Poll::Ready(None);
+                }
+                chunk.truncate(filled);
+                self.hash(&chunk);
+                Poll::Ready(Some(Ok(Bytes::from(chunk))))
+            }
+            Poll::Ready(Err(e)) => {
+                self.done = true;
+                Poll::Ready(Some(Err(CuimpError::IoError(e))))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl AsyncRead for BodyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+        let read = buf.filled().get(before..).unwrap_or_default();
+        if read.is_empty() && buf.remaining() > 0 {
+            this.finish_digest();
+        } else {
+            this.hash(read);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncBufRead for BodyStream {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if ready!(Pin::new(&mut this.reader).poll_fill_buf(cx))?.is_empty() {
+            this.finish_digest();
+        }
+        Poll::Ready(Ok(this.reader.buffer()))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        if let Some(digest) = this.digest.as_mut() {
+            digest.update(this.reader.buffer().get(..amt).unwrap_or_default());
+        }
+        Pin::new(&mut this.reader).consume(amt);
+    }
+}
+
+impl CuimpHttp {
+    /// Make an HTTP request and stream the response body instead of buffering it.
+    ///
+    /// `config.timeout` is passed to curl as `--max-time` and bounds the whole transfer.
+    /// Streaming requests bypass cassettes and fail in replay mode.
+    pub async fn request_stream(
+        &mut self,
+        config: CuimpRequestConfig,
+    ) -> Result<CuimpStreamResponse> {
+        if self.is_replaying() {
+            return Err(CuimpError::RequestFailed(
+                "Streaming requests cannot be replayed from a cassette".to_string(),
+            ));
+        }
+
+        let follow_redirects = self.redirects(&config).curl_follows();
+        let mut output_flags = vec!["-i", "--no-buffer"];
+        let max_time = config
+            .timeout
+            .map(|ms| format!("{:.3}", ms as f64 / 1000.0));
+        if let Some(max_time) = &max_time {
+            output_flags.push("--max-time");
+            output_flags.push(max_time);
+        }
+
+        let request_started = std::time::SystemTime::now();
+        let prepared = self.prepare(&config, &output_flags).await?;
+        let permit = self.throttle(&prepared.url).await;
+        self.emit_command(&prepared.bin, &prepared.args);
+        let (mut child, tree) =
+            spawn_binary(&prepared.bin, &prepared.args, prepared.stdin.clone())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| CuimpError::RequestFailed("Failed to capture stdout".to_string()))?;
+        let mut reader = BufReader::with_capacity(CHUNK_SIZE, stdout);
+
+        let head = read_head(&mut reader, follow_redirects).await;
+        // Observed once the response head arrives; reading the body is up to the caller
+        let attempt = AttemptInfo {
+            outcome: match &head {
+                Ok(head) => AttemptOutcome::Status(head.status),
+                Err(e) => AttemptOutcome::Error(e.to_string()),
+            },
+            duration: request_started.elapsed().unwrap_or_default(),
+            proxy: self.attempt_proxy(&prepared),
+            fingerprint: self.fingerprint(),
+            exit_code: None,
+            stderr: String::new(),
+            debug_info: None,
+            connection: None,
+            truncated: false,
+        };
+        self.observe_outcome(
+            &prepared,
+            request_started,
+            &[attempt],
+            head.as_ref().map(|head| (head.status, None)),
+        );
+        let head = head?;
+
+        Ok(CuimpStreamResponse {
+            status: head.status,
+            status_text: self.reason_phrase().resolve(head.status, &head.status_text),
+            http_version: head.http_version,
+            headers: head.headers,
+            cookies: head
+                .set_cookies
+                .iter()
+                .filter_map(|header| Cookie::parse(header))
+                .collect(),
+            body: BodyStream {
+                reader,
+                _tree: tree,
+                _child: child,
+                _permit: permit,
+                done: false,
+                digest: prepared.compute_sha256.then(Sha256::new),
+                sha256: None,
+            },
+            request: RequestInfo {
+                unicode_host: unicode_host(&prepared.url),
+                url: prepared.url,
+                method: prepared.method.to_string(),
+                headers: prepared.headers,
+                command: prepared.command,
+            },
+        })
+    }
+}
+
+/// Read header blocks until the final response head, skipping interim
+/// responses (1xx, proxy CONNECT and followed redirects).
+async fn read_head<R>(reader: &mut R, follow_redirects: bool) -> Result<ResponseHead>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    loop {
+        let mut status_line = String::new();
+        loop {
+            status_line.clear();
+            if reader.read_line(&mut status_line).await? == 0 {
+                return Err(CuimpError::InvalidResponse(
+                    "Connection closed before a response head was received".to_string(),
+                ));
+            }
+            if !status_line.trim().is_empty() {
+                break;
+            }
+        }
+
+        let status_line = status_line.trim();
+        if !status_line.starts_with("HTTP/") {
+            return Err(CuimpError::InvalidResponse(format!(
+                "No HTTP response found:\n{}",
+                status_line
+            )));
+        }
+
+        let mut parts = status_line.splitn(3, ' ');
+        let http_version = parts.next().and_then(HttpVersion::from_status_token);
+        let status: u16 = parts
+            .next()
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| {
+                CuimpError::InvalidResponse(format!("Malformed status line: {}", status_line))
+            })?;
+        let status_text = parts.next().unwrap_or("").trim().to_string();
+
+        let mut headers = HashMap::new();
+        let mut set_cookies = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await? == 0 {
+                break;
+            }
+            let trimmed = line.trim_end_matches(['\r', '\n']);
+            if trimmed.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = trimmed.split_once(':') {
+                if key.trim().eq_ignore_ascii_case("set-cookie") {
+                    set_cookies.push(value.trim().to_string());
+                }
+                headers.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+
+        let is_interim = (100..200).contains(&status)
+            || status_text.eq_ignore_ascii_case("connection established")
+            || (follow_redirects
+                && (300..400).contains(&status)
+                && headers.keys().any(|k| k.eq_ignore_ascii_case("location")));
+        if !is_interim {
+            return Ok(ResponseHead {
+                status,
+                status_text,
+                http_version,
+                headers,
+                set_cookies,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_head_skips_interim_blocks() {
+        let raw: &[u8] = b"HTTP/1.1 200 Connection established\r\n\r\n\
+            HTTP/1.1 301 Moved Permanently\r\nLocation: /next\r\n\r\n\
+            HTTP/2 200\r\ncontent-type: text/plain\r\nset-cookie: a=1\r\nset-cookie: b=2; Secure\r\n\r\nbody";
+        let mut reader = BufReader::new(raw);
+        let head = read_head(&mut reader, true).await.unwrap();
+        assert_eq!(
+            (head.status, head.http_version),
+            (200, Some(HttpVersion::Http2))
+        );
+        assert_eq!(
+            head.headers.get("content-type").map(String::as_str),
+            Some("text/plain")
+        );
+        assert_eq!(head.set_cookies, ["a=1", "b=2; Secure"]);
+
+        let mut rest = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut reader, &mut rest)
+            .await
+            .unwrap();
+        assert_eq!(rest, "body");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_body_as_async_buf_read() {
+        let (mut child, tree) = spawn_binary(
+            "sh",
+            &["-c".to_string(), "printf 'one\\ntwo\\n'".to_string()],
+            None,
+        )
+        .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let body = BodyStream {
+            reader: BufReader::with_capacity(CHUNK_SIZE, stdout),
+            _tree: tree,
+            _child: child,
+            _permit: None,
+            done: false,
+            digest: Some(Sha256::new()),
+            sha256: None,
+        };
+
+        let mut lines = body.lines();
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("one"));
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("two"));
+        assert_eq!(lines.next_line().await.unwrap(), None);
+        let body = lines.into_inner();
+        assert_eq!(
+            body.sha256(),
+            Some(crate::checksum::sha256_hex(b"one\ntwo\n").as_str())
+        );
+        assert!(body.verify_sha256("0000").is_err());
+    }
+}
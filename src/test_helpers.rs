@@ -0,0 +1,215 @@
+use crate::types::CuimpResponse;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Characters of the body shown in assertion failures
+const BODY_PREVIEW_CHARS: usize = 2048;
+
+/// Assertions for test suites, panicking with the request, the mismatch and the body.
+///
+/// They return `&Self` to chain: `response.assert_status(200).assert_header("content-type", "application/json")`.
+impl<T> CuimpResponse<T> {
+    /// Assert the response status
+    #[track_caller]
+    pub fn assert_status(&self, expected: u16) -> &Self {
+        if self.status != expected {
+            panic!(
+                "expected status {}, got {} {}\n{}",
+                expected,
+                self.status,
+                self.status_text,
+                self.failure_context()
+            );
+        }
+        self
+    }
+
+    /// Assert a response header's value, matching the name case-insensitively
+    #[track_caller]
+    pub fn assert_header(&self, name: &str, expected: &str) -> &Self {
+        match self.header(name) {
+            Some(value) if value == expected => {}
+            Some(value) => panic!(
+                "header {}: expected {:?}, got {:?}\n{}",
+                name,
+                expected,
+                value,
+                self.failure_context()
+            ),
+            None => {
+                let mut names: Vec<_> = self.headers.keys().map(String::as_str).collect();
+                names.sort_unstable();
+                panic!(
+                    "header {}: expected {:?}, but it is missing (received: {})\n{}",
+                    name,
+                    expected,
+                    names.join(", "),
+                    self.failure_context()
+                )
+            }
+        }
+        self
+    }
+
+    /// Assert that the JSON body matches `expected`, e.g. `json!({"user": {"name": "alice"}})`.
+    ///
+    /// Objects match when every field of `expected` matches, so fields left out are not
+    /// checked; arrays must have the same length and matching elements; numbers compare by
+    /// value (`1` matches `1.0`). Every mismatch is reported with its path.
+    #[track_caller]
+    pub fn assert_json_matches(&self, expected: Value) -> &Self {
+        let actual: Value = match serde_json::from_slice(&self.raw_body) {
+            Ok(actual) => actual,
+            Err(e) => panic!(
+                "response body is not JSON: {}\n{}",
+                e,
+                self.failure_context()
+            ),
+        };
+        let mut mismatches = Vec::new();
+        json_mismatches("$", &expected, &actual, &mut mismatches);
+        if !mismatches.is_empty() {
+            panic!(
+                "response JSON does not match:\n  {}\n{}",
+                mismatches.join("\n  "),
+                self.failure_context()
+            );
+        }
+        self
+    }
+
+    /// Request line, status and a preview of the body, to follow an assertion message
+    fn failure_context(&self) -> String {
+        let mut context = format!(
+            "request: {} {}\nstatus: {} {}\nbody:\n",
+            self.request.method, self.request.url, self.status, self.status_text
+        );
+        let body = match serde_json::from_slice::<Value>(&self.raw_body) {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_default(),
+            Err(_) => String::from_utf8_lossy(&self.raw_body).into_owned(),
+        };
+        match body.char_indices().nth(BODY_PREVIEW_CHARS) {
+            Some((end, _)) => {
+                let _ = write!(
+                    context,
+                    "{}\n... ({} bytes)",
+                    body.get(..end).unwrap_or_default(),
+                    self.raw_body.len()
+                );
+            }
+            None => context.push_str(&body),
+        }
+        context
+    }
+}
+
+/// Collect `path: expected ..., got ...` lines for every place `actual` differs from `expected`
+fn json_mismatches(path: &str, expected: &Value, actual: &Value, mismatches: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                let path = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => json_mismatches(&path, expected, actual, mismatches),
+                    None => mismatches.push(format!(
+                        "{}: expected {}, but it is missing",
+                        path, expected
+                    )),
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                mismatches.push(format!(
+                    "{}: expected {} elements, got {}",
+                    path,
+                    expected.len(),
+                    actual.len()
+                ));
+            }
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                json_mismatches(
+                    &format!("{}[{}]", path, index),
+                    expected,
+                    actual,
+                    mismatches,
+                );
+            }
+        }
+        (Value::Number(e), Value::Number(a)) if e == a || e.as_f64() == a.as_f64() => {}
+        (expected, actual) if expected == actual => {}
+        (expected, actual) => {
+            mismatches.push(format!("{}: expected {}, got {}", path, expected, actual))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::types::RequestInfo;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn response(body: &str) -> CuimpResponse<Value> {
+        CuimpResponse {
+            status: 200,
+            status_text: "OK".to_string(),
+            http_version: None,
+            headers: HashMap::from([("Content-Type".to_string(), "application/json".to_string())]),
+            cookies: Vec::new(),
+            data: Value::Null,
+            raw_body: body.as_bytes().to_vec(),
+            request: RequestInfo {
+                url: "https://api.example.com/users/1".parse().unwrap(),
+                method: "GET".to_string(),
+                headers: HashMap::new(),
+                command: String::new(),
+                unicode_host: None,
+            },
+            attempts: Vec::new(),
+            diagnostics: None,
+            debug_info: None,
+            connection: None,
+            sha256: None,
+            truncated: false,
+        }
+    }
+
+    fn failure(check: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let payload = std::panic::catch_unwind(check).unwrap_err();
+        payload.downcast_ref::<String>().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_assertions() {
+        let ok = response(r#"{"id": 1, "name": "alice", "roles": ["admin"], "score": 2.0}"#);
+        ok.assert_status(200)
+            .assert_header("content-type", "application/json")
+            .assert_json_matches(json!({"name": "alice", "roles": ["admin"], "score": 2}));
+
+        let message = failure(|| {
+            response(r#"{"id": 1, "name": "bob", "roles": []}"#).assert_json_matches(
+                json!({"name": "alice", "roles": ["admin"], "email": "a@example.com"}),
+            );
+        });
+        assert!(message.starts_with("response JSON does not match:\n"));
+        assert!(message.contains("  $.name: expected \"alice\", got \"bob\"\n"));
+        assert!(message.contains("  $.roles: expected 1 elements, got 0\n"));
+        assert!(message.contains("  $.email: expected \"a@example.com\", but it is missing\n"));
+        assert!(message.contains("request: GET https://api.example.com/users/1\n"));
+        assert!(message.contains("\"name\": \"bob\""));
+
+        let message = failure(|| {
+            response("{}").assert_status(404);
+        });
+        assert!(message.starts_with("expected status 404, got 200 OK\n"));
+        let message = failure(|| {
+            response("{}").assert_header("etag", "\"v1\"");
+        });
+        assert!(message.starts_with(
+            "header etag: expected \"\\\"v1\\\"\", but it is missing (received: Content-Type)"
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::error::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+/// Custom body transformer. Receives the body and the response headers, which it may update
+/// (e.g. to drop `Content-Encoding` after decoding).
+pub type BodyTransformFn =
+    dyn Fn(Vec<u8>, &mut HashMap<String, String>) -> Result<Vec<u8>> + Send + Sync;
+
+/// A step in the body pipeline, applied to every buffered response before deserialization.
+///
+/// Transforms run in registration order and their output also replaces
+/// `CuimpResponse::raw_body`. Streaming responses are not transformed.
+#[derive(Clone)]
+pub enum BodyTransform {
+    /// Decode a `gzip` or `deflate` `Content-Encoding` (for binaries not run with `--compressed`)
+    #[cfg(feature = "decompress")]
+    Decompress,
+    /// Re-encode the body as UTF-8 from the `Content-Type` charset.
+    /// Supports UTF-8, UTF-16, ISO-8859-1/US-ASCII and windows-1252; other charsets pass through.
+    DecodeCharset,
+    /// Remove tracking query parameters (`utm_*`, `fbclid`, `gclid`, ...) from URLs in a text body
+    StripTrackingParams,
+    /// User-supplied transformer
+    Custom(Arc<BodyTransformFn>),
+}
+
+impl BodyTransform {
+    /// Wrap a closure as a transform
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(Vec<u8>, &mut HashMap<String, String>) -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        BodyTransform::Custom(Arc::new(f))
+    }
+
+    /// Apply this transform
+    pub fn apply(&self, body: Vec<u8>, headers: &mut HashMap<String, String>) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "decompress")]
+            BodyTransform::Decompress => decompress(body, headers),
+            BodyTransform::DecodeCharset => Ok(decode_charset(body, headers)),
+            BodyTransform::StripTrackingParams => Ok(match String::from_utf8(body) {
+                Ok(text) => strip_tracking_params(&text).into_bytes(),
+                Err(e) => e.into_bytes(),
+            }),
+            BodyTransform::Custom(f) => f(body, headers),
+        }
+    }
+}
+
+impl fmt::Debug for BodyTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "decompress")]
+            BodyTransform::Decompress => f.write_str("Decompress"),
+            BodyTransform::DecodeCharset => f.write_str("DecodeCharset"),
+            BodyTransform::StripTrackingParams => f.write_str("StripTrackingParams"),
+            BodyTransform::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Run `transforms` in order
+pub(crate) fn apply_transforms(
+    transforms: &[BodyTransform],
+    mut body: Vec<u8>,
+    headers: &mut HashMap<String, String>,
+) -> Result<Vec<u8>> {
+    for transform in transforms {
+        body = transform.apply(body, headers)?;
+    }
+    Ok(body)
+}
+
+/// Case-insensitive header lookup returning the stored key
+fn find_header<'a>(
+    headers: &'a HashMap<String, String>,
+    name: &str,
+) -> Option<(&'a String, &'a String)> {
+    headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name))
+}
+
+#[cfg(feature = "decompress")]
+fn decompress(body: Vec<u8>, headers: &mut HashMap<String, String>) -> Result<Vec<u8>> {
+    use crate::error::CuimpError;
+    use std::io::Read;
+
+    let Some((key, encoding)) = find_header(headers, "content-encoding") else {
+        return Ok(body);
+    };
+    let (key, encoding) = (key.clone(), encoding.trim().to_ascii_lowercase());
+
+    let mut decoded = Vec::new();
+    let result = match encoding.as_str() {
+        "gzip" | "x-gzip" => {
+            flate2::read::MultiGzDecoder::new(body.as_slice()).read_to_end(&mut decoded)
+        }
+        "deflate" => flate2::read::ZlibDecoder::new(body.as_slice()).read_to_end(&mut decoded),
+        _ => return Ok(body),
+    };
+    result.map_err(|e| {
+        CuimpError::InvalidResponse(format!("Failed to decode {} body: {}", encoding, e))
+    })?;
+
+    headers.remove(&key);
+    Ok(decoded)
+}
+
+fn decode_charset(body: Vec<u8>, headers: &mut HashMap<String, String>) -> Vec<u8> {
+    let Some((key, content_type)) = find_header(headers, "content-type") else {
+        return body;
+    };
+    let Some(charset) = content_type
+        .split(';')
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_ascii_lowercase())
+    else {
+        return body;
+    };
+
+    let decoded: String = match charset.as_str() {
+        "utf-8" | "utf8" => return body,
+        "iso-8859-1" | "latin1" | "us-ascii" | "ascii" => {
+            body.iter().map(|&b| char::from(b)).collect()
+        }
+        "windows-1252" | "cp1252" => body.iter().map(|&b| windows_1252_char(b)).collect(),
+        "utf-16le" | "utf-16be" | "utf-16" => {
+            let (big_endian, data) = match body.get(..2) {
+                Some([0xFE, 0xFF]) => (true, body.get(2..).unwrap_or_default()),
+                Some([0xFF, 0xFE]) => (false, body.get(2..).unwrap_or_default()),
+                _ => (charset == "utf-16be", body.as_slice()),
+            };
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|pair| match pair {
+                    [a, b] if big_endian => u16::from_be_bytes([*a, *b]),
+                    [a, b] => u16::from_le_bytes([*a, *b]),
+                    _ => 0,
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return body,
+    };
+
+    let content_type = content_type
+        .split(';')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if name.trim().eq_ignore_ascii_case("charset") => {
+                " charset=utf-8".to_string()
+            }
+            _ => param.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    headers.insert(key.clone(), content_type);
+    decoded.into_bytes()
+}
+
+/// windows-1252 differs from ISO-8859-1 only in 0x80..=0x9F
+fn windows_1252_char(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž',
+        '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH
+            .get(usize::from(byte - 0x80))
+            .copied()
+            .unwrap_or('\u{FFFD}'),
+        _ => char::from(byte),
+    }
+}
+
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid",
+    "mc_eid", "_hsenc", "_hsmi", "mkt_tok",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// Rewrite every `http(s)://` URL in `text` without tracking parameters
+fn strip_tracking_params(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest
+        .find("http://")
+        .into_iter()
+        .chain(rest.find("https://"))
+        .min()
+    {
+        let (before, from_url) = rest.split_at(start);
+        out.push_str(before);
+        let end = from_url
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '`' | ')'))
+            .unwrap_or(from_url.len());
+        let (candidate, after) = from_url.split_at(end);
+        out.push_str(&clean_url(candidate).unwrap_or_else(|| candidate.to_string()));
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `candidate` without tracking parameters, or `None` when nothing changes
+fn clean_url(candidate: &str) -> Option<String> {
+    // Links inside HTML attributes separate parameters with `&amp;`
+    let html_escaped = candidate.contains("&amp;");
+    let unescaped = candidate.replace("&amp;", "&");
+    let mut url = Url::parse(&unescaped).ok()?;
+
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let kept: Vec<&(String, String)> = pairs
+        .iter()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .collect();
+    if kept.len() == pairs.len() {
+        return None;
+    }
+
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+
+    let cleaned = url.to_string();
+    Some(if html_escaped {
+        cleaned.replace('&', "&amp;")
+    } else {
+        cleaned
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_tracking_params() {
+        let html = r#"<a href="https://example.com/a?id=1&amp;utm_source=x&amp;fbclid=y">a</a> https://example.com/b?utm_medium=z ok"#;
+        assert_eq!(
+            strip_tracking_params(html),
+            r#"<a href="https://example.com/a?id=1">a</a> https://example.com/b ok"#
+        );
+        assert_eq!(
+            strip_tracking_params("http://example.com/?q=1"),
+            "http://example.com/?q=1"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_order_and_charset() {
+        let mut headers = HashMap::from([(
+            "Content-Type".to_string(),
+            "text/html; charset=windows-1252".to_string(),
+        )]);
+        let transforms = vec![
+            BodyTransform::DecodeCharset,
+            BodyTransform::custom(|body, _| {
+                Ok(String::from_utf8_lossy(&body).to_uppercase().into_bytes())
+            }),
+        ];
+
+        let body = apply_transforms(&transforms, b"caf\xe9 \x80".to_vec(), &mut headers).unwrap();
+        assert_eq!(String::from_utf8(body).unwrap(), "CAFÉ €");
+        assert_eq!(headers["Content-Type"], "text/html; charset=utf-8");
+    }
+
+    #[cfg(feature = "decompress")]
+    #[test]
+    fn test_decompress_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello").unwrap();
+        let mut headers = HashMap::from([("Content-Encoding".to_string(), "gzip".to_string())]);
+
+        let body = BodyTransform::Decompress
+            .apply(encoder.finish().unwrap(), &mut headers)
+            .unwrap();
+        assert_eq!(body, b"hello");
+        assert!(headers.is_empty());
+    }
+}
@@ -1,13 +1,219 @@
+use crate::auth::AuthRefresh;
+use crate::cassette::Cassette;
+use crate::challenge::ChallengeDetector;
+use crate::checksum::{sha256_hex, verify};
+use crate::connection::ConnectionInfo;
+use crate::cookie::{Cookie, CookiePolicy};
+use crate::events::EventHandler;
+use crate::fingerprint::{Http2Fingerprint, TlsOptions};
+use crate::locale::Locale;
+use crate::metrics::MetricsSink;
+#[cfg(feature = "oauth1")]
+use crate::oauth::OAuth1;
+use crate::presets::HeaderPresets;
+use crate::profile::DomainProfiles;
+use crate::redact::Redaction;
+use crate::redirect::RedirectPolicy;
+use crate::retry::{AttemptInfo, CurlRetry, RetryPolicy};
+use crate::rotation::RotationPolicy;
+use crate::signer::RequestSigner;
+use crate::transform::BodyTransform;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Impersonation target family; parses from and serializes to lowercase names (`"chrome"`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Browser {
+    Chrome,
+    Firefox,
+    Edge,
+    Safari,
+    /// Tor Browser
+    Tor,
+    /// The OkHttp Android client
+    OkHttp,
+    /// Plain curl without impersonation
+    Curl,
+}
+
+impl Browser {
+    pub const ALL: &'static [Browser] = &[
+        Browser::Chrome,
+        Browser::Firefox,
+        Browser::Edge,
+        Browser::Safari,
+        Browser::Tor,
+        Browser::OkHttp,
+        Browser::Curl,
+    ];
+
+    /// Versions curl-impersonate releases provide wrappers for (`curl_safari184_ios` is
+    /// `184_ios`); empty for `OkHttp` and `Curl`, which are not checked
+    pub fn known_versions(self) -> &'static [&'static str] {
+        match self {
+            Browser::Chrome => &[
+                "99",
+                "100",
+                "101",
+                "104",
+                "107",
+                "110",
+                "116",
+                "119",
+                "120",
+                "123",
+                "124",
+                "131",
+                "133a",
+                "136",
+                "99_android",
+                "131_android",
+            ],
+            Browser::Firefox => &["133", "135"],
+            Browser::Edge => &["99", "101"],
+            Browser::Safari => &[
+                "153", "155", "170", "172_ios", "180", "180_ios", "184", "184_ios", "260",
+                "260_ios",
+            ],
+            Browser::Tor => &["145"],
+            Browser::OkHttp | Browser::Curl => &[],
+        }
+    }
+
+    /// Name as used in wrapper scripts (`curl_chrome124`) and config files
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Browser::Chrome => "chrome",
+            Browser::Firefox => "firefox",
+            Browser::Edge => "edge",
+            Browser::Safari => "safari",
+            Browser::Tor => "tor",
+            Browser::OkHttp => "okhttp",
+            Browser::Curl => "curl",
+        }
+    }
+}
+
+/// Operating system of a curl-impersonate release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Platform {
+    Linux,
+    Windows,
+    Macos,
+}
+
+impl Platform {
+    pub const ALL: &'static [Platform] = &[Platform::Linux, Platform::Windows, Platform::Macos];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Platform::Linux => "linux",
+            Platform::Windows => "windows",
+            Platform::Macos => "macos",
+        }
+    }
+}
+
+/// CPU architecture of a curl-impersonate release; also parses Rust's names (`x86_64`, `aarch64`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Arch {
+    X64,
+    Arm64,
+}
+
+impl Arch {
+    pub const ALL: &'static [Arch] = &[Arch::X64, Arch::Arm64];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Arch::X64 => "x64",
+            Arch::Arm64 => "arm64",
+        }
+    }
+}
+
+/// `Display`, `FromStr` (case-insensitive) and the string conversions serde goes through
+macro_rules! string_enum {
+    ($name:ident, $error:ident, $kind:literal $(, $alias:literal => $value:expr)*) => {
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = crate::error::CuimpError;
+
+            fn from_str(s: &str) -> crate::error::Result<Self> {
+                let lower = s.trim().to_ascii_lowercase();
+                $(if lower == $alias {
+                    return Ok($value);
+                })*
+                $name::ALL.iter().copied().find(|value| value.as_str() == lower).ok_or_else(|| {
+                    let supported: Vec<&str> = $name::ALL.iter().map(|value| value.as_str()).collect();
+                    crate::error::CuimpError::$error(format!("{}. Supported {}: {}", s, $kind, supported.join(", ")))
+                })
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = crate::error::CuimpError;
+
+            fn try_from(value: String) -> crate::error::Result<Self> {
+                value.parse()
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                value.as_str().to_string()
+            }
+        }
+    };
+}
+
+string_enum!(Browser, UnsupportedBrowser, "browsers");
+string_enum!(Platform, UnsupportedPlatform, "platforms");
+string_enum!(Arch, UnsupportedArchitecture, "architectures", "x86_64" => Arch::X64, "aarch64" => Arch::Arm64);
 
 /// Browser descriptor for impersonation
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CuimpDescriptor {
-    pub browser: Option<String>,
+    pub browser: Option<Browser>,
     pub version: Option<String>,
-    pub architecture: Option<String>,
-    pub platform: Option<String>,
+    /// curl-impersonate release to download (e.g. `v1.0.0`); the latest release when unset
+    #[serde(default)]
+    pub release: Option<String>,
+    pub architecture: Option<Arch>,
+    pub platform: Option<Platform>,
+    /// Expected SHA-256 (hex) of the release archive. When unset, the digest pinned for
+    /// the release or else the checksum published next to the archive is used; without
+    /// either the download fails unless `allow_unverified` is set.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Minisign public key (base64 `RW...` line) the release archive must be signed with.
+    /// Requires the `verify-signatures` feature; downloads fail without a valid `.minisig`.
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// Install a downloaded release no SHA-256 is known for instead of failing with
+    /// `CuimpError::ChecksumUnknown`
+    #[serde(default)]
+    pub allow_unverified: bool,
+}
+
+impl CuimpDescriptor {
+    /// Impersonation target as `browser version` (e.g. `chrome 123`), if a browser is set
+    pub(crate) fn target(&self) -> Option<String> {
+        let browser = self.browser?.as_str();
+        Some(match &self.version {
+            Some(version) => format!("{} {}", browser, version),
+            None => browser.to_string(),
+        })
+    }
 }
 
 /// Information about the curl-impersonate binary
@@ -18,6 +224,20 @@ pub struct BinaryInfo {
     pub version: Option<String>,
 }
 
+/// A curl-impersonate binary or wrapper script found on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledBinary {
+    pub path: String,
+    /// Target of a wrapper script (`chrome` for `curl_chrome124`); `None` for the main binary
+    pub browser: Option<String>,
+    /// Target version from the file name (`124` for `curl_chrome124`)
+    pub version: Option<String>,
+    /// `x64`/`arm64` from the executable header; `None` for scripts
+    pub architecture: Option<String>,
+    pub size: u64,
+    pub modified: Option<std::time::SystemTime>,
+}
+
 /// HTTP methods
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Method {
@@ -44,54 +264,711 @@ impl Method {
     }
 }
 
+impl std::str::FromStr for Method {
+    type Err = crate::error::CuimpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "GET" => Ok(Method::GET),
+            "POST" => Ok(Method::POST),
+            "PUT" => Ok(Method::PUT),
+            "PATCH" => Ok(Method::PATCH),
+            "DELETE" => Ok(Method::DELETE),
+            "HEAD" => Ok(Method::HEAD),
+            "OPTIONS" => Ok(Method::OPTIONS),
+            other => Err(crate::error::CuimpError::ParseError(format!(
+                "Unsupported method: {}",
+                other
+            ))),
+        }
+    }
+}
+
 impl std::fmt::Display for Method {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())
     }
 }
 
+/// HTTP protocol version of a response, taken from its status line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HttpVersion {
+    #[serde(rename = "HTTP/1.0")]
+    Http10,
+    #[serde(rename = "HTTP/1.1")]
+    Http11,
+    #[serde(rename = "HTTP/2")]
+    Http2,
+    #[serde(rename = "HTTP/3")]
+    Http3,
+}
+
+impl HttpVersion {
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+            HttpVersion::Http3 => "HTTP/3",
+        }
+    }
+
+    /// Parse the version token of a status line (`HTTP/2`, `HTTP/2.0`, `HTTP/1.1`)
+    pub(crate) fn from_status_token(token: &str) -> Option<HttpVersion> {
+        match token.strip_prefix("HTTP/")? {
+            "1.0" => Some(HttpVersion::Http10),
+            "1.1" => Some(HttpVersion::Http11),
+            "2" | "2.0" => Some(HttpVersion::Http2),
+            "3" | "3.0" => Some(HttpVersion::Http3),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Proxy server, holding its `[user:pass@]host:port` address
+///
+/// Converts into the URL string taken by the `proxy` options:
+/// `proxy: Some(Proxy::Socks5h("127.0.0.1:9050".into()).into())`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Proxy {
+    /// `http://` proxy
+    Http(String),
+    /// `https://` proxy; the connection to the proxy itself uses TLS
+    Https(String),
+    /// `socks4://` proxy; hostnames are resolved locally
+    Socks4(String),
+    /// `socks5://` proxy; hostnames are resolved locally and the proxy only sees IP addresses
+    Socks5(String),
+    /// `socks5h://` proxy; hostnames are sent to the proxy and resolved there,
+    /// so DNS lookups do not leak outside the proxy (e.g. Tor)
+    Socks5h(String),
+}
+
+impl Proxy {
+    pub fn scheme(&self) -> &str {
+        match self {
+            Proxy::Http(_) => "http",
+            Proxy::Https(_) => "https",
+            Proxy::Socks4(_) => "socks4",
+            Proxy::Socks5(_) => "socks5",
+            Proxy::Socks5h(_) => "socks5h",
+        }
+    }
+
+    /// The `[user:pass@]host:port` part
+    pub fn address(&self) -> &str {
+        match self {
+            Proxy::Http(address)
+            | Proxy::Https(address)
+            | Proxy::Socks4(address)
+            | Proxy::Socks5(address)
+            | Proxy::Socks5h(address) => address,
+        }
+    }
+
+    /// Whether the proxy resolves target hostnames
+    pub fn resolves_remotely(&self) -> bool {
+        matches!(self, Proxy::Http(_) | Proxy::Https(_) | Proxy::Socks5h(_))
+    }
+}
+
+/// Parses `scheme://address`, `scheme address` (e.g. `socks5 host:1080`) or a bare
+/// address, which is taken as an HTTP proxy. Schemes are case-insensitive.
+impl std::str::FromStr for Proxy {
+    type Err = crate::error::CuimpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (scheme, address) = match s.split_once("://") {
+            Some((scheme, address)) => (scheme, address),
+            None => match s.split_once(char::is_whitespace) {
+                Some((scheme, address)) => (scheme, address.trim_start()),
+                None => ("http", s),
+            },
+        };
+        let address = address.trim_end_matches('/').to_string();
+        if address.is_empty() {
+            return Err(crate::error::CuimpError::InvalidConfig(format!(
+                "Proxy {:?} has no address",
+                s
+            )));
+        }
+        match scheme.to_lowercase().as_str() {
+            "http" => Ok(Proxy::Http(address)),
+            "https" => Ok(Proxy::Https(address)),
+            "socks4" => Ok(Proxy::Socks4(address)),
+            "socks5" => Ok(Proxy::Socks5(address)),
+            "socks5h" => Ok(Proxy::Socks5h(address)),
+            other => Err(crate::error::CuimpError::InvalidConfig(format!(
+                "Unsupported proxy scheme: {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Proxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}://{}", self.scheme(), self.address())
+    }
+}
+
+impl From<Proxy> for String {
+    fn from(proxy: Proxy) -> Self {
+        proxy.to_string()
+    }
+}
+
+/// Where `CuimpResponse::status_text` comes from
+///
+/// HTTP/2 and HTTP/3 responses carry no reason phrase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReasonPhrase {
+    /// The server's reason phrase, or the canonical one when it sent none
+    #[default]
+    CanonicalIfMissing,
+    /// Always the canonical phrase for the status code
+    Canonical,
+    /// Exactly what the server sent (empty for HTTP/2)
+    AsSent,
+}
+
+impl ReasonPhrase {
+    pub(crate) fn resolve(self, status: u16, sent: &str) -> String {
+        let canonical = || canonical_reason(status).unwrap_or_default().to_string();
+        match self {
+            ReasonPhrase::AsSent => sent.to_string(),
+            ReasonPhrase::Canonical => canonical(),
+            ReasonPhrase::CanonicalIfMissing if sent.is_empty() => canonical(),
+            ReasonPhrase::CanonicalIfMissing => sent.to_string(),
+        }
+    }
+}
+
+/// Canonical reason phrase of a status code (RFC 9110 and common extensions)
+pub fn canonical_reason(status: u16) -> Option<&'static str> {
+    Some(match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        102 => "Processing",
+        103 => "Early Hints",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        203 => "Non-Authoritative Information",
+        204 => "No Content",
+        205 => "Reset Content",
+        206 => "Partial Content",
+        207 => "Multi-Status",
+        208 => "Already Reported",
+        226 => "IM Used",
+        300 => "Multiple Choices",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        305 => "Use Proxy",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        402 => "Payment Required",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        407 => "Proxy Authentication Required",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        411 => "Length Required",
+        412 => "Precondition Failed",
+        413 => "Content Too Large",
+        414 => "URI Too Long",
+        415 => "Unsupported Media Type",
+        416 => "Range Not Satisfiable",
+        417 => "Expectation Failed",
+        418 => "I'm a teapot",
+        421 => "Misdirected Request",
+        422 => "Unprocessable Content",
+        423 => "Locked",
+        424 => "Failed Dependency",
+        425 => "Too Early",
+        426 => "Upgrade Required",
+        428 => "Precondition Required",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        451 => "Unavailable For Legal Reasons",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        506 => "Variant Also Negotiates",
+        507 => "Insufficient Storage",
+        508 => "Loop Detected",
+        510 => "Not Extended",
+        511 => "Network Authentication Required",
+        _ => return None,
+    })
+}
+
+/// How request-level `extra_curl_args` combine with the client-level ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtraArgsMerge {
+    /// Client args followed by request args
+    Append,
+    /// Request args followed by client args
+    Prepend,
+    /// Request args replace client args entirely (when present)
+    #[default]
+    Replace,
+}
+
 /// HTTP request configuration
 #[derive(Debug, Clone, Default)]
 pub struct CuimpRequestConfig {
     pub url: Option<String>,
     pub method: Option<Method>,
     pub base_url: Option<String>,
+    /// Request headers, replacing client defaults, domain profile headers and browser
+    /// presets of the same name (compared case-insensitively)
     pub headers: Option<HashMap<String, String>>,
+    /// Inherited headers not to send with this request: client defaults, domain profile
+    /// headers, `Accept-Language` of the locale, browser presets, the default `Content-Type`
+    /// and headers curl or the impersonation target adds itself. `headers` still apply.
+    pub remove_headers: Option<Vec<String>>,
+    /// Query parameters, appended to the URL's query sorted by name
     pub params: Option<HashMap<String, String>>,
+    /// Query string sent exactly as given (without the `?`), replacing the URL's query;
+    /// `params` are appended to it
+    pub raw_query: Option<String>,
     pub data: Option<serde_json::Value>,
+    /// Raw request body sent byte-for-byte through stdin (`--data-binary @-`); takes precedence over `data`
+    pub body: Option<Vec<u8>>,
     pub timeout: Option<u64>,
-    pub max_redirects: Option<u32>,
+    /// Milliseconds the whole request may take, from resolving (or downloading) the binary
+    /// through the transfer and any retries; `CuimpError::DeadlineExceeded` names the phase
+    /// it ran out in. `timeout` only bounds each curl run.
+    pub deadline: Option<u64>,
+    /// How redirects are followed (default: `RedirectPolicy::Limited(10)`)
+    pub redirect: Option<RedirectPolicy>,
+    /// Drop `Authorization` and `Cookie` headers on redirects to another origin (default: true);
+    /// cookies from the jar still go to the domains they belong to
+    pub strip_credentials_on_redirect: Option<bool>,
+    /// Fail with `CuimpError::RedirectRejected` when an https request is redirected to http
+    /// (default: false)
+    pub forbid_https_downgrade: Option<bool>,
     pub proxy: Option<String>,
     pub insecure_tls: Option<bool>,
     pub extra_curl_args: Option<Vec<String>>,
+    /// How `extra_curl_args` combine with the client's; for `Append` and `Prepend`,
+    /// a non-repeatable option given in both keeps only the request's value
+    pub extra_curl_args_merge: Option<ExtraArgsMerge>,
+    pub retry: Option<RetryPolicy>,
+    /// Connection retries delegated to curl (`--retry`); conflicts with an active `retry` policy
+    pub curl_retry: Option<CurlRetry>,
+    /// Cookie handling for this request (defaults to `CookiePolicy::JarDefault`)
+    pub cookies: Option<CookiePolicy>,
+    /// Fail with `CuimpError::HeadersTooLarge` instead of sending more header bytes than this
+    pub max_request_header_bytes: Option<usize>,
+    /// Send an oversized `Cookie` header as several `Cookie` lines of at most 4 KiB each
+    pub split_cookie_header: Option<bool>,
+    /// Locale to present (Accept-Language and geo-matched proxy)
+    pub locale: Option<Locale>,
+    /// Cipher, curve and extension-order overrides for the ClientHello
+    pub tls: Option<TlsOptions>,
+    /// HTTP/2 SETTINGS, WINDOW_UPDATE and priority overrides
+    pub http2: Option<Http2Fingerprint>,
+    /// Whether curl may send `Expect: 100-continue` before a request body.
+    ///
+    /// By default (`None` or `true`) curl adds it to large uploads over HTTP/1.1 and waits
+    /// briefly for the server's `100 Continue`; HTTP/2 and HTTP/3 never use it. `false`
+    /// suppresses the header (`-H "Expect:"`), which some anti-bot endpoints and proxies
+    /// require. An `Expect` header set explicitly is left alone.
+    pub expect_continue: Option<bool>,
+    /// Abort the transfer when it stays below a minimum speed (`--speed-limit`/`--speed-time`)
+    pub low_speed: Option<LowSpeedLimit>,
+    /// Cap the transfer rate at this many bytes per second (`--limit-rate`)
+    pub max_rate: Option<u64>,
+    /// Run curl with `--verbose` and return its trace (connection, TLS handshake, ALPN
+    /// and the headers exchanged) in `CuimpResponse::debug_info`. Sensitive header values
+    /// are redacted. Not supported with `Backend::Ffi` or streaming requests.
+    pub debug: bool,
+    /// Keep the server's certificate chain, parsed, in `ConnectionInfo::certificates`
+    /// (curl's `%{certs}`, or `CURLINFO_CERTINFO` with `Backend::Ffi`). Only the leaf's
+    /// subject, issuer and expiry are reported otherwise.
+    pub capture_certificates: bool,
+    /// Compute the SHA-256 of the body into `CuimpResponse::sha256` as it is received: of
+    /// `raw_body`, of the file for `download_to`, or of the stream for `request_stream`
+    /// (`BodyStream::sha256`)
+    pub compute_sha256: bool,
+    /// When the transfer times out after the response head, return the body received so
+    /// far with `CuimpResponse::truncated` set instead of `CuimpError::Timeout`. Such a
+    /// response is not retried. `Backend::Ffi` ignores it.
+    pub allow_partial: bool,
+    /// Present the request to a virtual host while connecting to another address
+    pub host_override: Option<HostOverride>,
+    /// Signs the request once headers and body are final, instead of `CuimpOptions::signer`
+    pub signer: Option<Arc<dyn RequestSigner>>,
+    /// Sign the request with OAuth 1.0a
+    #[cfg(feature = "oauth1")]
+    pub oauth1: Option<OAuth1>,
+}
+
+/// Virtual host for a request, e.g. to test a CDN origin or staging VIP behind the
+/// production hostname.
+///
+/// The URL's host is replaced by `host`, so it is sent as `Host` and SNI and checked
+/// against the certificate, while curl connects to `address` (`--connect-to`). Not
+/// supported with `Backend::Ffi`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostOverride {
+    /// Hostname presented to the server
+    pub host: String,
+    /// Host or IP to connect to; the URL's host when `None`
+    pub address: Option<String>,
+    /// Port to connect to; the URL's port when `None`
+    pub port: Option<u16>,
+}
+
+impl HostOverride {
+    /// Present `host` while connecting to the URL's host
+    pub fn new(host: impl Into<String>) -> Self {
+        HostOverride {
+            host: host.into(),
+            address: None,
+            port: None,
+        }
+    }
+
+    /// Connect to `address` instead
+    pub fn connect_to(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// Connect to `port` instead of the URL's port
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Point `url` at the virtual host and return the `--connect-to` arguments that
+    /// route it to the original (or overriding) address
+    pub(crate) fn apply(&self, url: &mut url::Url) -> crate::error::Result<Vec<String>> {
+        let (Some(original), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+            return Err(crate::error::CuimpError::InvalidUrl(format!(
+                "{}: host override needs a URL with a host",
+                url
+            )));
+        };
+        let address = match &self.address {
+            // IPv6 literals are bracketed in `--connect-to`, as `host_str` returns them
+            Some(address) if address.parse::<std::net::Ipv6Addr>().is_ok() => {
+                format!("[{}]", address)
+            }
+            Some(address) => address.clone(),
+            None => original.to_string(),
+        };
+        url.set_host(Some(&self.host)).map_err(|e| {
+            crate::error::CuimpError::InvalidUrl(format!(
+                "{:?}: invalid host override: {}",
+                self.host, e
+            ))
+        })?;
+        let host = url.host_str().unwrap_or(&self.host);
+        Ok(vec![
+            "--connect-to".to_string(),
+            format!(
+                "{}:{}:{}:{}",
+                host,
+                port,
+                address,
+                self.port.unwrap_or(port)
+            ),
+        ])
+    }
+}
+
+/// Minimum transfer speed; slower transfers fail with `CuimpError::Timeout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowSpeedLimit {
+    /// Minimum average speed in bytes per second
+    pub bytes_per_sec: u64,
+    /// How long the transfer may stay below `bytes_per_sec` before it is aborted
+    pub secs: u64,
+}
+
+impl LowSpeedLimit {
+    pub(crate) fn to_args(self) -> Vec<String> {
+        vec![
+            "--speed-limit".to_string(),
+            self.bytes_per_sec.to_string(),
+            "--speed-time".to_string(),
+            self.secs.max(1).to_string(),
+        ]
+    }
 }
 
 /// HTTP response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Serializes with a `schema_version` and a base64 `raw_body`; see `RESPONSE_SCHEMA_VERSION`.
+#[derive(Debug, Clone)]
 pub struct CuimpResponse<T> {
     pub status: u16,
     pub status_text: String,
+    /// Protocol of the final response; `None` when it could not be determined
+    pub http_version: Option<HttpVersion>,
     pub headers: HashMap<String, String>,
+    /// Cookies from every `Set-Cookie` header received, including on redirects,
+    /// whether or not the client keeps a cookie jar
+    pub cookies: Vec<Cookie>,
     pub data: T,
     pub raw_body: Vec<u8>,
     pub request: RequestInfo,
+    /// Every attempt made for this request, including the final one
+    pub attempts: Vec<AttemptInfo>,
+    /// What curl reported for the final attempt when it exited with an error or
+    /// wrote to stderr even though a response was received (e.g. a partial transfer)
+    pub diagnostics: Option<Diagnostics>,
+    /// curl's verbose trace of the final attempt when `CuimpRequestConfig::debug` is set
+    pub debug_info: Option<String>,
+    /// Protocol, addresses and TLS details of the final attempt's connection; `None`
+    /// for replayed responses and with `Backend::Ffi`
+    pub connection: Option<ConnectionInfo>,
+    /// Lowercase hex SHA-256 of the body when `CuimpRequestConfig::compute_sha256` is set
+    pub sha256: Option<String>,
+    /// The transfer timed out and the body is what arrived until then (`allow_partial`)
+    pub truncated: bool,
+}
+
+impl<T> CuimpResponse<T> {
+    /// The anti-bot block this response is, if any, by the built-in `ChallengeDetector`
+    pub fn blocked(&self) -> Option<crate::challenge::Blocked> {
+        ChallengeDetector::default().detect_response(self)
+    }
+
+    /// Value of a response header, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Check the body against a hex SHA-256 digest, ignoring case, failing with
+    /// `CuimpError::ChecksumMismatch`.
+    ///
+    /// Uses `sha256` when it was computed and hashes `raw_body` otherwise, so a file
+    /// written by `download_to` needs `CuimpRequestConfig::compute_sha256`.
+    pub fn verify_sha256(&self, expected: &str) -> crate::error::Result<()> {
+        let actual = match &self.sha256 {
+            Some(sha256) => sha256.clone(),
+            None => sha256_hex(&self.raw_body),
+        };
+        verify(&actual, expected, self.request.url.as_str())
+    }
+}
+
+/// Exit code and stderr messages of curl
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostics {
+    /// Exit code of curl (`CURLcode` with `Backend::Ffi`)
+    pub exit_code: Option<i32>,
+    /// Errors, warnings and notes curl wrote to stderr, without the progress meter
+    pub stderr: String,
 }
 
 /// Request information included in the response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestInfo {
-    pub url: String,
+    /// URL as sent; internationalized domain names are in punycode, as curl receives them
+    pub url: url::Url,
     pub method: String,
     pub headers: HashMap<String, String>,
     pub command: String,
+    /// Unicode form of the host when it is an internationalized domain name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unicode_host: Option<String>,
+}
+
+/// How `CuimpHttp` runs requests
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Spawn the curl-impersonate binary for every request
+    #[default]
+    Subprocess,
+    /// Run requests in-process through libcurl-impersonate, reusing connections
+    #[cfg(feature = "ffi")]
+    Ffi {
+        /// Shared library to load; searched next to the binary and on the loader path when unset
+        library: Option<std::path::PathBuf>,
+    },
 }
 
 /// Options for creating a Cuimp instance
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct CuimpOptions {
     pub descriptor: Option<CuimpDescriptor>,
     pub path: Option<String>,
+    /// Directory binaries are downloaded to and looked up in first
+    /// (default: `$CUIMP_HOME/binaries`, or `~/.cuimp/binaries`)
+    pub binaries_dir: Option<String>,
     pub extra_curl_args: Option<Vec<String>>,
+    /// Default proxy for requests made by `CuimpHttp`
+    pub proxy: Option<String>,
+    /// Use `HTTP_PROXY`/`HTTPS_PROXY`/`ALL_PROXY` when no proxy is configured (default: true).
+    /// With `false` and no proxy, requests connect directly (`--noproxy '*'`), whatever the environment.
+    pub trust_env: bool,
+    /// Default request timeout in milliseconds
+    pub timeout: Option<u64>,
+    /// Default `CuimpRequestConfig::deadline` in milliseconds, covering binary resolution,
+    /// the transfer and retries
+    pub deadline: Option<u64>,
+    /// Headers sent with every request made by `CuimpHttp`
+    pub headers: Option<HashMap<String, String>>,
+    /// Default locale for requests made by `CuimpHttp`
+    pub locale: Option<Locale>,
+    /// Default ClientHello overrides for requests made by `CuimpHttp`
+    pub tls: Option<TlsOptions>,
+    /// Default HTTP/2 fingerprint overrides for requests made by `CuimpHttp`
+    pub http2: Option<Http2Fingerprint>,
+    /// Default for `CuimpRequestConfig::expect_continue`
+    pub expect_continue: Option<bool>,
+    /// Default low-speed abort for requests made by `CuimpHttp`
+    pub low_speed: Option<LowSpeedLimit>,
+    /// Default transfer rate cap in bytes per second for requests made by `CuimpHttp`
+    pub max_rate: Option<u64>,
+    /// Proxies keyed by ISO 3166 region (`DE`, `US`, ...). Requests with a locale
+    /// and no explicit proxy rotate through the proxies of the locale's region.
+    pub geo_proxies: Option<HashMap<String, Vec<String>>>,
+    /// Per-domain rate, concurrency, headers, fingerprint and proxies, chosen by request URL
+    pub domain_profiles: Option<DomainProfiles>,
+    /// Ordered transforms applied to every buffered response body before deserialization
+    pub body_transforms: Option<Vec<BodyTransform>>,
+    /// How long the latest release tag cached in `metadata.json` under `CUIMP_HOME` (`~/.cuimp`) is reused (default: 1 day)
+    pub release_cache_ttl_secs: Option<u64>,
+    /// Look up the latest release on GitHub even if the cached tag is fresh
+    pub refresh_release_cache: bool,
+    /// Download the binary even when one is already installed, e.g. to replace a corrupted
+    /// or stale install; ignored when `path` is set
+    pub force: bool,
+    /// Download a binary when none is installed (default: true). When false, only `path`
+    /// and installed binaries are used and a missing one fails with `BinaryNotFound`.
+    /// Builds without the `download` feature never download.
+    pub auto_download: bool,
+    /// Record responses to, or replay them from, a cassette file
+    pub cassette: Option<Cassette>,
+    /// Default retry policy for requests made by `CuimpHttp`
+    pub retry: Option<RetryPolicy>,
+    /// Default connection retries delegated to curl; also settable per request
+    pub curl_retry: Option<CurlRetry>,
+    /// Default redirect policy for requests made by `CuimpHttp`
+    pub redirect: Option<RedirectPolicy>,
+    pub strip_credentials_on_redirect: Option<bool>,
+    pub forbid_https_downgrade: Option<bool>,
+    /// How `status_text` is filled in, notably for HTTP/2 responses without a reason phrase
+    pub reason_phrase: ReasonPhrase,
+    /// Keep cookies received by `CuimpHttp` and send them on later requests
+    pub cookie_store: bool,
+    /// Netscape cookie file (curl's `--cookie`/`--cookie-jar` format) to start the cookie
+    /// jar from when it exists; implies `cookie_store`. `CuimpHttp::save_cookies` writes it back.
+    pub cookie_file: Option<std::path::PathBuf>,
+    /// Secret redaction for `RequestInfo::command` and log output (defaults to `Redaction::default()`)
+    pub redaction: Option<Redaction>,
+    /// Subprocess (default) or, with the `ffi` feature, in-process libcurl-impersonate
+    pub backend: Backend,
+    /// When the impersonated browser's default headers are added (default: without a wrapper script)
+    pub header_presets: HeaderPresets,
+    /// Receives method, host, status, duration, sizes and exit code of every request
+    pub metrics: Option<Arc<dyn MetricsSink>>,
+    /// Receives what happens while resolving binaries and running curl (binary found,
+    /// download progress, command spawned, ...); nothing is printed to stdout
+    pub on_event: Option<Arc<dyn EventHandler>>,
+    /// Identical GET requests (same URL and headers) in flight at the same time share one
+    /// curl invocation; applies to `run_all`, `compat::Client` and `CuimpService`
+    pub coalesce_requests: bool,
+    /// Schemes accepted for request URLs, replacing the default `http`, `https`, `ws`
+    /// and `wss`; other URLs fail with `CuimpError::InvalidUrl` before curl runs
+    pub allowed_url_schemes: Option<Vec<String>>,
+    /// Pass `data` bodies larger than this many bytes to curl through a temporary file
+    /// (`--data-binary @file`) instead of argv; `DEFAULT_BODY_FILE_THRESHOLD` when `None`.
+    /// Raw `body` bytes always go through stdin.
+    pub body_file_threshold: Option<usize>,
+    /// Fail responses recognized as anti-bot block or challenge pages with
+    /// `CuimpError::Blocked` instead of returning them
+    pub challenge_detector: Option<ChallengeDetector>,
+    /// Retry blocked requests with other fingerprints and/or proxies; implies the default
+    /// `challenge_detector` when none is set
+    pub rotation: Option<RotationPolicy>,
+    /// Renews credentials on `401 Unauthorized` and sends the request once more
+    pub on_unauthorized: Option<AuthRefresh>,
+    /// Signs every request made by `CuimpHttp` once headers and body are final, e.g. with
+    /// an API's own HMAC scheme; runs after `oauth1`
+    pub signer: Option<Arc<dyn RequestSigner>>,
+    /// OAuth 1.0a credentials signing requests made by `CuimpHttp`
+    #[cfg(feature = "oauth1")]
+    pub oauth1: Option<OAuth1>,
+}
+
+impl Default for CuimpOptions {
+    fn default() -> Self {
+        CuimpOptions {
+            descriptor: None,
+            path: None,
+            binaries_dir: None,
+            extra_curl_args: None,
+            proxy: None,
+            trust_env: true,
+            timeout: None,
+            deadline: None,
+            headers: None,
+            locale: None,
+            tls: None,
+            http2: None,
+            expect_continue: None,
+            low_speed: None,
+            max_rate: None,
+            geo_proxies: None,
+            domain_profiles: None,
+            body_transforms: None,
+            release_cache_ttl_secs: None,
+            refresh_release_cache: false,
+            force: false,
+            auto_download: true,
+            cassette: None,
+            retry: None,
+            curl_retry: None,
+            redirect: None,
+            strip_credentials_on_redirect: None,
+            forbid_https_downgrade: None,
+            reason_phrase: ReasonPhrase::default(),
+            cookie_store: false,
+            cookie_file: None,
+            redaction: None,
+            backend: Backend::default(),
+            header_presets: HeaderPresets::default(),
+            metrics: None,
+            on_event: None,
+            coalesce_requests: false,
+            allowed_url_schemes: None,
+            body_file_threshold: None,
+            challenge_detector: None,
+            rotation: None,
+            on_unauthorized: None,
+            signer: None,
+            #[cfg(feature = "oauth1")]
+            oauth1: None,
+        }
+    }
 }
 
 impl From<CuimpDescriptor> for CuimpOptions {
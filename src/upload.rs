@@ -0,0 +1,122 @@
+use crate::types::{CuimpRequestConfig, CuimpResponse, Method};
+use std::collections::HashMap;
+
+/// Entity metadata of a downloaded resource, for uploading it elsewhere unchanged
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityMetadata {
+    /// Full `Content-Type`, including parameters such as `charset`
+    pub content_type: Option<String>,
+    pub content_language: Option<String>,
+    pub content_disposition: Option<String>,
+    /// Validators of the source; they describe the source server's copy and are not
+    /// sent by [`EntityMetadata::apply`]
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl EntityMetadata {
+    /// `charset` parameter of the content type
+    pub fn charset(&self) -> Option<String> {
+        self.content_type
+            .as_deref()?
+            .split(';')
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+    }
+
+    /// Set `Content-Type`, `Content-Language` and `Content-Disposition` on a request,
+    /// leaving headers the request already sets untouched
+    pub fn apply(&self, config: &mut CuimpRequestConfig) {
+        let headers = config.headers.get_or_insert_with(HashMap::new);
+        let entries = [
+            ("Content-Type", &self.content_type),
+            ("Content-Language", &self.content_language),
+            ("Content-Disposition", &self.content_disposition),
+        ];
+        for (name, value) in entries {
+            if let Some(value) = value {
+                if !headers.keys().any(|k| k.eq_ignore_ascii_case(name)) {
+                    headers.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+impl<T> CuimpResponse<T> {
+    /// Content-type, charset and validator metadata of this response
+    pub fn entity_metadata(&self) -> EntityMetadata {
+        let header = |name: &str| self.header(name).map(str::to_string);
+        EntityMetadata {
+            content_type: header("content-type"),
+            content_language: header("content-language"),
+            content_disposition: header("content-disposition"),
+            etag: header("etag"),
+            last_modified: header("last-modified"),
+        }
+    }
+
+    /// Request that uploads this response's body byte-for-byte to `url` with the
+    /// original content type and charset
+    pub fn to_upload(&self, url: impl Into<String>, method: Method) -> CuimpRequestConfig {
+        let mut config = CuimpRequestConfig {
+            url: Some(url.into()),
+            method: Some(method),
+            body: Some(self.raw_body.clone()),
+            ..Default::default()
+        };
+        self.entity_metadata().apply(&mut config);
+        config
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::types::RequestInfo;
+
+    #[test]
+    fn test_to_upload_preserves_metadata() {
+        let response = CuimpResponse {
+            status: 200,
+            status_text: "OK".to_string(),
+            http_version: None,
+            headers: HashMap::from([
+                (
+                    "content-type".to_string(),
+                    "text/csv; charset=\"ISO-8859-1\"".to_string(),
+                ),
+                ("ETag".to_string(), "\"v1\"".to_string()),
+            ]),
+            cookies: Vec::new(),
+            data: (),
+            raw_body: vec![0xE9, 0x00, 0xFF],
+            request: RequestInfo {
+                url: url::Url::parse("https://example.com/a.csv").unwrap(),
+                method: "GET".to_string(),
+                headers: HashMap::new(),
+                command: String::new(),
+                unicode_host: None,
+            },
+            attempts: Vec::new(),
+            diagnostics: None,
+            debug_info: None,
+            connection: None,
+            sha256: None,
+            truncated: false,
+        };
+
+        let metadata = response.entity_metadata();
+        assert_eq!(metadata.charset().as_deref(), Some("ISO-8859-1"));
+        assert_eq!(metadata.etag.as_deref(), Some("\"v1\""));
+
+        let upload = response.to_upload("https://backup.example.com/a.csv", Method::PUT);
+        assert_eq!(upload.body.as_deref(), Some([0xE9, 0x00, 0xFF].as_slice()));
+        assert_eq!(
+            upload.headers.unwrap()["Content-Type"],
+            "text/csv; charset=\"ISO-8859-1\""
+        );
+    }
+}
@@ -1,40 +1,84 @@
-use crate::constants::{ARCHITECTURE_LIST, BROWSER_LIST, PLATFORM_LIST};
 use crate::error::{CuimpError, Result};
 use crate::types::CuimpDescriptor;
 
 pub fn validate_descriptor(descriptor: &CuimpDescriptor) -> Result<()> {
-    // Validate browser if provided
-    if let Some(browser) = &descriptor.browser {
-        if !BROWSER_LIST.contains(&browser.as_str()) {
-            return Err(CuimpError::UnsupportedBrowser(format!(
-                "{}. Supported browsers: {}",
-                browser,
-                BROWSER_LIST.join(", ")
+    // Validate pinned release if provided
+    if let Some(release) = &descriptor.release {
+        let number = release.trim_start_matches('v');
+        let valid = release == "latest"
+            || (number.starts_with(|c: char| c.is_ascii_digit())
+                && number
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-'));
+        if !valid {
+            return Err(CuimpError::InvalidDescriptor(format!(
+                "Invalid release {:?}. Expected a tag like v1.0.0 or \"latest\"",
+                release
             )));
         }
     }
 
-    // Validate architecture if provided
-    if let Some(architecture) = &descriptor.architecture {
-        if !ARCHITECTURE_LIST.contains(&architecture.as_str()) {
-            return Err(CuimpError::UnsupportedArchitecture(format!(
-                "{}. Supported architectures: {}",
-                architecture,
-                ARCHITECTURE_LIST.join(", ")
-            )));
-        }
-    }
+    Ok(())
+}
 
-    // Validate platform if provided
-    if let Some(platform) = &descriptor.platform {
-        if !PLATFORM_LIST.contains(&platform.as_str()) {
-            return Err(CuimpError::UnsupportedPlatform(format!(
-                "{}. Supported platforms: {}",
-                platform,
-                PLATFORM_LIST.join(", ")
-            )));
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::pinned_release;
+
+    #[test]
+    fn test_pinned_release() {
+        let descriptor = |version: Option<&str>, release: Option<&str>| CuimpDescriptor {
+            version: version.map(str::to_string),
+            release: release.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(pinned_release(&descriptor(Some("124"), None)), None);
+        assert_eq!(
+            pinned_release(&descriptor(Some("v1.0.0"), None)),
+            Some("v1.0.0")
+        );
+        assert_eq!(
+            pinned_release(&descriptor(Some("1.0.0"), Some("v0.9.5"))),
+            Some("v0.9.5")
+        );
+        assert_eq!(pinned_release(&descriptor(None, Some("latest"))), None);
+
+        assert!(validate_descriptor(&descriptor(None, Some("v1.0.0-beta.1"))).is_ok());
+        assert!(validate_descriptor(&descriptor(None, Some("../../evil"))).is_err());
     }
 
-    Ok(())
+    #[test]
+    fn test_descriptor_enums() {
+        use crate::types::{Arch, Browser, Platform};
+
+        assert_eq!("Firefox".parse::<Browser>().unwrap(), Browser::Firefox);
+        assert_eq!("aarch64".parse::<Arch>().unwrap(), Arch::Arm64);
+        assert!(matches!(
+            "netscape".parse::<Browser>(),
+            Err(CuimpError::UnsupportedBrowser(message)) if message.ends_with("chrome, firefox, edge, safari, tor, okhttp, curl")
+        ));
+
+        let descriptor: CuimpDescriptor =
+            serde_json::from_str(r#"{"browser":"okhttp","platform":"macos","architecture":"x64"}"#)
+                .unwrap();
+        assert_eq!(
+            (
+                descriptor.browser,
+                descriptor.platform,
+                descriptor.architecture
+            ),
+            (
+                Some(Browser::OkHttp),
+                Some(Platform::Macos),
+                Some(Arch::X64)
+            )
+        );
+        assert_eq!(
+            serde_json::to_value(&descriptor).unwrap()["browser"],
+            "okhttp"
+        );
+        assert!(serde_json::from_str::<CuimpDescriptor>(r#"{"platform":"beos"}"#).is_err());
+    }
 }